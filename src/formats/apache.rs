@@ -0,0 +1,129 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{FormatError, collapse_whitespace, ensure_newline, options};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ApacheOptions {
+    /// Spaces per `<Section>` nesting level.
+    pub indent: usize,
+}
+
+impl Default for ApacheOptions {
+    fn default() -> Self {
+        Self { indent: 4 }
+    }
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).apache)
+}
+
+/// Formatter for Apache httpd configuration (`httpd.conf`, `.htaccess`):
+/// indents `<Section>` … `</Section>` blocks, collapses whitespace between
+/// arguments outside quotes, keeps comments and single blank lines, and
+/// indents `\` continuation lines one extra level.
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &ApacheOptions,
+) -> Result<Option<String>, FormatError> {
+    let mut out = String::new();
+    let mut sections: Vec<String> = Vec::new();
+    let mut pending_blank = false;
+    let mut continuation = false;
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if continuation {
+            out.push_str(&" ".repeat((sections.len() + 1) * opts.indent));
+            out.push_str(&collapse_whitespace(line, &['"', '\'']));
+            out.push('\n');
+            continuation = line.ends_with('\\');
+            continue;
+        }
+        if line.is_empty() {
+            pending_blank = !out.is_empty();
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("</") {
+            let name = rest.trim_end_matches('>').trim();
+            match sections.pop() {
+                Some(open) if open.eq_ignore_ascii_case(name) => {}
+                Some(open) => {
+                    return Err(FormatError::Message(format!(
+                        "apache parse error on line {}: </{name}> closes <{open}>",
+                        idx + 1
+                    )));
+                }
+                None => {
+                    return Err(FormatError::Message(format!(
+                        "apache parse error on line {}: unexpected </{name}>",
+                        idx + 1
+                    )));
+                }
+            }
+            // No blank line directly before a closing tag.
+            pending_blank = false;
+            push_indented(&mut out, sections.len(), opts, &format!("</{name}>"));
+            continue;
+        }
+
+        if pending_blank && !last_was_open(&out) {
+            out.push('\n');
+        }
+        pending_blank = false;
+
+        if line.starts_with('#') {
+            push_indented(&mut out, sections.len(), opts, line);
+            continue;
+        }
+
+        if let Some(inner) = line.strip_prefix('<').and_then(|l| l.strip_suffix('>')) {
+            let inner = collapse_whitespace(inner, &['"', '\'']);
+            let name = inner
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            push_indented(&mut out, sections.len(), opts, &format!("<{inner}>"));
+            sections.push(name);
+            continue;
+        }
+
+        push_indented(
+            &mut out,
+            sections.len(),
+            opts,
+            &collapse_whitespace(line, &['"', '\'']),
+        );
+        continuation = line.ends_with('\\');
+    }
+
+    if let Some(open) = sections.last() {
+        return Err(FormatError::Message(format!(
+            "apache parse error: <{open}> is never closed"
+        )));
+    }
+
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+/// Whether the last printed line opened a section (no blank line after it).
+fn last_was_open(out: &str) -> bool {
+    out.lines()
+        .last()
+        .map(str::trim_start)
+        .is_some_and(|l| l.starts_with('<') && !l.starts_with("</"))
+}
+
+fn push_indented(out: &mut String, depth: usize, opts: &ApacheOptions, line: &str) {
+    out.push_str(&" ".repeat(depth * opts.indent));
+    out.push_str(line);
+    out.push('\n');
+}
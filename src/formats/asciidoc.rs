@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+use super::{FormatError, ensure_newline, markdown};
+
+/// AsciiDoc: one space after heading and list markers, blank lines around
+/// section titles, single blank lines, aligned `|===` tables and
+/// `[source,lang]` listings run through the matching formatter. Listing,
+/// literal, passthrough and comment blocks are otherwise copied as written.
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    let (out, warnings) = format_with_warnings(text);
+    for warning in warnings {
+        eprintln!("{}: warning: {warning}", path.display());
+    }
+    Ok(out)
+}
+
+pub fn format_with_warnings(text: &str) -> (Option<String>, Vec<String>) {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut lang: Option<&str> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.is_empty() {
+            lang = None;
+            i += 1;
+            continue;
+        }
+        if i > 0 && lines[i - 1].is_empty() && !out.is_empty() {
+            out.push(String::new());
+        }
+
+        if let Some(close) = verbatim_delimiter(line) {
+            let end = (i + 1..lines.len()).find(|&j| lines[j] == close);
+            let Some(end) = end else {
+                out.extend(lines[i..].iter().map(|l| l.to_string()));
+                break;
+            };
+            let fence_lang = line.strip_prefix("```").filter(|l| !l.is_empty());
+            let tag = fence_lang.or(lang.filter(|_| line.starts_with('-')));
+            out.push(line.to_string());
+            let body = &lines[i + 1..end];
+            let formatted = tag.and_then(|tag| {
+                let kind = markdown::code_kind(tag)?;
+                let code: String = body.iter().map(|l| format!("{l}\n")).collect();
+                markdown::format_code(kind, tag, &code, i + 2, &mut warnings)
+            });
+            match formatted {
+                Some(code) => out.extend(code.lines().map(str::to_string)),
+                None => out.extend(body.iter().map(|l| l.to_string())),
+            }
+            out.push(close.to_string());
+            lang = None;
+            i = end + 1;
+            continue;
+        }
+        if line == "|===" {
+            let end = (i + 1..lines.len())
+                .find(|&j| lines[j] == "|===")
+                .unwrap_or(lines.len());
+            out.push(line.to_string());
+            out.extend(table(&lines[i + 1..end]));
+            if end < lines.len() {
+                out.push(line.to_string());
+            }
+            i = end + 1;
+            continue;
+        }
+
+        if let Some(attributes) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            lang = source_lang(attributes).or(lang);
+        } else if !line.starts_with('.') || line.starts_with(". ") {
+            lang = None;
+        }
+        if let Some(heading) = heading(line) {
+            let section = !heading.starts_with("= ") && !heading.starts_with("# ");
+            if section
+                && out
+                    .last()
+                    .is_some_and(|l| !l.is_empty() && !is_block_meta(l))
+            {
+                out.push(String::new());
+            }
+            out.push(heading);
+            if section && lines.get(i + 1).is_some_and(|l| !l.is_empty()) {
+                out.push(String::new());
+            }
+        } else {
+            out.push(list_item(line).unwrap_or_else(|| line.to_string()));
+        }
+        i += 1;
+    }
+    let out = ensure_newline(out.join("\n"));
+    ((out != text).then_some(out), warnings)
+}
+
+/// The closing line of a block whose content is not AsciiDoc.
+fn verbatim_delimiter(line: &str) -> Option<&str> {
+    if line.starts_with("```") {
+        return Some("```");
+    }
+    let first = line.chars().next()?;
+    (matches!(first, '-' | '.' | '+' | '/') && line.len() >= 4 && line.chars().all(|c| c == first))
+        .then_some(line)
+}
+
+/// `java` from `[source,java]`, `[source, java, linenums]` or `[,java]`.
+fn source_lang(attributes: &str) -> Option<&str> {
+    let mut parts = attributes.split(',').map(str::trim);
+    let style = parts.next()?;
+    if !(style == "source" || style.is_empty()) {
+        return None;
+    }
+    parts
+        .next()
+        .filter(|lang| !lang.is_empty() && !lang.contains('='))
+}
+
+/// Lines that belong to the block below them: attributes, anchors, titles
+/// and comments.
+fn is_block_meta(line: &str) -> bool {
+    line.starts_with('[')
+        || line.starts_with("//")
+        || (line.starts_with('.') && !line.starts_with(". "))
+}
+
+fn heading(line: &str) -> Option<String> {
+    let marker_len = line.find(|c| c != '=' && c != '#')?;
+    let (marker, title) = line.split_at(marker_len);
+    if marker.is_empty() || marker_len > 6 || marker.contains('=') == marker.contains('#') {
+        return None;
+    }
+    let title = title.strip_prefix([' ', '\t'])?.trim();
+    (!title.is_empty()).then(|| format!("{marker} {title}"))
+}
+
+fn list_item(line: &str) -> Option<String> {
+    let marker_len = line.find(|c| !matches!(c, '*' | '.' | '-'))?;
+    let (marker, text) = line.split_at(marker_len);
+    let uniform = marker
+        .chars()
+        .all(|c| c == marker.chars().next().unwrap_or(' '));
+    if marker.is_empty() || !uniform || (marker.starts_with('-') && marker.len() > 1) {
+        return None;
+    }
+    let text = text.strip_prefix([' ', '\t'])?.trim_start();
+    (!text.is_empty()).then(|| format!("{marker} {text}"))
+}
+
+/// Pads the cells of a table whose rows are one line each with the same
+/// number of plain `|` cells; anything else, such as cell specifiers like
+/// `2+|` or `a|`, is left as written.
+fn table(lines: &[&str]) -> Vec<String> {
+    let rows: Option<Vec<Vec<String>>> = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.strip_prefix('|').map(split_cells))
+        .collect();
+    let rows = rows.filter(|rows| {
+        rows.first()
+            .is_some_and(|first| rows.iter().all(|r| r.len() == first.len()))
+    });
+    let Some(rows) = rows else {
+        return lines.iter().map(|l| l.to_string()).collect();
+    };
+    let mut widths = vec![0; rows.first().map_or(0, Vec::len)];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let mut rows = rows.into_iter();
+    lines
+        .iter()
+        .map(|line| {
+            if line.is_empty() {
+                return String::new();
+            }
+            let cells = rows.next().unwrap_or_default();
+            let mut out = String::new();
+            for (width, cell) in widths.iter().zip(&cells) {
+                out.push_str("| ");
+                out.push_str(cell);
+                out.push_str(&" ".repeat(width - cell.width() + 1));
+            }
+            out.trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Cells after the leading `|`, split on unescaped `|`.
+fn split_cells(row: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('\\');
+                cell.push(chars.next().unwrap_or('|'));
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            c => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
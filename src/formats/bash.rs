@@ -124,6 +124,30 @@ fn parse(text: &str) -> Result<Tree, FormatError> {
         .ok_or_else(|| FormatError::Message("failed to parse shell script".to_string()))
 }
 
+/// Line of the first syntax error in `text`, if bash cannot parse it.
+pub(super) fn syntax_error_line(text: &str) -> Option<usize> {
+    let tree = parse(text).ok()?;
+    let mut cursor = tree.walk();
+    if !cursor.node().has_error() {
+        return None;
+    }
+    loop {
+        let node = cursor.node();
+        if node.is_error() || node.is_missing() {
+            return Some(node.start_position().row + 1);
+        }
+        // Descend towards the error, else move on to the next sibling.
+        let moved = if node.has_error() {
+            cursor.goto_first_child()
+        } else {
+            cursor.goto_next_sibling()
+        };
+        if !moved {
+            return Some(node.start_position().row + 1);
+        }
+    }
+}
+
 fn starts_with_any(line: &str, prefixes: &[&str]) -> bool {
     prefixes.iter().any(|p| line.trim_start().starts_with(p))
 }
@@ -321,12 +345,13 @@ fn split_comment(line: &str) -> (&str, Option<&str>) {
         match ch {
             '\'' if !in_double => in_single = !in_single,
             '"' if !in_single => in_double = !in_double,
-            '#' if !in_single && !in_double => {
-                if idx == 0 || line.as_bytes()[idx - 1].is_ascii_whitespace() {
-                    let code = line[..idx].trim_end();
-                    let comment = &line[idx..];
-                    return (code, Some(comment));
-                }
+            '#' if !in_single
+                && !in_double
+                && (idx == 0 || line.as_bytes()[idx - 1].is_ascii_whitespace()) =>
+            {
+                let code = line[..idx].trim_end();
+                let comment = &line[idx..];
+                return (code, Some(comment));
             }
             _ => {}
         }
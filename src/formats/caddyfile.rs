@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{FormatError, collapse_whitespace, ensure_newline, options};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CaddyfileOptions {
+    /// Indent with tabs like `caddy fmt`; otherwise use `indent` spaces.
+    pub use_tabs: bool,
+    pub indent: usize,
+}
+
+impl Default for CaddyfileOptions {
+    fn default() -> Self {
+        Self {
+            use_tabs: true,
+            indent: 4,
+        }
+    }
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).caddyfile)
+}
+
+/// Formatter for Caddyfiles in the style of `caddy fmt`: site blocks,
+/// snippets, named matchers and nested directive blocks are indented, the
+/// opening `{` is separated by one space, whitespace outside quotes and
+/// backticks is collapsed and heredoc bodies are copied verbatim.
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &CaddyfileOptions,
+) -> Result<Option<String>, FormatError> {
+    let unit = if opts.use_tabs {
+        "\t".to_string()
+    } else {
+        " ".repeat(opts.indent)
+    };
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut pending_blank = false;
+    let mut after_open = true;
+    let mut lines = text.lines().enumerate();
+
+    while let Some((idx, raw)) = lines.next() {
+        let line = raw.trim();
+        if line.is_empty() {
+            pending_blank = !after_open;
+            continue;
+        }
+
+        let (code, comment) = split_comment(line);
+        let comment = comment.map(|c| format!(" {c}")).unwrap_or_default();
+
+        if code == "}" {
+            if depth == 0 {
+                return Err(FormatError::Message(format!(
+                    "caddyfile parse error on line {}: unexpected `}}`",
+                    idx + 1
+                )));
+            }
+            depth -= 1;
+            pending_blank = false;
+            push_line(&mut out, &unit.repeat(depth), &format!("}}{comment}"));
+            after_open = false;
+            continue;
+        }
+
+        if pending_blank {
+            out.push('\n');
+            pending_blank = false;
+        }
+
+        if line.starts_with('#') {
+            push_line(&mut out, &unit.repeat(depth), line);
+            after_open = false;
+            continue;
+        }
+
+        let normalized = collapse_whitespace(code, &['"', '`']);
+        if let Some(head) = normalized.strip_suffix('{') {
+            let head = head.trim_end();
+            let opened = if head.is_empty() {
+                "{".to_string()
+            } else {
+                format!("{head} {{")
+            };
+            push_line(&mut out, &unit.repeat(depth), &format!("{opened}{comment}"));
+            depth += 1;
+            after_open = true;
+            continue;
+        }
+
+        push_line(
+            &mut out,
+            &unit.repeat(depth),
+            &format!("{normalized}{comment}"),
+        );
+        after_open = false;
+
+        if let Some(marker) = heredoc_marker(&normalized) {
+            for (_, body) in lines.by_ref() {
+                out.push_str(body);
+                out.push('\n');
+                // The closing marker may be followed by more arguments (`HTML 200`).
+                if body.split_whitespace().next() == Some(marker) {
+                    break;
+                }
+            }
+        }
+    }
+
+    if depth > 0 {
+        return Err(FormatError::Message(
+            "caddyfile parse error: missing closing `}`".to_string(),
+        ));
+    }
+
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+/// Splits a trailing `# comment` (a `#` starting a token outside quotes and
+/// backticks) off `line`.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut prev = ' ';
+    for (at, c) in line.char_indices() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if c == '"' || c == '`' => quote = Some(c),
+            None if c == '#' && prev.is_whitespace() && at > 0 => {
+                return (line[..at].trim_end(), Some(&line[at..]));
+            }
+            None => {}
+        }
+        prev = c;
+    }
+    (line, None)
+}
+
+/// The end marker of a heredoc opened at the end of `line` (`respond <<HTML`).
+fn heredoc_marker(line: &str) -> Option<&str> {
+    let last = line.split_whitespace().last()?;
+    let marker = last.strip_prefix("<<")?;
+    (!marker.is_empty()
+        && marker
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    .then_some(marker)
+}
+
+fn push_line(out: &mut String, indent: &str, line: &str) {
+    out.push_str(indent);
+    out.push_str(line);
+    out.push('\n');
+}
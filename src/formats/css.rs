@@ -2,16 +2,94 @@ use std::path::Path;
 
 use anyhow::Result;
 use dprint_plugin_css as css;
-use once_cell::sync::Lazy;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::scss::{self, Syntax};
+use super::{FormatError, ensure_newline, options};
 
-static CONF: Lazy<css::configuration::Configuration> =
-    Lazy::new(|| css::configuration::ConfigurationBuilder::new().build());
+/// Shared by CSS, SCSS, Sass and Less.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CssOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    pub sort_properties: SortProperties,
+    /// Case of hex colors such as `#FFF`.
+    pub color_case: ColorCase,
+    pub quotes: Quotes,
+}
+
+impl Default for CssOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            sort_properties: SortProperties::default(),
+            color_case: ColorCase::default(),
+            quotes: Quotes::default(),
+        }
+    }
+}
+
+impl CssOptions {
+    /// Whether formatted CSS still needs the rewriting pass.
+    fn rewrites(&self) -> bool {
+        self.sort_properties != SortProperties::None
+            || self.color_case != ColorCase::Keep
+            || self.quotes != Quotes::Keep
+    }
+}
+
+/// Order of declarations within a run not broken by blank lines, comments,
+/// variables, nested rules or `@include`s.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortProperties {
+    #[default]
+    None,
+    Alphabetical,
+    /// Positioning, box model, typography, visual, then animation, like
+    /// stylelint-config-recess-order.
+    Grouped,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorCase {
+    #[default]
+    Keep,
+    Lower,
+    Upper,
+}
+
+/// Quote character of strings; strings containing it keep theirs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quotes {
+    #[default]
+    Keep,
+    Double,
+    Single,
+}
 
 pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let formatted =
-        css::format_text(path, text, &CONF).map_err(|e| FormatError::Message(e.to_string()))?;
+    format_with_options(path, text, &options::for_path(path).css)
+}
+
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &CssOptions,
+) -> Result<Option<String>, FormatError> {
+    let config = css::configuration::ConfigurationBuilder::new()
+        .indent_width(opts.indent.min(u8::MAX.into()) as u8)
+        .build();
+    let mut formatted =
+        css::format_text(path, text, &config).map_err(|e| FormatError::Message(e.to_string()))?;
+    if opts.rewrites() {
+        if let Some(out) = scss::format_with_options(&formatted, Syntax::Css, opts)? {
+            formatted = out;
+        }
+    }
     if formatted == text {
         Ok(None)
     } else {
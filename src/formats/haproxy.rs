@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{FormatError, collapse_whitespace, ensure_newline, options};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HaproxyOptions {
+    /// Spaces before keywords inside a section.
+    pub indent: usize,
+}
+
+impl Default for HaproxyOptions {
+    fn default() -> Self {
+        Self { indent: 4 }
+    }
+}
+
+/// Keywords that start a new section in haproxy.cfg.
+const SECTIONS: &[&str] = &[
+    "global",
+    "defaults",
+    "frontend",
+    "backend",
+    "listen",
+    "userlist",
+    "peers",
+    "resolvers",
+    "mailers",
+    "program",
+    "http-errors",
+    "ring",
+    "cache",
+    "log-forward",
+    "fcgi-app",
+    "crt-store",
+    "traces",
+];
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).haproxy)
+}
+
+pub fn is_section_header(line: &str) -> bool {
+    line.split_whitespace()
+        .next()
+        .is_some_and(|kw| SECTIONS.contains(&kw))
+}
+
+/// Formatter for HAProxy configuration: section headers at column 0, every
+/// keyword inside a section indented, whitespace between arguments collapsed
+/// outside quotes. Sections are separated by a blank line; comments directly
+/// above a section header stay with the header, other comments follow the
+/// surrounding indentation.
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &HaproxyOptions,
+) -> Result<Option<String>, FormatError> {
+    let pad = " ".repeat(opts.indent);
+    let mut out = String::new();
+    let mut in_section = false;
+    let mut pending_blank = false;
+    let mut comments: Vec<&str> = Vec::new();
+
+    for raw in text.lines() {
+        let line = raw.trim();
+        if line.is_empty() {
+            if !comments.is_empty() {
+                // A blank line detaches buffered comments from the next line.
+                flush_blank(&mut out, &mut pending_blank);
+                for c in comments.drain(..) {
+                    push_line(&mut out, if in_section { &pad } else { "" }, c);
+                }
+            }
+            pending_blank = true;
+            continue;
+        }
+        if line.starts_with('#') {
+            comments.push(line);
+            continue;
+        }
+
+        let header = is_section_header(line);
+        if header {
+            // Separate sections; comments leading into a header sit below the gap.
+            pending_blank = true;
+            flush_blank(&mut out, &mut pending_blank);
+            in_section = true;
+        } else {
+            flush_blank(&mut out, &mut pending_blank);
+        }
+        let indent = if header || !in_section {
+            ""
+        } else {
+            pad.as_str()
+        };
+        for c in comments.drain(..) {
+            push_line(&mut out, indent, c);
+        }
+        push_line(&mut out, indent, &collapse_whitespace(line, &['"', '\'']));
+    }
+    for c in comments.drain(..) {
+        push_line(&mut out, if in_section { &pad } else { "" }, c);
+    }
+
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+fn flush_blank(out: &mut String, pending: &mut bool) {
+    if *pending && !out.is_empty() {
+        out.push('\n');
+    }
+    *pending = false;
+}
+
+fn push_line(out: &mut String, indent: &str, line: &str) {
+    out.push_str(indent);
+    out.push_str(line);
+    out.push('\n');
+}
@@ -4,14 +4,442 @@ use anyhow::Result;
 
 use super::{FormatError, ensure_newline};
 
+/// HCL formatter following `terraform fmt` (hclwrite) rules:
+/// - line structure is kept; each line is re-indented two spaces per open bracket level
+/// - tokens are re-spaced (`a = b`, `f(x)`, `[1, 2]`, `{ a = 1 }`, `-1`, `a.b[0]`)
+/// - `=` of consecutive single-line attributes and trailing comments are aligned
+/// - comments, heredoc bodies and quoted templates are copied verbatim
 pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let body: hcl::Body =
-        hcl::from_str(text).map_err(|e| FormatError::Message(format!("hcl parse error: {e}")))?;
-    let out = hcl::format::to_string(&body)
-        .map_err(|e| FormatError::Message(format!("hcl format error: {e}")))?;
-    if out == text {
-        Ok(None)
-    } else {
-        Ok(Some(ensure_newline(out)))
+    hcl::parse(text).map_err(|e| FormatError::Message(format!("hcl parse error: {e}")))?;
+
+    let lines = tokenize(text)?;
+    let mut rendered = render(&lines);
+    while rendered.last().is_some_and(|l| l.is_empty()) {
+        rendered.pop();
+    }
+    let out = ensure_newline(rendered.join("\n"));
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Number,
+    /// A whole quoted string, template interpolations included.
+    Str,
+    /// `<<EOT` / `<<-EOT`; the body follows as raw lines.
+    Heredoc,
+    Comment,
+    Punct,
+}
+
+#[derive(Debug, Clone)]
+struct Tok {
+    kind: Kind,
+    text: String,
+}
+
+impl Tok {
+    fn is(&self, punct: &str) -> bool {
+        self.kind == Kind::Punct && self.text == punct
+    }
+
+    fn bracket_change(&self) -> i32 {
+        match (self.kind, self.text.as_str()) {
+            (Kind::Punct, "(" | "[" | "{") => 1,
+            (Kind::Punct, ")" | "]" | "}") => -1,
+            _ => 0,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.text.chars().count()
+    }
+}
+
+#[derive(Debug)]
+enum Line {
+    Tokens(Vec<Tok>),
+    /// Heredoc body or terminator, printed untouched.
+    Raw(String),
+}
+
+const PUNCTS: &[&str] = &[
+    "...", "==", "!=", "<=", ">=", "&&", "||", "=>", "::", "=", "+", "-", "*", "/", "%", "<", ">",
+    "!", "?", ":", ",", ".", "(", ")", "[", "]", "{", "}",
+];
+
+fn tokenize(text: &str) -> Result<Vec<Line>, FormatError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut lines = Vec::new();
+    let mut current: Vec<Tok> = Vec::new();
+    let mut heredocs: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\n' => {
+                lines.push(Line::Tokens(std::mem::take(&mut current)));
+                i += 1;
+                // Heredoc bodies start on the line after their opener.
+                for marker in heredocs.drain(..) {
+                    loop {
+                        if i >= chars.len() {
+                            return Err(FormatError::Message(format!(
+                                "hcl parse error: unterminated heredoc `{marker}`"
+                            )));
+                        }
+                        let end = chars[i..]
+                            .iter()
+                            .position(|&c| c == '\n')
+                            .map_or(chars.len(), |p| i + p);
+                        let raw: String = chars[i..end].iter().collect();
+                        i = (end + 1).min(chars.len());
+                        let done = raw.trim() == marker;
+                        lines.push(Line::Raw(raw.trim_end_matches('\r').to_string()));
+                        if done {
+                            break;
+                        }
+                    }
+                }
+            }
+            c if c.is_whitespace() => i += 1,
+            '#' => i = push_line_comment(&chars, i, &mut current),
+            '/' if chars.get(i + 1) == Some(&'/') => i = push_line_comment(&chars, i, &mut current),
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let start = i;
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                current.push(Tok {
+                    kind: Kind::Comment,
+                    text: chars[start..i].iter().collect(),
+                });
+            }
+            '"' => {
+                let end = scan_string(&chars, i);
+                current.push(Tok {
+                    kind: Kind::Str,
+                    text: chars[i..end].iter().collect(),
+                });
+                i = end;
+            }
+            '<' if chars.get(i + 1) == Some(&'<') => {
+                let start = i;
+                i += 2;
+                if chars.get(i) == Some(&'-') {
+                    i += 1;
+                }
+                let name_start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                heredocs.push(chars[name_start..i].iter().collect());
+                current.push(Tok {
+                    kind: Kind::Heredoc,
+                    text: chars[start..i].iter().collect(),
+                });
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                current.push(Tok {
+                    kind: Kind::Ident,
+                    text: chars[start..i].iter().collect(),
+                });
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+                {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                if matches!(chars.get(i), Some('e' | 'E')) {
+                    let mut j = i + 1;
+                    if matches!(chars.get(j), Some('+' | '-')) {
+                        j += 1;
+                    }
+                    if chars.get(j).is_some_and(char::is_ascii_digit) {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                current.push(Tok {
+                    kind: Kind::Number,
+                    text: chars[start..i].iter().collect(),
+                });
+            }
+            _ => {
+                let rest: String = chars[i..(i + 3).min(chars.len())].iter().collect();
+                let punct = PUNCTS
+                    .iter()
+                    .find(|p| rest.starts_with(**p))
+                    .ok_or_else(|| {
+                        FormatError::Message(format!("hcl parse error: unexpected `{c}`"))
+                    })?;
+                current.push(Tok {
+                    kind: Kind::Punct,
+                    text: punct.to_string(),
+                });
+                i += punct.chars().count();
+            }
+        }
+    }
+    lines.push(Line::Tokens(current));
+    Ok(lines)
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn push_line_comment(chars: &[char], start: usize, current: &mut Vec<Tok>) -> usize {
+    let end = chars[start..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(chars.len(), |p| start + p);
+    let text: String = chars[start..end].iter().collect();
+    current.push(Tok {
+        kind: Kind::Comment,
+        text: text.trim_end().to_string(),
+    });
+    end
+}
+
+/// End index (exclusive) of the quoted string starting at `start`, skipping
+/// over `${…}` / `%{…}` sequences that may contain nested strings.
+fn scan_string(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            '$' | '%' if chars.get(i + 1) == Some(&'{') => {
+                i += 2;
+                let mut depth = 1;
+                while i < chars.len() && depth > 0 {
+                    match chars[i] {
+                        '"' => {
+                            i = scan_string(chars, i);
+                            continue;
+                        }
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// One formatted line split into hclwrite's cells.
+struct Cells {
+    indent: usize,
+    tokens: Vec<Tok>,
+    /// Spaces before each token (the first token's entry is ignored).
+    spaces: Vec<usize>,
+    /// Index of the `=` that starts the assignment cell.
+    assign: Option<usize>,
+    /// Whether the last token is a trailing comment.
+    comment: bool,
+}
+
+impl Cells {
+    fn columns(&self, range: std::ops::Range<usize>) -> usize {
+        range
+            .map(|i| {
+                let spaces = if i == 0 { self.indent } else { self.spaces[i] };
+                spaces + self.tokens[i].width()
+            })
+            .sum()
+    }
+
+    fn lead_end(&self) -> usize {
+        self.assign.unwrap_or(self.body_end())
+    }
+
+    fn body_end(&self) -> usize {
+        self.tokens.len() - usize::from(self.comment)
+    }
+}
+
+fn render(lines: &[Line]) -> Vec<String> {
+    let mut indents: Vec<i32> = Vec::new();
+    let mut cells: Vec<Option<Cells>> = Vec::new();
+
+    for line in lines {
+        let tokens = match line {
+            Line::Tokens(tokens) if !tokens.is_empty() => tokens,
+            _ => {
+                cells.push(None);
+                continue;
+            }
+        };
+        let net: i32 = tokens.iter().map(Tok::bracket_change).sum();
+        let indent = if net > 0 {
+            indents.push(net);
+            indents.len() - 1
+        } else {
+            let mut closed = -net;
+            while closed > 0 {
+                let Some(top) = indents.last_mut() else { break };
+                if closed < *top {
+                    *top -= closed;
+                    closed = 0;
+                } else {
+                    closed -= *top;
+                    indents.pop();
+                }
+            }
+            indents.len()
+        };
+
+        let spaces = (0..tokens.len())
+            .map(|i| match i {
+                0 => 0,
+                _ => {
+                    let before = i.checked_sub(2).map(|b| &tokens[b]);
+                    usize::from(space_after(&tokens[i - 1], before, &tokens[i]))
+                }
+            })
+            .collect();
+        let comment = tokens.len() > 1 && tokens[tokens.len() - 1].kind == Kind::Comment;
+        let body_end = tokens.len() - usize::from(comment);
+        // Only whole single-line expressions join an alignment group.
+        let assign = tokens[..body_end]
+            .iter()
+            .position(|t| t.is("="))
+            .filter(|&i| i > 0)
+            .filter(|&i| {
+                tokens[i..body_end]
+                    .iter()
+                    .map(Tok::bracket_change)
+                    .sum::<i32>()
+                    == 0
+            });
+        cells.push(Some(Cells {
+            indent: indent * 2,
+            tokens: tokens.clone(),
+            spaces,
+            assign,
+            comment,
+        }));
+    }
+
+    align(
+        &mut cells,
+        |c| c.assign.is_some(),
+        |c| c.columns(0..c.lead_end()),
+        |c, n| {
+            let idx = c.assign.expect("in assign chain");
+            c.spaces[idx] = n;
+        },
+    );
+    align(
+        &mut cells,
+        |c| c.comment,
+        |c| c.columns(0..c.body_end()),
+        |c, n| {
+            let idx = c.tokens.len() - 1;
+            c.spaces[idx] = n;
+        },
+    );
+
+    lines
+        .iter()
+        .zip(cells)
+        .map(|(line, cells)| match (line, cells) {
+            (Line::Raw(raw), _) => raw.clone(),
+            (_, None) => String::new(),
+            (_, Some(c)) => {
+                let mut out = " ".repeat(c.indent);
+                for (i, tok) in c.tokens.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(&" ".repeat(c.spaces[i]));
+                    }
+                    out.push_str(&tok.text);
+                }
+                out
+            }
+        })
+        .collect()
+}
+
+/// Pad one cell of consecutive lines so it starts at a shared column; any
+/// line without the cell ends the group.
+fn align(
+    cells: &mut [Option<Cells>],
+    has_cell: impl Fn(&Cells) -> bool,
+    columns: impl Fn(&Cells) -> usize,
+    set_spaces: impl Fn(&mut Cells, usize),
+) {
+    let mut start = 0;
+    while start < cells.len() {
+        let in_group = |c: &Option<Cells>| c.as_ref().is_some_and(&has_cell);
+        if !in_group(&cells[start]) {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < cells.len() && in_group(&cells[end]) {
+            end += 1;
+        }
+        let max = cells[start..end]
+            .iter()
+            .flatten()
+            .map(&columns)
+            .max()
+            .unwrap_or(0);
+        for c in cells[start..end].iter_mut().flatten() {
+            let n = max - columns(c) + 1;
+            set_spaces(c, n);
+        }
+        start = end;
+    }
+}
+
+/// hclwrite's `spaceAfterToken`: whether a space separates `subject` from
+/// the following token.
+fn space_after(subject: &Tok, before: Option<&Tok>, after: &Tok) -> bool {
+    let ident_like = |t: &Tok| matches!(t.kind, Kind::Ident | Kind::Number);
+    match () {
+        _ if subject.kind == Kind::Ident && after.is("(") => false,
+        _ if (subject.kind == Kind::Ident && after.is("::"))
+            || (subject.is("::") && after.kind == Kind::Ident) =>
+        {
+            false
+        }
+        _ if subject.is(".") || after.is(".") => false,
+        _ if after.is(",") || after.is("...") => false,
+        _ if subject.is(",") => true,
+        _ if after.is("[") && (ident_like(subject) || subject.bracket_change() < 0) => false,
+        _ if subject.is("-") => match before {
+            None => false,
+            Some(b) if b.kind == Kind::Punct => matches!(b.text.as_str(), ")" | "]" | "}"),
+            Some(_) => true,
+        },
+        _ if subject.is("!") => false,
+        _ if subject.is("{") && after.is("}") => false,
+        _ if subject.is("{") || after.is("}") => true,
+        _ if subject.bracket_change() > 0 => false,
+        _ if after.bracket_change() < 0 => false,
+        _ => true,
     }
 }
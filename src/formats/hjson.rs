@@ -0,0 +1,442 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{FormatError, ensure_newline};
+
+const INDENT: &str = "  ";
+
+/// Hjson formatter: one `key: value` per line without commas, two-space
+/// indentation, comments and quoteless strings kept as written, `'''`
+/// strings re-indented under their key. Containers written on one line stay
+/// on one line when nothing in them needs a line of its own.
+pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let doc = Parser { text, pos: 0 }.document()?;
+    let mut out = String::with_capacity(text.len());
+    match &doc {
+        Document::Braceless(members) => print_members(&mut out, members, 0),
+        Document::Value { member, tail } => {
+            print_member(&mut out, member, 0, true);
+            print_gap(&mut out, tail, 0, false, true);
+        }
+    }
+    let out = ensure_newline(out);
+    Ok((out != text).then_some(out))
+}
+
+enum Document {
+    /// A root object written without braces.
+    Braceless(Container),
+    Value {
+        member: Member,
+        tail: Vec<String>,
+    },
+}
+
+/// Comments before a member; an empty string marks a blank line.
+struct Member {
+    gap: Vec<String>,
+    key: Option<String>,
+    value: Value,
+    trailing: Option<String>,
+}
+
+enum Value {
+    /// Literal or quoted string, as written.
+    Scalar(String),
+    /// Runs to the end of the line, so it never shares one.
+    Quoteless(String),
+    /// Content of a `'''` string, one entry per line.
+    Multiline(Vec<String>),
+    Object(Container),
+    Array(Container),
+}
+
+struct Container {
+    members: Vec<Member>,
+    dangling: Vec<String>,
+    inline: bool,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn error(&self, msg: &str) -> FormatError {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        FormatError::Message(format!("line {line}: {msg}"))
+    }
+
+    fn document(mut self) -> Result<Document, FormatError> {
+        let gap = self.gap();
+        if !matches!(self.peek(), Some('{' | '[')) {
+            let start = self.pos;
+            let braceless = self.key().is_ok() && {
+                self.skip_spaces();
+                self.peek() == Some(':')
+            };
+            if braceless {
+                self.pos = 0;
+                return Ok(Document::Braceless(self.container(None, true)?));
+            }
+            self.pos = start;
+        }
+        let value = self.value()?;
+        let trailing = self.trailing();
+        let tail = self.gap();
+        if self.pos < self.text.len() {
+            return Err(self.error("unexpected content after the root value"));
+        }
+        let member = Member {
+            gap,
+            key: None,
+            value,
+            trailing,
+        };
+        Ok(Document::Value { member, tail })
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    /// Whitespace, stray commas and comments between members.
+    fn gap(&mut self) -> Vec<String> {
+        let mut gap = Vec::new();
+        loop {
+            let rest = self.rest();
+            let ws = rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n', ',']).len();
+            if rest[..ws].matches('\n').count() >= 2
+                && gap.last().is_none_or(|g: &String| !g.is_empty())
+            {
+                gap.push(String::new());
+            }
+            self.pos += ws;
+            match self.comment() {
+                Some(comment) => gap.push(comment),
+                None => return gap,
+            }
+        }
+    }
+
+    fn comment(&mut self) -> Option<String> {
+        let rest = self.rest();
+        let len = if rest.starts_with('#') || rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            body.find("*/").map_or(rest.len(), |e| e + 4)
+        } else {
+            return None;
+        };
+        self.pos += len;
+        Some(rest[..len].trim_end().to_string())
+    }
+
+    /// An optional comma and comment on the rest of the current line.
+    fn trailing(&mut self) -> Option<String> {
+        self.skip_spaces();
+        if self.peek() == Some(',') {
+            self.pos += 1;
+            self.skip_spaces();
+        }
+        self.comment()
+    }
+
+    fn key(&mut self) -> Result<String, FormatError> {
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            return self.quoted(quote);
+        }
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c == ':' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..len];
+        if key.is_empty() || key.contains(['{', '}', '[', ']', ',']) {
+            return Err(self.error("expected a key"));
+        }
+        self.pos += len;
+        Ok(key.to_string())
+    }
+
+    fn value(&mut self) -> Result<Value, FormatError> {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+        match self.peek() {
+            Some('{') => Ok(Value::Object(self.container(Some('}'), true)?)),
+            Some('[') => Ok(Value::Array(self.container(Some(']'), false)?)),
+            Some('\'') if self.rest().starts_with("'''") => self.multiline(),
+            Some(quote @ ('"' | '\'')) => Ok(Value::Scalar(self.quoted(quote)?)),
+            Some(_) => self.literal_or_quoteless(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn container(&mut self, close: Option<char>, keyed: bool) -> Result<Container, FormatError> {
+        let start = self.pos;
+        if close.is_some() {
+            self.pos += 1;
+        }
+        let mut members = Vec::new();
+        let dangling = loop {
+            let gap = self.gap();
+            match (self.peek(), close) {
+                (None, None) => break gap,
+                (None, Some(close)) => return Err(self.error(&format!("expected `{close}`"))),
+                (Some(c), Some(close)) if c == close => {
+                    self.pos += 1;
+                    break gap;
+                }
+                _ => {}
+            }
+            let key = if keyed {
+                let key = self.key()?;
+                self.skip_spaces();
+                if self.peek() != Some(':') {
+                    return Err(self.error("expected `:` after the key"));
+                }
+                self.pos += 1;
+                Some(key)
+            } else {
+                None
+            };
+            let value = self.value()?;
+            let trailing = self.trailing();
+            members.push(Member {
+                gap,
+                key,
+                value,
+                trailing,
+            });
+        };
+        Ok(Container {
+            members,
+            dangling,
+            inline: !self.text[start..self.pos].contains('\n'),
+        })
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, FormatError> {
+        let rest = self.rest();
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            match c {
+                '\n' => break,
+                '\\' if !escaped => escaped = true,
+                c if c == quote && !escaped => {
+                    self.pos += i + 1;
+                    return Ok(rest[..=i].to_string());
+                }
+                _ => escaped = false,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// `'''` strings drop the whitespace up to the column of the opening
+    /// quotes from every line, and the line break before the closing ones.
+    fn multiline(&mut self) -> Result<Value, FormatError> {
+        let line_start = self.text[..self.pos].rfind('\n').map_or(0, |i| i + 1);
+        let column = self.text[line_start..self.pos].chars().count();
+        self.pos += 3;
+        let rest = self.rest();
+        let Some(end) = rest.find("'''") else {
+            return Err(self.error("unterminated multiline string"));
+        };
+        self.pos += end + 3;
+        let mut raw = &rest[..end];
+        if let Some(nl) = raw.find('\n').filter(|&nl| raw[..nl].trim().is_empty()) {
+            raw = &raw[nl + 1..];
+        }
+        let mut lines: Vec<String> = raw
+            .split('\n')
+            .map(|line| {
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                let skip = line
+                    .chars()
+                    .take(column)
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .count();
+                line[skip..].to_string()
+            })
+            .collect();
+        if lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        Ok(Value::Multiline(lines))
+    }
+
+    /// `true`, `false`, `null` and numbers may be followed by a comma, a
+    /// bracket or a comment; anything else is a quoteless string that runs
+    /// to the end of the line.
+    fn literal_or_quoteless(&mut self) -> Result<Value, FormatError> {
+        let rest = self.rest();
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let len = line
+            .find(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}' | '#'))
+            .unwrap_or(line.len());
+        let (token, after) = line.split_at(len);
+        let after = after.trim_start();
+        let ends = after.is_empty()
+            || after.starts_with([',', ']', '}', '#'])
+            || after.starts_with("//")
+            || after.starts_with("/*");
+        if ends && is_literal(token) {
+            self.pos += len;
+            return Ok(Value::Scalar(token.to_string()));
+        }
+        if line.starts_with([',', ':', ']', '}']) {
+            return Err(self.error("expected a value"));
+        }
+        self.pos += line.len();
+        Ok(Value::Quoteless(line.trim_end().to_string()))
+    }
+}
+
+fn is_literal(token: &str) -> bool {
+    if matches!(token, "true" | "false" | "null") {
+        return true;
+    }
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        && token.parse::<f64>().is_ok()
+}
+
+fn indent(out: &mut String, level: usize) {
+    for _ in 0..level {
+        out.push_str(INDENT);
+    }
+}
+
+/// Comments on their own lines; blank lines are kept singly, but not at the
+/// start or end of a container.
+fn print_gap(out: &mut String, gap: &[String], level: usize, first: bool, last: bool) {
+    let start = if first {
+        gap.iter().position(|g| !g.is_empty()).unwrap_or(gap.len())
+    } else {
+        0
+    };
+    let end = if last {
+        gap.iter().rposition(|g| !g.is_empty()).map_or(0, |i| i + 1)
+    } else {
+        gap.len()
+    };
+    for entry in gap.get(start..end).unwrap_or_default() {
+        if !entry.is_empty() {
+            indent(out, level);
+            out.push_str(entry);
+        }
+        out.push('\n');
+    }
+}
+
+fn print_members(out: &mut String, container: &Container, level: usize) {
+    for (i, member) in container.members.iter().enumerate() {
+        print_member(out, member, level, i == 0);
+    }
+    print_gap(
+        out,
+        &container.dangling,
+        level,
+        container.members.is_empty(),
+        true,
+    );
+}
+
+fn print_member(out: &mut String, member: &Member, level: usize, first: bool) {
+    print_gap(out, &member.gap, level, first, false);
+    indent(out, level);
+    if let Some(key) = &member.key {
+        out.push_str(key);
+        out.push(':');
+        if let Value::Multiline(_) = member.value {
+            out.push('\n');
+            indent(out, level + 1);
+            print_value(out, &member.value, level + 1);
+        } else {
+            out.push(' ');
+            print_value(out, &member.value, level);
+        }
+    } else {
+        print_value(out, &member.value, level);
+    }
+    if let Some(comment) = &member.trailing {
+        out.push(' ');
+        out.push_str(comment);
+    }
+    out.push('\n');
+}
+
+/// Prints a value whose first line is already indented to `level`.
+fn print_value(out: &mut String, value: &Value, level: usize) {
+    match value {
+        Value::Scalar(s) | Value::Quoteless(s) => out.push_str(s),
+        Value::Multiline(lines) => {
+            out.push_str("'''");
+            for line in lines {
+                out.push('\n');
+                if !line.is_empty() {
+                    indent(out, level);
+                    out.push_str(line);
+                }
+            }
+            out.push('\n');
+            indent(out, level);
+            out.push_str("'''");
+        }
+        Value::Object(c) | Value::Array(c) => {
+            let (open, close) = match value {
+                Value::Object(_) => ('{', '}'),
+                _ => ('[', ']'),
+            };
+            out.push(open);
+            if fits_inline(c) {
+                for (i, member) in c.members.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    if let Some(key) = &member.key {
+                        out.push_str(key);
+                        out.push_str(": ");
+                    }
+                    print_value(out, &member.value, level);
+                }
+            } else {
+                out.push('\n');
+                print_members(out, c, level + 1);
+                indent(out, level);
+            }
+            out.push(close);
+        }
+    }
+}
+
+fn fits_inline(c: &Container) -> bool {
+    c.inline
+        && c.dangling.is_empty()
+        && c.members.iter().all(|m| {
+            m.gap.is_empty()
+                && m.trailing.is_none()
+                && match &m.value {
+                    Value::Scalar(_) => true,
+                    Value::Object(c) | Value::Array(c) => fits_inline(c),
+                    Value::Quoteless(_) | Value::Multiline(_) => false,
+                }
+        })
+}
@@ -1,31 +1,1296 @@
 use std::path::Path;
 
 use anyhow::Result;
-use html5ever::tendril::TendrilSink;
-use html5ever::{serialize, serialize::SerializeOpts};
-use markup5ever_rcdom::{RcDom, SerializableHandle};
-
-use super::{FormatError, ensure_newline};
-
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let dom: RcDom = html5ever::parse_document(RcDom::default(), Default::default())
-        .from_utf8()
-        .read_from(&mut text.as_bytes())
-        .map_err(|e| FormatError::Message(format!("html parse error: {e:?}")))?;
-
-    let mut out = Vec::new();
-    let opts = SerializeOpts {
-        traversal_scope: serialize::TraversalScope::ChildrenOnly(None),
-        ..Default::default()
+use serde::Deserialize;
+
+use super::{FormatError, css, ensure_newline, javascript, json, options, typescript};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HtmlOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    /// Text is wrapped and long start tags get one attribute per line past this width.
+    pub line_width: usize,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            line_width: 80,
+        }
+    }
+}
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Phrasing elements laid out inside the surrounding text flow.
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "del", "dfn", "em",
+    "i", "img", "input", "ins", "kbd", "label", "mark", "meter", "output", "progress", "q", "s",
+    "samp", "select", "small", "span", "strong", "sub", "sup", "textarea", "time", "u", "var",
+    "wbr",
+];
+
+/// Elements whose content is copied (or delegated) rather than parsed.
+const RAW: &[&str] = &["script", "style", "pre", "textarea"];
+
+/// Elements whose end tag may be omitted when the parent ends.
+const OPTIONAL_END: &[&str] = &[
+    "p", "li", "dt", "dd", "option", "optgroup", "tr", "td", "th", "thead", "tbody", "tfoot",
+    "colgroup", "rt", "rp",
+];
+
+/// Start tags that implicitly close an open `<p>`.
+const CLOSES_P: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Template language mixed into an HTML file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// Jinja / Django / Nunjucks: `{% if %}`, `{{ x }}`, `{# #}`.
+    Jinja,
+    /// Go `html/template`: `{{ if }}`, `{{ end }}`, `{{/* */}}`.
+    Go,
+    /// Handlebars / Mustache: `{{#each}}`, `{{/each}}`, `{{! }}`.
+    Handlebars,
+    /// Vue templates: `{{ x }}` interpolation; control flow lives in attributes.
+    Vue,
+    /// Svelte: `{#if}`, `{:else}`, `{/if}`, `{@html x}`, `{x}`.
+    Svelte,
+    /// Astro: `{x}` JSX expressions.
+    Astro,
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).html)
+}
+
+pub fn format_template(
+    path: &Path,
+    text: &str,
+    template: Template,
+) -> Result<Option<String>, FormatError> {
+    format_template_with_options(path, text, &options::for_path(path).html, template)
+}
+
+/// HTML pretty printer:
+/// - block elements go on their own lines, indented by nesting depth
+/// - text and inline elements are re-flowed to `line_width` without adding or
+///   removing whitespace between words
+/// - `<pre>`/`<textarea>` content is kept verbatim; `<script>`, `<style>` and
+///   JSON-LD bodies are formatted by the JavaScript/TypeScript, CSS and JSON formatters
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &HtmlOptions,
+) -> Result<Option<String>, FormatError> {
+    print(path, text, opts, None)
+}
+
+/// Like [`format_with_options`], with template tags kept intact: expressions
+/// flow with the text and blocks such as `{% if %}…{% endif %}` are indented
+/// like elements.
+pub fn format_template_with_options(
+    path: &Path,
+    text: &str,
+    opts: &HtmlOptions,
+    template: Template,
+) -> Result<Option<String>, FormatError> {
+    print(path, text, opts, Some(template))
+}
+
+fn print(
+    path: &Path,
+    text: &str,
+    opts: &HtmlOptions,
+    template: Option<Template>,
+) -> Result<Option<String>, FormatError> {
+    let nodes = parse(text, template)?;
+    let mut printer = Printer {
+        path,
+        opts,
+        template,
+        lines: Vec::new(),
+    };
+    printer.children(&nodes, 0)?;
+    let out = ensure_newline(printer.lines.join("\n"));
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    Text(String),
+    /// Comments, doctype, processing instructions, CDATA, stray end tags and
+    /// template statements.
+    Verbatim(String),
+    /// Template expression kept as one word, e.g. `{{ user.name }}`.
+    Tag(String),
+    /// `{% else %}`-like separator inside a template block.
+    Branch(String),
+    Block(Block),
+}
+
+/// `{% if %}…{% endif %}`, `{{#each}}…{{/each}}` and friends.
+#[derive(Debug, Default)]
+struct Block {
+    open: String,
+    keyword: String,
+    children: Vec<Node>,
+    close: Option<String>,
+    /// Whether the block spans several source lines; single-line blocks stay
+    /// in the text flow.
+    multiline: bool,
+    /// Source offset of the opening tag.
+    start: usize,
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    /// Tag name as written in the source.
+    name: String,
+    /// Lowercased name used for lookups.
+    tag: String,
+    attrs: Vec<Attr>,
+    children: Vec<Node>,
+    /// Unparsed body of `RAW` elements.
+    raw: Option<String>,
+    self_closing: bool,
+    /// Whether the source had an end tag (or an implied one, like a `<li>`
+    /// followed by another `<li>`).
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Attr {
+    name: String,
+    value: Option<String>,
+    quote: char,
+    /// Template tags glued to the closing quote.
+    suffix: String,
+    /// An unquoted expression such as Svelte's `on:click={handler}`, which
+    /// would become a plain string if quoted.
+    bare: bool,
+}
+
+impl Attr {
+    fn render(&self) -> String {
+        match &self.value {
+            None => self.name.clone(),
+            Some(v) if self.bare => format!("{}={v}", self.name),
+            Some(v) => {
+                let quote = match (v.contains('"'), v.contains('\'')) {
+                    (true, false) => '\'',
+                    (true, true) => self.quote,
+                    _ => '"',
+                };
+                format!("{}={quote}{v}{quote}{}", self.name, self.suffix)
+            }
+        }
+    }
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<String> {
+        self.attrs
+            .iter()
+            .find(|a| a.name.eq_ignore_ascii_case(name))
+            .map(|a| {
+                a.value
+                    .clone()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_ascii_lowercase()
+            })
+    }
+
+    fn open_flat(&self) -> String {
+        let mut s = format!("<{}", self.name);
+        for attr in &self.attrs {
+            s.push(' ');
+            s.push_str(&attr.render());
+        }
+        s.push_str(if self.self_closing { " />" } else { ">" });
+        s
+    }
+
+    fn close(&self) -> String {
+        format!("</{}>", self.name)
+    }
+
+    fn is_void(&self) -> bool {
+        self.self_closing || VOID.contains(&self.tag.as_str())
+    }
+}
+
+fn is_inline(node: &Node) -> bool {
+    match node {
+        Node::Text(_) | Node::Tag(_) => true,
+        Node::Verbatim(_) | Node::Branch(_) => false,
+        Node::Element(el) => INLINE.contains(&el.tag.as_str()) && el.children.iter().all(is_inline),
+        Node::Block(block) => {
+            !block.multiline
+                && block
+                    .children
+                    .iter()
+                    .all(|c| is_inline(c) || matches!(c, Node::Branch(_)))
+        }
+    }
+}
+
+fn is_blank(node: &Node) -> bool {
+    matches!(node, Node::Text(t) if t.trim().is_empty())
+}
+
+fn leading_newlines(node: &Node) -> usize {
+    match node {
+        Node::Text(t) => t[..t.len() - t.trim_start().len()].matches('\n').count(),
+        _ => 0,
+    }
+}
+
+fn trailing_newlines(node: &Node) -> usize {
+    match node {
+        Node::Text(t) => t[t.trim_end().len()..].matches('\n').count(),
+        _ => 0,
+    }
+}
+
+/// Open container while parsing: an element or a template block.
+enum Frame {
+    Element(Element),
+    Block(Block),
+}
+
+impl Frame {
+    fn children(&mut self) -> &mut Vec<Node> {
+        match self {
+            Frame::Element(el) => &mut el.children,
+            Frame::Block(block) => &mut block.children,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    template: Option<Template>,
+    stack: Vec<Frame>,
+}
+
+fn parse(text: &str, template: Option<Template>) -> Result<Vec<Node>, FormatError> {
+    let mut parser = Parser {
+        text,
+        template,
+        stack: vec![Frame::Element(Element::default())],
+    };
+    parser.run()?;
+    while parser.stack.len() > 1 {
+        parser.pop_implied();
+    }
+    match parser.stack.pop() {
+        Some(Frame::Element(root)) => Ok(root.children),
+        _ => unreachable!("document root is an element"),
+    }
+}
+
+impl Parser<'_> {
+    fn run(&mut self) -> Result<(), FormatError> {
+        let text = self.text;
+        let bytes = text.as_bytes();
+        let mut i = 0;
+
+        while i < text.len() {
+            let rest = &text[i..];
+            if let Some((len, role)) = template_tag(rest, self.template)? {
+                if let Role::Open(keyword) = &role {
+                    if matches!(keyword.as_str(), "raw" | "verbatim") {
+                        // `{% raw %}…{% endraw %}` is copied untouched.
+                        let end = raw_block_end(rest, len, keyword, self.template)?;
+                        self.push(Node::Verbatim(rest[..end].to_string()));
+                        i += end;
+                        continue;
+                    }
+                }
+                self.template_tag(i, &rest[..len], role);
+                i += len;
+                continue;
+            }
+
+            let verbatim_end = if rest.starts_with("<!--") {
+                Some(find_end(rest, "-->", "comment")?)
+            } else if rest.starts_with("<![CDATA[") {
+                Some(find_end(rest, "]]>", "CDATA section")?)
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                Some(find_end(rest, ">", "declaration")?)
+            } else {
+                None
+            };
+            if let Some(end) = verbatim_end {
+                self.push(Node::Verbatim(rest[..end].to_string()));
+                i += end;
+                continue;
+            }
+
+            if rest.starts_with("</") {
+                let end = find_end(rest, ">", "end tag")?;
+                let tag = rest[2..end - 1].trim().to_ascii_lowercase();
+                // Template blocks are boundaries: `</div>` never closes an
+                // element opened outside the enclosing `{% if %}`.
+                let open = self
+                    .stack
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .rev()
+                    .take_while(|(_, f)| matches!(f, Frame::Element(_)))
+                    .find(|(_, f)| matches!(f, Frame::Element(e) if e.tag == tag))
+                    .map(|(pos, _)| pos);
+                match open {
+                    Some(pos) => {
+                        while self.stack.len() > pos + 1 {
+                            self.pop_implied();
+                        }
+                        self.pop(true);
+                    }
+                    // Stray end tags are kept as written.
+                    None => self.push(Node::Verbatim(rest[..end].to_string())),
+                }
+                i += end;
+                continue;
+            }
+
+            if rest.starts_with('<') && bytes.get(i + 1).is_some_and(u8::is_ascii_alphabetic) {
+                let (mut el, end) = parse_start_tag(text, i, self.template)?;
+                i = end;
+                while let Some(Frame::Element(top)) = self.stack.last() {
+                    if self.stack.len() == 1 || !closes(&top.tag, &el.tag) {
+                        break;
+                    }
+                    self.pop(true);
+                }
+                if RAW.contains(&el.tag.as_str()) && !el.self_closing {
+                    let close = format!("</{}", el.tag);
+                    let body_end = text[i..]
+                        .to_ascii_lowercase()
+                        .find(&close)
+                        .map_or(text.len(), |p| i + p);
+                    el.raw = Some(text[i..body_end].to_string());
+                    el.closed = body_end < text.len();
+                    i = text[body_end..]
+                        .find('>')
+                        .map_or(text.len(), |p| body_end + p + 1);
+                    self.push(Node::Element(el));
+                } else if el.is_void() {
+                    self.push(Node::Element(el));
+                } else {
+                    self.stack.push(Frame::Element(el));
+                }
+                continue;
+            }
+
+            let end = text_end(text, i + 1, self.template);
+            self.push(Node::Text(text[i..end].to_string()));
+            i = end;
+        }
+        Ok(())
+    }
+
+    fn template_tag(&mut self, start: usize, tag: &str, role: Role) {
+        match role {
+            Role::Inline => self.push(Node::Tag(tag.to_string())),
+            Role::Statement => self.push(Node::Verbatim(tag.to_string())),
+            Role::Open(keyword) => self.stack.push(Frame::Block(Block {
+                open: tag.to_string(),
+                keyword,
+                start,
+                ..Block::default()
+            })),
+            Role::Branch => match self.innermost_block(|_| true) {
+                Some(pos) => {
+                    while self.stack.len() > pos + 1 {
+                        self.pop(false);
+                    }
+                    self.push(Node::Branch(tag.to_string()));
+                }
+                None => self.push(Node::Tag(tag.to_string())),
+            },
+            Role::Close(keyword) => match self.innermost_block(|b| b.keyword == keyword) {
+                Some(pos) => {
+                    while self.stack.len() > pos + 1 {
+                        self.pop(false);
+                    }
+                    if let Some(Frame::Block(block)) = self.stack.pop() {
+                        let end = start + tag.len();
+                        self.push(Node::Block(Block {
+                            close: Some(tag.to_string()),
+                            multiline: self.text[block.start..end].contains('\n'),
+                            ..block
+                        }));
+                    }
+                }
+                None => self.push(Node::Tag(tag.to_string())),
+            },
+        }
+    }
+
+    fn innermost_block(&self, matches: impl Fn(&Block) -> bool) -> Option<usize> {
+        self.stack
+            .iter()
+            .rposition(|f| matches!(f, Frame::Block(b) if matches(b)))
+    }
+
+    fn push(&mut self, node: Node) {
+        self.stack
+            .last_mut()
+            .expect("document root")
+            .children()
+            .push(node);
+    }
+
+    /// Close the innermost container because an ancestor ended; elements
+    /// whose end tag is optional (`<li>`, `<p>`, `<td>`…) count as closed.
+    fn pop_implied(&mut self) {
+        let closed = matches!(self.stack.last(), Some(Frame::Element(el)) if OPTIONAL_END.contains(&el.tag.as_str()));
+        self.pop(closed);
+    }
+
+    /// Close the innermost container; `closed` records whether the source
+    /// had an end tag for it.
+    fn pop(&mut self, closed: bool) {
+        let node = match self.stack.pop().expect("open container") {
+            Frame::Element(el) => Node::Element(Element { closed, ..el }),
+            Frame::Block(block) => Node::Block(Block {
+                multiline: true,
+                ..block
+            }),
+        };
+        self.push(node);
+    }
+}
+
+/// Offset just past `terminator` in `rest`.
+fn find_end(rest: &str, terminator: &str, what: &str) -> Result<usize, FormatError> {
+    rest.find(terminator)
+        .map(|p| p + terminator.len())
+        .ok_or_else(|| FormatError::Message(format!("html parse error: unterminated {what}")))
+}
+
+/// Start of the next markup construct at or after `from`.
+fn text_end(text: &str, from: usize, template: Option<Template>) -> usize {
+    let bytes = text.as_bytes();
+    let mut j = from;
+    while j < text.len() {
+        let markup = bytes[j] == b'<'
+            && bytes
+                .get(j + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || matches!(b, b'/' | b'!' | b'?'));
+        if markup || starts_template(&bytes[j..], template) {
+            return j;
+        }
+        j += 1;
+    }
+    text.len()
+}
+
+/// Offset just past the tag closing the raw block opened in `rest[..open_len]`.
+fn raw_block_end(
+    rest: &str,
+    open_len: usize,
+    keyword: &str,
+    template: Option<Template>,
+) -> Result<usize, FormatError> {
+    let mut i = open_len;
+    while let Some(p) = rest[i..].find('{') {
+        i += p;
+        if let Ok(Some((len, Role::Close(k)))) = template_tag(&rest[i..], template) {
+            if k == keyword {
+                return Ok(i + len);
+            }
+        }
+        i += 1;
+    }
+    Err(FormatError::Message(format!(
+        "html parse error: unterminated `{keyword}` block"
+    )))
+}
+
+/// Whether starting `new` implicitly closes the open element `open`.
+fn closes(open: &str, new: &str) -> bool {
+    match open {
+        "p" => CLOSES_P.contains(&new),
+        "li" => new == "li",
+        "option" => matches!(new, "option" | "optgroup"),
+        "dt" | "dd" => matches!(new, "dt" | "dd"),
+        "tr" => new == "tr",
+        "td" | "th" => matches!(new, "td" | "th" | "tr"),
+        "thead" | "tbody" => matches!(new, "tbody" | "tfoot"),
+        _ => false,
+    }
+}
+
+/// Advance over an attribute name or unquoted value, treating template tags
+/// as opaque so `{% if a > b %}` does not end the start tag.
+fn scan_attr_word(text: &str, mut i: usize, template: Option<Template>, stop_at_eq: bool) -> usize {
+    let b = text.as_bytes();
+    while i < b.len() {
+        if starts_template(&b[i..], template) {
+            match template_tag(&text[i..], template) {
+                Ok(Some((len, _))) => {
+                    i += len;
+                    continue;
+                }
+                _ => return b.len(),
+            }
+        }
+        if b[i].is_ascii_whitespace()
+            || b[i] == b'>'
+            || (stop_at_eq && b[i] == b'=')
+            || (b[i] == b'/' && b.get(i + 1) == Some(&b'>'))
+        {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Position of the closing quote `q`, skipping quotes inside template tags
+/// such as `"{{ "a" | upper }}"`.
+fn find_quote(text: &str, mut i: usize, q: u8, template: Option<Template>) -> Option<usize> {
+    let b = text.as_bytes();
+    while i < b.len() {
+        if starts_template(&b[i..], template) {
+            if let Ok(Some((len, _))) = template_tag(&text[i..], template) {
+                i += len;
+                continue;
+            }
+        }
+        if b[i] == q {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+fn parse_start_tag(
+    text: &str,
+    start: usize,
+    template: Option<Template>,
+) -> Result<(Element, usize), FormatError> {
+    let b = text.as_bytes();
+    let len = b.len();
+    let mut i = start + 1;
+    while i < len && !b[i].is_ascii_whitespace() && b[i] != b'>' && b[i] != b'/' {
+        i += 1;
+    }
+    let name = text[start + 1..i].to_string();
+    let mut el = Element {
+        tag: name.to_ascii_lowercase(),
+        name,
+        closed: true,
+        ..Element::default()
+    };
+    let name_end = i;
+    let unterminated = || {
+        FormatError::Message(format!(
+            "html parse error: unterminated tag <{}",
+            &text[start + 1..name_end]
+        ))
     };
 
-    let handle: SerializableHandle = dom.document.clone().into();
-    serialize(&mut out, &handle, opts).map_err(|e| FormatError::Message(e.to_string()))?;
-    let out = String::from_utf8(out).map_err(|e| FormatError::Message(e.to_string()))?;
+    loop {
+        while i < len && b[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= len {
+            return Err(unterminated());
+        }
+        match b[i] {
+            b'>' => return Ok((el, i + 1)),
+            b'/' if b.get(i + 1) == Some(&b'>') => {
+                el.self_closing = true;
+                return Ok((el, i + 2));
+            }
+            b'/' | b'=' => {
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let name_start = i;
+        i = scan_attr_word(text, i, template, true);
+        let mut attr = Attr {
+            name: text[name_start..i].to_string(),
+            value: None,
+            quote: '"',
+            suffix: String::new(),
+            bare: false,
+        };
+        let mut j = i;
+        while j < len && b[j].is_ascii_whitespace() {
+            j += 1;
+        }
+        if b.get(j) == Some(&b'=') {
+            i = j + 1;
+            while i < len && b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match b.get(i) {
+                Some(&q @ (b'"' | b'\'')) => {
+                    let close = find_quote(text, i + 1, q, template).ok_or_else(unterminated)?;
+                    attr.value = Some(text[i + 1..close].to_string());
+                    attr.quote = q as char;
+                    // `class="a"{% endif %}`: keep what is glued to the quote.
+                    let suffix_end = scan_attr_word(text, close + 1, template, false);
+                    attr.suffix = text[close + 1..suffix_end].to_string();
+                    i = suffix_end;
+                }
+                _ => {
+                    let value_start = i;
+                    i = scan_attr_word(text, i, template, false);
+                    attr.bare = matches!(template, Some(Template::Svelte | Template::Astro))
+                        && b.get(value_start) == Some(&b'{');
+                    attr.value = Some(text[value_start..i].to_string());
+                }
+            }
+        }
+        if i >= len {
+            return Err(unterminated());
+        }
+        el.attrs.push(attr);
+    }
+}
+
+/// How a template tag takes part in the document structure.
+#[derive(Debug, PartialEq)]
+enum Role {
+    /// An expression printed inside the text flow, e.g. `{{ user.name }}`.
+    Inline,
+    /// A statement or comment on its own line, e.g. `{% include "a" %}`.
+    Statement,
+    /// Opens a block closed by `Close` with the same keyword.
+    Open(String),
+    /// `else`-like separator inside the innermost block.
+    Branch,
+    Close(String),
+}
+
+fn starts_template(bytes: &[u8], template: Option<Template>) -> bool {
+    match template {
+        None => false,
+        Some(Template::Jinja) => {
+            bytes.first() == Some(&b'{') && matches!(bytes.get(1), Some(b'{' | b'%' | b'#'))
+        }
+        Some(Template::Go | Template::Handlebars | Template::Vue) => bytes.starts_with(b"{{"),
+        Some(Template::Svelte | Template::Astro) => bytes.first() == Some(&b'{'),
+    }
+}
+
+/// Length and role of the template tag at the start of `rest`, if any.
+fn template_tag(
+    rest: &str,
+    template: Option<Template>,
+) -> Result<Option<(usize, Role)>, FormatError> {
+    let Some(template) = template else {
+        return Ok(None);
+    };
+    if !starts_template(rest.as_bytes(), Some(template)) {
+        return Ok(None);
+    }
+    let (open, close) = match template {
+        Template::Jinja if rest.starts_with("{#") => ("{#", "#}"),
+        Template::Jinja if rest.starts_with("{%") => ("{%", "%}"),
+        Template::Handlebars if rest.starts_with("{{!--") => ("{{!--", "--}}"),
+        Template::Handlebars if rest.starts_with("{{{") => ("{{{", "}}}"),
+        Template::Svelte | Template::Astro => ("{", "}"),
+        _ => ("{{", "}}"),
+    };
+    let len = match template {
+        Template::Svelte | Template::Astro => expression_len(rest),
+        _ => rest[open.len()..]
+            .find(close)
+            .map(|p| open.len() + p + close.len()),
+    }
+    .ok_or_else(|| {
+        FormatError::Message(format!(
+            "html parse error: unterminated template tag `{open}`"
+        ))
+    })?;
+    let inner = rest[open.len()..len - close.len()]
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '+' | '~'));
+    let keyword = inner
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .next()
+        .unwrap_or_default();
+
+    let role = match template {
+        Template::Jinja => match open {
+            "{#" => Role::Statement,
+            "{{" => Role::Inline,
+            _ => jinja_role(keyword, inner),
+        },
+        Template::Go => match keyword {
+            _ if inner.starts_with("/*") => Role::Statement,
+            "if" | "range" | "with" | "define" | "block" => Role::Open(String::new()),
+            "else" => Role::Branch,
+            "end" => Role::Close(String::new()),
+            _ => Role::Inline,
+        },
+        Template::Handlebars => {
+            // `{{#each items}}`, `{{#> layout}}`: the block name is the first
+            // word after the sigils.
+            let name = |s: &str| {
+                s.trim_start_matches(['>', '*'])
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            match inner.chars().next() {
+                _ if open == "{{!--" || inner.starts_with('!') => Role::Statement,
+                _ if open == "{{{" => Role::Inline,
+                Some('#') => Role::Open(name(&inner[1..])),
+                Some('/') => Role::Close(name(&inner[1..])),
+                Some('^') if inner.len() == 1 => Role::Branch,
+                Some('^') => Role::Open(name(&inner[1..])),
+                _ if keyword == "else" => Role::Branch,
+                _ => Role::Inline,
+            }
+        }
+        Template::Vue | Template::Astro => Role::Inline,
+        Template::Svelte => {
+            let name = inner
+                .get(1..)
+                .and_then(|s| s.split_whitespace().next())
+                .unwrap_or_default()
+                .to_string();
+            match inner.chars().next() {
+                Some('#') => Role::Open(name),
+                Some(':') => Role::Branch,
+                Some('/') => Role::Close(name),
+                _ if matches!(keyword, "@const" | "@debug") => Role::Statement,
+                _ => Role::Inline,
+            }
+        }
+    };
+    Ok(Some((len, role)))
+}
+
+/// Length of the balanced `{…}` expression at the start of `rest`; braces
+/// inside string literals do not count. Quotes in the text of JSX elements,
+/// as in `{ok && <p>Don't</p>}`, are plain characters.
+fn expression_len(rest: &str) -> Option<usize> {
+    enum Scope {
+        Code,
+        Tag,
+        Text,
+    }
+    let chars: Vec<(usize, char)> = rest.char_indices().collect();
+    let next = |i: usize| chars.get(i + 1).map(|&(_, c)| c);
+    let mut scopes = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    // Last significant character of code, to tell `<p>` from `a < b`.
+    let mut prev = None;
+    let mut i = 0;
+    while let Some(&(at, c)) = chars.get(i) {
+        i += 1;
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match scopes.last() {
+            None | Some(Scope::Code) => match c {
+                '"' | '\'' | '`' => quote = Some(c),
+                '{' => scopes.push(Scope::Code),
+                '}' => {
+                    scopes.pop();
+                    if scopes.is_empty() {
+                        return Some(at + 1);
+                    }
+                }
+                '<' if next(i - 1).is_some_and(|n| n.is_alphabetic() || n == '>')
+                    && !prev.is_some_and(|p: char| {
+                        p.is_alphanumeric() || matches!(p, '_' | '$' | ')' | ']')
+                    }) =>
+                {
+                    scopes.push(Scope::Tag);
+                }
+                _ => {}
+            },
+            Some(Scope::Tag) => match c {
+                '"' | '\'' => quote = Some(c),
+                '{' => scopes.push(Scope::Code),
+                '/' if next(i - 1) == Some('>') => {
+                    i += 1;
+                    scopes.pop();
+                }
+                '>' => {
+                    scopes.pop();
+                    scopes.push(Scope::Text);
+                }
+                _ => {}
+            },
+            Some(Scope::Text) => match c {
+                '{' => scopes.push(Scope::Code),
+                '<' if next(i - 1) == Some('/') => {
+                    // The closing tag ends the element's text.
+                    i += chars[i..].iter().position(|&(_, c)| c == '>')? + 1;
+                    scopes.pop();
+                }
+                '<' if next(i - 1).is_some_and(|n| n.is_alphabetic() || n == '>') => {
+                    scopes.push(Scope::Tag);
+                }
+                _ => {}
+            },
+        }
+        if !c.is_whitespace() {
+            prev = Some(c);
+        }
+    }
+    None
+}
+
+const JINJA_BLOCKS: &[&str] = &[
+    "if",
+    "for",
+    "block",
+    "macro",
+    "call",
+    "filter",
+    "with",
+    "autoescape",
+    "trans",
+    "blocktrans",
+    "embed",
+    "apply",
+    "spaceless",
+    "raw",
+    "verbatim",
+];
+
+fn jinja_role(keyword: &str, inner: &str) -> Role {
+    match keyword {
+        "elif" | "else" | "elseif" | "empty" | "pluralize" | "plural" => Role::Branch,
+        // `{% set x %}…{% endset %}` is a block; `{% set x = 1 %}` is not.
+        "set" if !inner.contains('=') => Role::Open("set".to_string()),
+        k if JINJA_BLOCKS.contains(&k) => Role::Open(k.to_string()),
+        k if k.starts_with("end") => Role::Close(k["end".len()..].to_string()),
+        _ => Role::Statement,
+    }
+}
+
+enum Embedded {
+    JavaScript,
+    TypeScript,
+    Json,
+    Css,
+}
+
+fn embedded_kind(el: &Element) -> Option<Embedded> {
+    let lang = el.attr("lang");
+    let ty = el.attr("type");
+    match el.tag.as_str() {
+        "script" => match (lang.as_deref(), ty.as_deref()) {
+            (Some("ts" | "tsx" | "typescript"), _) => Some(Embedded::TypeScript),
+            (_, Some("text/typescript" | "application/typescript")) => Some(Embedded::TypeScript),
+            (
+                _,
+                None
+                | Some(
+                    ""
+                    | "module"
+                    | "text/javascript"
+                    | "application/javascript"
+                    | "text/ecmascript"
+                    | "application/ecmascript",
+                ),
+            ) => Some(Embedded::JavaScript),
+            (
+                _,
+                Some("application/ld+json" | "application/json" | "importmap" | "speculationrules"),
+            ) => Some(Embedded::Json),
+            _ => None,
+        },
+        "style" => match (lang.as_deref(), ty.as_deref()) {
+            (None | Some("css"), None | Some("" | "text/css")) => Some(Embedded::Css),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Remove the common leading indentation and surrounding blank lines.
+fn dedent(code: &str) -> Vec<String> {
+    let lines: Vec<&str> = code.lines().map(str::trim_end).collect();
+    let start = lines
+        .iter()
+        .position(|l| !l.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(start, |p| p + 1);
+    let lines = &lines[start..end];
+    let margin = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.get(margin..).unwrap_or("").to_string())
+        .collect()
+}
+
+struct Printer<'a> {
+    path: &'a Path,
+    opts: &'a HtmlOptions,
+    template: Option<Template>,
+    lines: Vec<String>,
+}
+
+impl Printer<'_> {
+    fn pad(&self, level: usize) -> String {
+        " ".repeat(level * self.opts.indent)
+    }
+
+    fn blank(&mut self) {
+        if self.lines.last().is_some_and(|l| !l.is_empty()) {
+            self.lines.push(String::new());
+        }
+    }
+
+    /// Print sibling nodes one block per line, keeping single blank lines
+    /// that separated them in the source.
+    fn children(&mut self, nodes: &[Node], level: usize) -> Result<(), FormatError> {
+        let start = self.lines.len();
+        let mut run: Vec<&Node> = Vec::new();
+        let mut gap = 0;
+        for node in nodes {
+            if is_inline(node) {
+                if run.is_empty() {
+                    if is_blank(node) {
+                        gap = leading_newlines(node);
+                        continue;
+                    }
+                    gap += leading_newlines(node);
+                    if gap >= 2 && self.lines.len() > start {
+                        self.blank();
+                    }
+                }
+                run.push(node);
+                continue;
+            }
+            if let Some(last) = run.last() {
+                gap = trailing_newlines(last);
+                self.fill(&run, level);
+                run.clear();
+            }
+            if gap >= 2 && self.lines.len() > start {
+                self.blank();
+            }
+            gap = 0;
+            match node {
+                Node::Element(el) => self.element(el, level)?,
+                Node::Block(block) => self.block(block, level)?,
+                Node::Verbatim(v) | Node::Branch(v) => {
+                    let pad = self.pad(level);
+                    self.lines.push(format!("{pad}{}", v.trim()));
+                }
+                Node::Text(_) | Node::Tag(_) => unreachable!("inline nodes are filled"),
+            }
+        }
+        if !run.is_empty() {
+            self.fill(&run, level);
+        }
+        Ok(())
+    }
+
+    fn element(&mut self, el: &Element, level: usize) -> Result<(), FormatError> {
+        let pad = self.pad(level);
+        let open = self.open_tag(el, level);
+        if el.is_void() {
+            self.lines.extend(open);
+            return Ok(());
+        }
+
+        if let Some(raw) = &el.raw {
+            return self.raw_element(el, raw, open, level);
+        }
+
+        if !el.closed {
+            self.lines.extend(open);
+            return self.children(&el.children, level + 1);
+        }
+
+        if el.children.iter().all(is_blank) {
+            let mut open = open;
+            let last = open.pop().expect("open tag");
+            self.lines.extend(open);
+            self.lines.push(format!("{last}{}", el.close()));
+            return Ok(());
+        }
+
+        if open.len() == 1 && el.children.iter().all(is_inline) {
+            let words = inline_words(&el.children.iter().collect::<Vec<_>>());
+            if !words.iter().any(|w| w.break_after) {
+                let flat: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+                let line = format!("{}{}{}", open[0], flat.join(" "), el.close());
+                if line.chars().count() <= self.opts.line_width && !line.contains('\n') {
+                    self.lines.push(line);
+                    return Ok(());
+                }
+            }
+        }
+
+        self.lines.extend(open);
+        self.children(&el.children, level + 1)?;
+        self.lines.push(format!("{pad}{}", el.close()));
+        Ok(())
+    }
+
+    /// A template block indented like an element, with its `else` branches
+    /// at the block's own level.
+    fn block(&mut self, block: &Block, level: usize) -> Result<(), FormatError> {
+        let pad = self.pad(level);
+        self.lines.push(format!("{pad}{}", block.open.trim()));
+        for segment in block
+            .children
+            .split_inclusive(|c| matches!(c, Node::Branch(_)))
+        {
+            match segment.split_last() {
+                Some((Node::Branch(branch), body)) => {
+                    self.children(body, level + 1)?;
+                    self.lines.push(format!("{pad}{}", branch.trim()));
+                }
+                _ => self.children(segment, level + 1)?,
+            }
+        }
+        if let Some(close) = &block.close {
+            self.lines.push(format!("{pad}{}", close.trim()));
+        }
+        Ok(())
+    }
+
+    fn raw_element(
+        &mut self,
+        el: &Element,
+        raw: &str,
+        mut open: Vec<String>,
+        level: usize,
+    ) -> Result<(), FormatError> {
+        let last = open.pop().expect("open tag");
+        self.lines.extend(open);
+        let close = if el.closed { el.close() } else { String::new() };
+        if matches!(el.tag.as_str(), "pre" | "textarea") || raw.trim().is_empty() {
+            let body = if raw.trim().is_empty() { "" } else { raw };
+            self.lines.push(format!("{last}{body}{close}"));
+            return Ok(());
+        }
+
+        // Code containing template tags is not valid JS/CSS on its own.
+        let templated =
+            (0..raw.len()).any(|i| starts_template(&raw.as_bytes()[i..], self.template));
+        let embedded = if templated { None } else { embedded_kind(el) };
+        let formatted = match embedded {
+            Some(kind) => {
+                let code = dedent(raw).join("\n");
+                let res = match kind {
+                    Embedded::JavaScript => {
+                        javascript::format(&self.path.with_extension("js"), &code)
+                    }
+                    Embedded::TypeScript => {
+                        typescript::format(&self.path.with_extension("ts"), &code)
+                    }
+                    Embedded::Json => json::format(&self.path.with_extension("json"), &code),
+                    Embedded::Css => css::format(&self.path.with_extension("css"), &code),
+                }
+                .map_err(|e| FormatError::Message(format!("<{}> block: {e}", el.tag)))?;
+                res.unwrap_or(code)
+            }
+            None => raw.to_string(),
+        };
+
+        self.lines.push(last);
+        let pad = self.pad(level + 1);
+        for line in dedent(&formatted) {
+            if line.is_empty() {
+                self.lines.push(line);
+            } else {
+                self.lines.push(format!("{pad}{line}"));
+            }
+        }
+        if el.closed {
+            self.lines.push(format!("{}{close}", self.pad(level)));
+        }
+        Ok(())
+    }
+
+    /// The start tag, with one attribute per line when it is too wide.
+    fn open_tag(&self, el: &Element, level: usize) -> Vec<String> {
+        let pad = self.pad(level);
+        let flat = format!("{pad}{}", el.open_flat());
+        if el.attrs.len() < 2 || flat.chars().count() <= self.opts.line_width {
+            return vec![flat];
+        }
+        let inner = self.pad(level + 1);
+        let mut lines = vec![format!("{pad}<{}", el.name)];
+        lines.extend(el.attrs.iter().map(|a| format!("{inner}{}", a.render())));
+        lines.push(format!("{pad}{}", if el.self_closing { "/>" } else { ">" }));
+        lines
+    }
+
+    /// Re-flow a run of text and inline elements to the line width.
+    fn fill(&mut self, run: &[&Node], level: usize) {
+        // A lone `<img>`/`<input>` may still need its attributes wrapped.
+        let mut content = run.iter().filter(|n| !is_blank(n));
+        if let (Some(Node::Element(el)), None) = (content.next(), content.next()) {
+            if el.is_void() {
+                self.lines.extend(self.open_tag(el, level));
+                return;
+            }
+        }
+        let pad = self.pad(level);
+        let width = self.opts.line_width.saturating_sub(pad.len());
+        let mut line = String::new();
+        let mut force_break = false;
+        for word in inline_words(run) {
+            let fits = line.chars().count() + 1 + word.text.chars().count() <= width;
+            if !line.is_empty() && (force_break || !fits) {
+                self.lines.push(format!("{pad}{line}"));
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word.text);
+            force_break = word.break_after;
+        }
+        if !line.is_empty() {
+            self.lines.push(format!("{pad}{line}"));
+        }
+    }
+}
+
+/// An unbreakable piece of inline content; words are separated by
+/// whitespace in the source.
+struct Word {
+    text: String,
+    /// Set after `<br>` so the next word starts a new line.
+    break_after: bool,
+}
+
+#[derive(Default)]
+struct Words {
+    words: Vec<Word>,
+    space: bool,
+}
+
+impl Words {
+    fn push(&mut self, atom: &str) {
+        match self.words.last_mut() {
+            Some(last) if !self.space && !last.break_after => last.text.push_str(atom),
+            _ => self.words.push(Word {
+                text: atom.to_string(),
+                break_after: false,
+            }),
+        }
+        self.space = false;
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Text(t) => {
+                for (idx, part) in t.split(char::is_whitespace).enumerate() {
+                    if idx > 0 {
+                        self.space = true;
+                    }
+                    if !part.is_empty() {
+                        self.push(part);
+                    }
+                }
+            }
+            Node::Verbatim(v) | Node::Tag(v) | Node::Branch(v) => self.push(v.trim()),
+            Node::Block(block) => {
+                self.push(&block.open);
+                for child in &block.children {
+                    self.node(child);
+                }
+                if let Some(close) = &block.close {
+                    self.push(close);
+                }
+            }
+            Node::Element(el) => {
+                self.push(&el.open_flat());
+                if el.is_void() {
+                    if el.tag == "br" {
+                        if let Some(last) = self.words.last_mut() {
+                            last.break_after = true;
+                        }
+                    }
+                    return;
+                }
+                if let Some(raw) = &el.raw {
+                    self.push(raw);
+                }
+                for child in &el.children {
+                    self.node(child);
+                }
+                if el.closed {
+                    self.push(&el.close());
+                }
+            }
+        }
+    }
+}
 
-    if out == text {
-        Ok(None)
-    } else {
-        Ok(Some(ensure_newline(out)))
+fn inline_words(nodes: &[&Node]) -> Vec<Word> {
+    let mut words = Words::default();
+    for node in nodes {
+        words.node(node);
     }
+    words.words
 }
@@ -1,21 +1,306 @@
 use std::path::Path;
 
 use anyhow::Result;
-use ini::Ini;
-
-use super::{FormatError, ensure_newline};
-
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let parsed = Ini::load_from_str(text)
-        .map_err(|e| FormatError::Message(format!("ini parse error: {e}")))?;
-    let mut buf = Vec::new();
-    parsed
-        .write_to(&mut buf)
-        .map_err(|e| FormatError::Message(format!("ini write error: {e}")))?;
-    let out = String::from_utf8(buf).map_err(|e| FormatError::Message(e.to_string()))?;
-    if out == text {
-        Ok(None)
+use serde::Deserialize;
+
+use super::{FormatError, ensure_newline, options};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct IniOptions {
+    /// Pad keys so the delimiters within a section line up.
+    pub align_equals: bool,
+    /// Write `key = value` (`true`) or `key=value` (`false`). Defaults to the
+    /// file's dialect: no spaces for systemd units, spaces everywhere else.
+    pub space_around_delimiter: Option<bool>,
+}
+
+/// Conventions that differ between INI flavours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    /// Python configparser style (`setup.cfg`, `tox.ini`, `php.ini`, ...):
+    /// indented lines continue the previous value.
+    Generic,
+    /// git config files: keys are indented with a tab inside sections.
+    Git,
+    /// systemd units: `Key=Value` without spaces, `\` continuations.
+    Systemd,
+}
+
+const SYSTEMD_EXTENSIONS: &[&str] = &[
+    "service",
+    "socket",
+    "timer",
+    "mount",
+    "automount",
+    "target",
+    "path",
+    "slice",
+    "scope",
+    "swap",
+    "network",
+    "netdev",
+    "link",
+];
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).ini)
+}
+
+/// Lossless INI formatter: comments, blank lines (collapsed to one), key
+/// order, duplicate keys, quoting and continuation lines are kept; only
+/// section headers and `key = value` spacing are normalized.
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &IniOptions,
+) -> Result<Option<String>, FormatError> {
+    let dialect = dialect_for(path);
+    let spaced = opts
+        .space_around_delimiter
+        .unwrap_or(dialect != Dialect::Systemd);
+
+    let sections = parse(text, dialect);
+    let mut out = String::new();
+    for section in &sections {
+        let width = if opts.align_equals {
+            section
+                .entries
+                .iter()
+                .filter_map(|e| match e {
+                    Entry::Pair { key, .. } => Some(key.chars().count()),
+                    _ => None,
+                })
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let indent = if dialect == Dialect::Git && section.header.is_some() {
+            "\t"
+        } else {
+            ""
+        };
+        if let Some(header) = &section.header {
+            out.push_str(header);
+            out.push('\n');
+        }
+        for entry in &section.entries {
+            match entry {
+                Entry::Blank => out.push('\n'),
+                Entry::Comment(c) => {
+                    out.push_str(indent);
+                    out.push_str(c);
+                    out.push('\n');
+                }
+                Entry::Pair {
+                    key,
+                    delimiter,
+                    value,
+                    continuation,
+                } => {
+                    out.push_str(indent);
+                    out.push_str(key);
+                    let pad = width.saturating_sub(key.chars().count());
+                    let (before, after) = match (*delimiter, spaced) {
+                        (':', true) => (String::new(), " "),
+                        (_, true) => (" ".repeat(pad + 1), " "),
+                        (_, false) => (" ".repeat(pad), ""),
+                    };
+                    out.push_str(&before);
+                    out.push(*delimiter);
+                    if !value.is_empty() {
+                        out.push_str(after);
+                        out.push_str(value);
+                    }
+                    out.push('\n');
+                    for line in continuation {
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                Entry::Other(line) => {
+                    out.push_str(indent);
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+fn dialect_for(path: &Path) -> Dialect {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let in_git_dir = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|d| d == ".git");
+    if matches!(name.as_str(), ".gitconfig" | ".gitmodules") || (in_git_dir && name == "config") {
+        Dialect::Git
+    } else if SYSTEMD_EXTENSIONS.contains(&ext.as_str()) {
+        Dialect::Systemd
+    } else {
+        Dialect::Generic
+    }
+}
+
+#[derive(Debug, Default)]
+struct Section {
+    header: Option<String>,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+enum Entry {
+    Blank,
+    Comment(String),
+    Pair {
+        key: String,
+        delimiter: char,
+        value: String,
+        /// Lines continuing the value, already re-indented.
+        continuation: Vec<String>,
+    },
+    /// A line that is neither a comment nor a `key=value` pair, e.g. a bare
+    /// flag like `skip-networking`.
+    Other(String),
+}
+
+fn parse(text: &str, dialect: Dialect) -> Vec<Section> {
+    let mut sections = vec![Section::default()];
+    let mut backslash_continues = false;
+
+    for raw in text.lines() {
+        let line = raw.trim_end();
+        let trimmed = line.trim_start();
+        let section = sections.last_mut().expect("at least one section");
+
+        if backslash_continues {
+            if let Some(Entry::Pair { continuation, .. }) = section.entries.last_mut() {
+                continuation.push(line.to_string());
+            }
+            backslash_continues = line.ends_with('\\');
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            if !section.entries.is_empty() && !matches!(section.entries.last(), Some(Entry::Blank))
+            {
+                section.entries.push(Entry::Blank);
+            }
+            continue;
+        }
+
+        let indented = line.len() != trimmed.len();
+        if indented && dialect == Dialect::Generic && !trimmed.starts_with(['#', ';']) {
+            if let Some(Entry::Pair { continuation, .. }) = section.entries.last_mut() {
+                continuation.push(format!("    {trimmed}"));
+                continue;
+            }
+        }
+
+        if trimmed.starts_with(['#', ';']) {
+            section.entries.push(Entry::Comment(trimmed.to_string()));
+            continue;
+        }
+
+        if let Some(header) = parse_header(trimmed) {
+            // Comments and blank lines directly above a header belong to it.
+            let mut moved = Vec::new();
+            while matches!(
+                section.entries.last(),
+                Some(Entry::Comment(_) | Entry::Blank)
+            ) {
+                moved.push(section.entries.pop().expect("checked above"));
+            }
+            let mut leading: Vec<Entry> = Vec::new();
+            while let Some(entry) = moved.pop() {
+                leading.push(entry);
+            }
+            // Keep a separating blank line in the previous section.
+            if matches!(leading.first(), Some(Entry::Blank)) {
+                section.entries.push(leading.remove(0));
+            }
+            let mut header_text = String::new();
+            for entry in leading {
+                match entry {
+                    Entry::Comment(c) => {
+                        header_text.push_str(&c);
+                        header_text.push('\n');
+                    }
+                    _ => header_text.push('\n'),
+                }
+            }
+            header_text.push_str(&header);
+            sections.push(Section {
+                header: Some(header_text),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let entry = match split_pair(trimmed) {
+            Some((key, delimiter, value)) => Entry::Pair {
+                key: key.to_string(),
+                delimiter,
+                value: value.to_string(),
+                continuation: Vec::new(),
+            },
+            None => Entry::Other(trimmed.to_string()),
+        };
+        backslash_continues = dialect == Dialect::Systemd && trimmed.ends_with('\\');
+        section.entries.push(entry);
+    }
+
+    for section in &mut sections {
+        while matches!(section.entries.last(), Some(Entry::Blank)) {
+            section.entries.pop();
+        }
+    }
+    // Blank lines between sections are printed as part of the previous one.
+    let count = sections.len();
+    for (idx, section) in sections.iter_mut().enumerate() {
+        let has_content = section.header.is_some() || !section.entries.is_empty();
+        if idx + 1 < count && has_content {
+            section.entries.push(Entry::Blank);
+        }
+    }
+    sections
+}
+
+/// `[ name ]  ; comment` → `[name] ; comment`.
+fn parse_header(line: &str) -> Option<String> {
+    let rest = line.strip_prefix('[')?;
+    let close = rest.find(']')?;
+    let name = rest[..close].trim();
+    let tail = rest[close + 1..].trim();
+    if !tail.is_empty() && !tail.starts_with(['#', ';']) {
+        return None;
+    }
+    Some(if tail.is_empty() {
+        format!("[{name}]")
     } else {
-        Ok(Some(ensure_newline(out)))
+        format!("[{name}] {tail}")
+    })
+}
+
+/// Split at the first `=` or `:` (configparser accepts both).
+fn split_pair(line: &str) -> Option<(&str, char, &str)> {
+    let idx = line.find(['=', ':'])?;
+    let key = line[..idx].trim_end();
+    if key.is_empty() {
+        return None;
     }
+    let delimiter = line[idx..].chars().next()?;
+    Some((key, delimiter, line[idx + 1..].trim()))
 }
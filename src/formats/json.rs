@@ -1,17 +1,759 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::path::Path;
 
 use anyhow::Result;
 use dprint_plugin_json as json;
+use jsonc_parser::ast::{Object, ObjectPropName, Value};
+use jsonc_parser::common::{Range, Ranged};
+use jsonc_parser::{CollectOptions, ParseOptions, parse_to_ast};
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 
 use super::FormatError;
-use super::ensure_newline;
+use super::{ensure_newline, options};
 
 static CONF: Lazy<json::configuration::Configuration> =
     Lazy::new(|| json::configuration::ConfigurationBuilder::new().build());
 
+/// `.json` files that their tools read as JSON with comments.
+const JSONC_FILES: &[&str] = &[
+    "jsconfig.json",
+    "tsconfig.json",
+    "devcontainer.json",
+    ".devcontainer.json",
+    ".eslintrc.json",
+    "tslint.json",
+    "api-extractor.json",
+    "language-configuration.json",
+];
+
+/// Opt-in rewrites; choose them per path with `[[overrides]]` so that e.g.
+/// translation files are sorted while `package.json` keeps its order.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct JsonOptions {
+    /// Sort object keys recursively; comments move with their member.
+    pub sort_keys: bool,
+    /// Keys placed first, in this order, when sorting (`["$schema", "name"]`).
+    pub priority_keys: Vec<String>,
+    /// Write numbers in shortest form (`1.50` -> `1.5`, `1E3` -> `1000`) and
+    /// strings with minimal escaping (`"\u0041"` -> `"A"`).
+    pub normalize: bool,
+    /// Fail when an object has the same key twice.
+    pub reject_duplicate_keys: bool,
+    /// RFC 8785 (JCS) output: sorted keys, no whitespace, no trailing newline.
+    pub canonical: bool,
+    /// Conventional field order for `package.json`, `composer.json`,
+    /// `tsconfig.json` and `.eslintrc.json`, with dependency maps sorted.
+    pub presets: bool,
+}
+
+/// Field order of a well-known manifest. Listed top-level fields come first
+/// in this order, unknown ones follow as written; the objects under `sorted`
+/// have their keys sorted.
+struct Preset {
+    fields: &'static [&'static str],
+    sorted: &'static [&'static str],
+    /// Composer lists platform packages (`php`, `ext-*`) before the others.
+    platform_first: bool,
+}
+
+/// The order used by `sort-package-json`.
+const PACKAGE_JSON: Preset = Preset {
+    fields: &[
+        "$schema",
+        "name",
+        "displayName",
+        "version",
+        "private",
+        "description",
+        "categories",
+        "keywords",
+        "homepage",
+        "bugs",
+        "repository",
+        "funding",
+        "license",
+        "qna",
+        "author",
+        "maintainers",
+        "contributors",
+        "publisher",
+        "sideEffects",
+        "type",
+        "imports",
+        "exports",
+        "main",
+        "svelte",
+        "umd:main",
+        "jsdelivr",
+        "unpkg",
+        "module",
+        "source",
+        "jsnext:main",
+        "browser",
+        "react-native",
+        "types",
+        "typesVersions",
+        "typings",
+        "style",
+        "example",
+        "examplestyle",
+        "assets",
+        "bin",
+        "man",
+        "directories",
+        "files",
+        "workspaces",
+        "binary",
+        "scripts",
+        "betterScripts",
+        "contributes",
+        "activationEvents",
+        "husky",
+        "simple-git-hooks",
+        "pre-commit",
+        "commitlint",
+        "lint-staged",
+        "config",
+        "nodemonConfig",
+        "browserify",
+        "babel",
+        "browserslist",
+        "xo",
+        "prettier",
+        "eslintConfig",
+        "eslintIgnore",
+        "npmpackagejsonlint",
+        "release",
+        "remarkConfig",
+        "stylelint",
+        "ava",
+        "jest",
+        "mocha",
+        "nyc",
+        "c8",
+        "tap",
+        "resolutions",
+        "overrides",
+        "dependencies",
+        "devDependencies",
+        "dependenciesMeta",
+        "peerDependencies",
+        "peerDependenciesMeta",
+        "optionalDependencies",
+        "bundledDependencies",
+        "bundleDependencies",
+        "extensionPack",
+        "extensionDependencies",
+        "flat",
+        "packageManager",
+        "engines",
+        "engineStrict",
+        "volta",
+        "languageName",
+        "os",
+        "cpu",
+        "preferGlobal",
+        "publishConfig",
+        "icon",
+        "badges",
+        "galleryBanner",
+        "preview",
+        "markdown",
+        "pnpm",
+    ],
+    sorted: &[
+        "dependencies",
+        "devDependencies",
+        "dependenciesMeta",
+        "peerDependencies",
+        "peerDependenciesMeta",
+        "optionalDependencies",
+        "resolutions",
+        "overrides",
+        "engines",
+    ],
+    platform_first: false,
+};
+
+/// The order of the properties in the Composer schema.
+const COMPOSER_JSON: Preset = Preset {
+    fields: &[
+        "$schema",
+        "name",
+        "type",
+        "description",
+        "keywords",
+        "homepage",
+        "readme",
+        "version",
+        "time",
+        "license",
+        "authors",
+        "support",
+        "funding",
+        "require",
+        "require-dev",
+        "conflict",
+        "replace",
+        "provide",
+        "suggest",
+        "autoload",
+        "autoload-dev",
+        "include-path",
+        "target-dir",
+        "minimum-stability",
+        "prefer-stable",
+        "repositories",
+        "config",
+        "scripts",
+        "scripts-descriptions",
+        "extra",
+        "bin",
+        "archive",
+        "abandoned",
+        "non-feature-branches",
+    ],
+    sorted: &[
+        "require",
+        "require-dev",
+        "conflict",
+        "replace",
+        "provide",
+        "suggest",
+    ],
+    platform_first: true,
+};
+
+const TSCONFIG_JSON: Preset = Preset {
+    fields: &[
+        "$schema",
+        "display",
+        "extends",
+        "compilerOptions",
+        "files",
+        "include",
+        "exclude",
+        "references",
+        "watchOptions",
+        "typeAcquisition",
+        "ts-node",
+    ],
+    sorted: &[],
+    platform_first: false,
+};
+
+const ESLINTRC_JSON: Preset = Preset {
+    fields: &[
+        "$schema",
+        "root",
+        "ignorePatterns",
+        "extends",
+        "plugins",
+        "parser",
+        "parserOptions",
+        "env",
+        "globals",
+        "settings",
+        "rules",
+        "overrides",
+    ],
+    sorted: &["env", "globals", "rules"],
+    platform_first: false,
+};
+
+fn preset_for(path: &Path) -> Option<&'static Preset> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "package.json" => Some(&PACKAGE_JSON),
+        "composer.json" => Some(&COMPOSER_JSON),
+        ".eslintrc.json" => Some(&ESLINTRC_JSON),
+        _ if name.ends_with(".json")
+            && (name.starts_with("tsconfig") || name.starts_with("jsconfig")) =>
+        {
+            Some(&TSCONFIG_JSON)
+        }
+        _ => None,
+    }
+}
+
+fn is_platform_package(name: &str) -> bool {
+    matches!(
+        name,
+        "php" | "php-64bit" | "php-ipv6" | "php-zts" | "php-debug" | "hhvm" | "composer"
+    ) || name.starts_with("ext-")
+        || name.starts_with("lib-")
+        || name.starts_with("composer-")
+}
+
 pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let res =
-        json::format_text(path, text, &CONF).map_err(|e| FormatError::Message(e.to_string()))?;
-    Ok(res.map(ensure_newline))
+    format_with_options(path, text, &options::for_path(path).json)
+}
+
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &JsonOptions,
+) -> Result<Option<String>, FormatError> {
+    if opts.canonical {
+        return canonicalize(text);
+    }
+    let preset = opts.presets.then(|| preset_for(path)).flatten();
+    if !(opts.sort_keys || opts.normalize || opts.reject_duplicate_keys || preset.is_some()) {
+        let res = json::format_text(&dprint_path(path), text, &CONF)
+            .map_err(|e| FormatError::Message(e.to_string()))?;
+        return Ok(res.map(ensure_newline));
+    }
+
+    let ast = parse_to_ast(text, &CollectOptions::default(), &ParseOptions::default())
+        .map_err(|e| FormatError::Message(e.to_string()))?;
+    let Some(root) = ast.value else {
+        return Ok(None);
+    };
+    if opts.reject_duplicate_keys {
+        check_duplicates(text, &root)?;
+    }
+    let source = if opts.sort_keys || opts.normalize || preset.is_some() {
+        Rewriter { text, opts, preset }.document(&root)
+    } else {
+        text.to_string()
+    };
+    let res = json::format_text(&dprint_path(path), &source, &CONF)
+        .map_err(|e| FormatError::Message(e.to_string()))?;
+    let out = ensure_newline(res.unwrap_or(source));
+    Ok((out != text).then_some(out))
+}
+
+/// Known JSONC files are handed to dprint as `.jsonc` so that, like real
+/// `.jsonc` files, their multi-line objects keep trailing commas.
+fn dprint_path(path: &Path) -> Cow<'_, Path> {
+    if is_known_jsonc(path) {
+        Cow::Owned(path.with_extension("jsonc"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+fn is_known_jsonc(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if !name.ends_with(".json") {
+        return false;
+    }
+    let in_dir = |dir: &str| {
+        path.parent()
+            .and_then(|p| p.file_name())
+            .is_some_and(|p| p == dir)
+    };
+    JSONC_FILES.contains(&name.as_str())
+        || name.starts_with("tsconfig.")
+        || name.starts_with("jsconfig.")
+        || in_dir(".vscode")
+        || in_dir(".devcontainer")
+}
+
+fn check_duplicates(text: &str, value: &Value) -> Result<(), FormatError> {
+    match value {
+        Value::Object(obj) => {
+            let mut seen = HashSet::new();
+            for prop in &obj.properties {
+                let name = prop.name.as_str();
+                if !seen.insert(name) {
+                    let line = text[..prop.range.start].matches('\n').count() + 1;
+                    return Err(FormatError::Message(format!(
+                        "duplicate key \"{name}\" on line {line}"
+                    )));
+                }
+                check_duplicates(text, &prop.value)?;
+            }
+            Ok(())
+        }
+        Value::Array(arr) => arr
+            .elements
+            .iter()
+            .try_for_each(|v| check_duplicates(text, v)),
+        _ => Ok(()),
+    }
+}
+
+/// RFC 8785: strict JSON in, canonical bytes out.
+fn canonicalize(text: &str) -> Result<Option<String>, FormatError> {
+    let strict = ParseOptions {
+        allow_comments: false,
+        allow_loose_object_property_names: false,
+        allow_trailing_commas: false,
+    };
+    let ast = parse_to_ast(text, &CollectOptions::default(), &strict)
+        .map_err(|e| FormatError::Message(e.to_string()))?;
+    let Some(root) = ast.value else {
+        return Ok(None);
+    };
+    check_duplicates(text, &root)?;
+    let mut out = String::new();
+    canonical(&root, &mut out)?;
+    Ok((out != text).then_some(out))
+}
+
+fn canonical(value: &Value, out: &mut String) -> Result<(), FormatError> {
+    match value {
+        Value::Object(obj) => {
+            let mut props: Vec<_> = obj.properties.iter().collect();
+            // Keys are compared as UTF-16 code units.
+            props.sort_by(|a, b| {
+                a.name
+                    .as_str()
+                    .encode_utf16()
+                    .cmp(b.name.as_str().encode_utf16())
+            });
+            out.push('{');
+            for (i, prop) in props.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&quote(prop.name.as_str()));
+                out.push(':');
+                canonical(&prop.value, out)?;
+            }
+            out.push('}');
+        }
+        Value::Array(arr) => {
+            out.push('[');
+            for (i, v) in arr.elements.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                canonical(v, out)?;
+            }
+            out.push(']');
+        }
+        Value::StringLit(s) => out.push_str(&quote(&s.value)),
+        Value::NumberLit(n) => {
+            let parsed: f64 = n
+                .value
+                .parse()
+                .map_err(|_| FormatError::Message(format!("number {} is out of range", n.value)))?;
+            if !parsed.is_finite() {
+                return Err(FormatError::Message(format!(
+                    "number {} is out of range",
+                    n.value
+                )));
+            }
+            out.push_str(&es6_number(parsed));
+        }
+        Value::BooleanLit(b) => out.push_str(if b.value { "true" } else { "false" }),
+        Value::NullKeyword(_) => out.push_str("null"),
+    }
+    Ok(())
+}
+
+/// ECMAScript `Number.prototype.toString`, as RFC 8785 requires.
+fn es6_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    // `{:e}` gives the shortest digits that round-trip, e.g. `1.2345e6`.
+    let sci = format!("{:e}", value.abs());
+    let (mantissa, exp) = sci.split_once('e').expect("exponent");
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exp.parse::<i32>().expect("exponent") + 1;
+    let body = if k <= n && n <= 21 {
+        format!("{digits}{}", "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{digits}", "0".repeat((-n) as usize))
+    } else {
+        let e = n - 1;
+        let sign = if e >= 0 { '+' } else { '-' };
+        let m = if k == 1 {
+            digits
+        } else {
+            format!("{}.{}", &digits[..1], &digits[1..])
+        };
+        format!("{m}e{sign}{}", e.abs())
+    };
+    if value < 0.0 {
+        format!("-{body}")
+    } else {
+        body
+    }
+}
+
+/// JSON string with only the escapes RFC 8785 requires.
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Comments in the text between two nodes (whitespace, `,` and `:` only).
+fn comments(gap: &str) -> Vec<&str> {
+    let bytes = gap.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"//") {
+            let end = gap[i..].find('\n').map_or(gap.len(), |e| i + e);
+            found.push(gap[i..end].trim_end());
+            i = end;
+        } else if bytes[i..].starts_with(b"/*") {
+            let end = gap[i + 2..].find("*/").map_or(gap.len(), |e| i + e + 4);
+            found.push(&gap[i..end]);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    found
+}
+
+/// An object member or array element with the comments around it.
+struct Member<'t> {
+    key: Option<String>,
+    lead: Vec<&'t str>,
+    body: String,
+    /// Comments after the member on the same line.
+    trailing: Vec<&'t str>,
+}
+
+/// Re-emits the document with sorted keys and/or normalized scalars, keeping
+/// comments and which containers were multi-line; dprint does the layout.
+struct Rewriter<'t> {
+    text: &'t str,
+    opts: &'t JsonOptions,
+    preset: Option<&'static Preset>,
+}
+
+/// Where a value sits, as far as presets are concerned.
+#[derive(Clone, Copy)]
+enum Scope<'a> {
+    Root,
+    Field(&'a str),
+    Nested,
+}
+
+/// How the members of a container are reordered.
+enum Order {
+    Keep,
+    /// `sort-keys` with `priority-keys` first.
+    Priority,
+    Fields(&'static [&'static str]),
+    Sorted {
+        platform_first: bool,
+    },
+}
+
+impl<'t> Rewriter<'t> {
+    fn document(&self, root: &Value) -> String {
+        let mut out = String::new();
+        for c in comments(&self.text[..root.start()]) {
+            out.push_str(c);
+            out.push('\n');
+        }
+        out.push_str(&self.value(root, Scope::Root));
+        for c in comments(&self.text[root.end()..]) {
+            out.push('\n');
+            out.push_str(c);
+        }
+        out.push('\n');
+        out
+    }
+
+    fn value(&self, value: &Value, scope: Scope) -> String {
+        match value {
+            Value::Object(obj) => self.object(obj, scope),
+            Value::Array(arr) => {
+                let members = arr
+                    .elements
+                    .iter()
+                    .map(|v| (v.range(), None, self.value(v, Scope::Nested)))
+                    .collect();
+                self.container(arr.range, members, ('[', ']'), Order::Keep)
+            }
+            Value::StringLit(s) if self.opts.normalize => quote(&s.value),
+            Value::NumberLit(n) if self.opts.normalize => normalize_number(n.value),
+            other => self.text[other.start()..other.end()].to_string(),
+        }
+    }
+
+    fn object(&self, obj: &Object, scope: Scope) -> String {
+        let order = match (self.preset, scope) {
+            (Some(preset), Scope::Root) => Order::Fields(preset.fields),
+            (Some(preset), Scope::Field(name)) if preset.sorted.contains(&name) => Order::Sorted {
+                platform_first: preset.platform_first,
+            },
+            _ if self.opts.sort_keys => Order::Priority,
+            _ => Order::Keep,
+        };
+        let members = obj
+            .properties
+            .iter()
+            .map(|prop| {
+                let mut body = match &prop.name {
+                    ObjectPropName::String(s) if self.opts.normalize => quote(&s.value),
+                    name => self.text[name.start()..name.end()].to_string(),
+                };
+                body.push_str(": ");
+                for c in comments(&self.text[prop.name.end()..prop.value.start()]) {
+                    body.push_str(c);
+                    body.push(if c.starts_with("//") { '\n' } else { ' ' });
+                }
+                let child = match scope {
+                    Scope::Root => Scope::Field(prop.name.as_str()),
+                    _ => Scope::Nested,
+                };
+                body.push_str(&self.value(&prop.value, child));
+                (prop.range, Some(prop.name.as_str().to_string()), body)
+            })
+            .collect();
+        self.container(obj.range, members, ('{', '}'), order)
+    }
+
+    fn container(
+        &self,
+        range: Range,
+        items: Vec<(Range, Option<String>, String)>,
+        (open, close): (char, char),
+        order: Order,
+    ) -> String {
+        let text = self.text;
+        let first = items.first().map_or(range.end, |(r, _, _)| r.start);
+        let multiline = text[range.start..first].contains('\n');
+
+        let mut members: Vec<Member> = Vec::new();
+        let mut prev = range.start + 1;
+        for (r, key, body) in items {
+            let gap = &text[prev..r.start];
+            let rest = split_trailing(gap, members.last_mut());
+            members.push(Member {
+                key,
+                lead: comments(rest),
+                body,
+                trailing: Vec::new(),
+            });
+            prev = r.end;
+        }
+        let dangling = comments(split_trailing(
+            &text[prev..range.end - 1],
+            members.last_mut(),
+        ));
+
+        let key = |m: &Member| m.key.clone().unwrap_or_default();
+        match order {
+            Order::Keep => {}
+            Order::Priority => members.sort_by_cached_key(|m| {
+                let key = key(m);
+                let priority = self.opts.priority_keys.iter().position(|p| *p == key);
+                (priority.unwrap_or(usize::MAX), key)
+            }),
+            Order::Fields(fields) => members.sort_by_cached_key(|m| {
+                let key = key(m);
+                fields.iter().position(|f| *f == key).unwrap_or(usize::MAX)
+            }),
+            Order::Sorted { platform_first } => members.sort_by_cached_key(|m| {
+                let key = key(m);
+                (!(platform_first && is_platform_package(&key)), key)
+            }),
+        }
+
+        let mut out = String::new();
+        out.push(open);
+        if multiline {
+            out.push('\n');
+        }
+        let count = members.len();
+        for (i, m) in members.into_iter().enumerate() {
+            for c in m.lead {
+                out.push_str(c);
+                out.push(if multiline || c.starts_with("//") {
+                    '\n'
+                } else {
+                    ' '
+                });
+            }
+            out.push_str(&m.body);
+            if i + 1 < count {
+                out.push(',');
+            }
+            for c in &m.trailing {
+                out.push(' ');
+                out.push_str(c);
+            }
+            if multiline || m.trailing.iter().any(|c| c.starts_with("//")) {
+                out.push('\n');
+            } else if i + 1 < count {
+                out.push(' ');
+            }
+        }
+        for c in dangling {
+            out.push_str(c);
+            out.push('\n');
+        }
+        out.push(close);
+        out
+    }
+}
+
+/// Give comments on the previous member's line to that member; returns the
+/// rest of the gap.
+fn split_trailing<'t>(gap: &'t str, previous: Option<&mut Member<'t>>) -> &'t str {
+    match (previous, gap.split_once('\n')) {
+        (Some(member), Some((same_line, rest))) => {
+            member.trailing = comments(same_line);
+            rest
+        }
+        (Some(member), None) => {
+            member.trailing = comments(gap);
+            ""
+        }
+        (None, _) => gap,
+    }
+}
+
+/// Shortest form of a number; negative zero, integers too long for an `f64`
+/// and numbers an `f64` would round stay as written.
+fn normalize_number(raw: &str) -> String {
+    let integer = raw
+        .trim_start_matches('-')
+        .bytes()
+        .all(|b| b.is_ascii_digit());
+    let significant = raw.trim_start_matches(['-', '0']).len();
+    match raw.parse::<f64>() {
+        Ok(value) if value == 0.0 && value.is_sign_negative() => raw.to_string(),
+        Ok(value) if value.is_finite() && !(integer && significant > 15) => {
+            let short = es6_number(value);
+            if significant_digits(&short) == significant_digits(raw) {
+                short
+            } else {
+                raw.to_string()
+            }
+        }
+        _ => raw.to_string(),
+    }
+}
+
+/// The digits of a number's mantissa without leading or trailing zeros.
+fn significant_digits(number: &str) -> String {
+    let mantissa = number.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    digits.trim_matches('0').to_string()
 }
@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use anyhow::Result;
+use dprint_plugin_json as json;
+use dprint_plugin_json::configuration::{Configuration, ConfigurationBuilder, TrailingCommaKind};
+use once_cell::sync::Lazy;
+
+use super::{FormatError, ensure_newline};
+
+/// JSON5 keeps trailing commas the way they were written.
+static CONF: Lazy<Configuration> = Lazy::new(|| {
+    ConfigurationBuilder::new()
+        .trailing_commas(TrailingCommaKind::Maintain)
+        .build()
+});
+
+/// JSON5 formatter. dprint reads comments, hex and `+` numbers but rejects
+/// `.5`, `5.`, `Infinity`, `NaN` and escapes such as line continuations, and
+/// would quote unquoted keys and single-quoted strings; all of those are
+/// swapped for placeholders while it formats and restored as written.
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    let (masked, literals) = mask(text);
+    let res =
+        json::format_text(path, &masked, &CONF).map_err(|e| FormatError::Message(e.to_string()))?;
+    Ok(res.map(|out| {
+        let out = literals.iter().enumerate().fold(out, |out, (i, literal)| {
+            let placeholder = placeholder(i);
+            out.replace(&format!("\"{placeholder}\""), literal)
+                .replace(&placeholder, literal)
+        });
+        ensure_newline(out)
+    }))
+}
+
+/// A hex literal dprint prints as written (and quotes in key position);
+/// fixed width so none is a prefix of another.
+fn placeholder(i: usize) -> String {
+    format!("0x4a534f4e35{i:06x}")
+}
+
+fn mask(text: &str) -> (String, Vec<String>) {
+    let bytes = text.as_bytes();
+    let mut out = String::with_capacity(text.len());
+    let mut literals = Vec::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let masked = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = text[i..].find('\n').map_or(text.len(), |e| i + e);
+                false
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..].find("*/").map_or(text.len(), |e| i + e + 4);
+                false
+            }
+            quote @ (b'"' | b'\'') => {
+                i += 1;
+                let mut plain = true;
+                while i < bytes.len() && bytes[i] != quote {
+                    if bytes[i] == b'\\' {
+                        plain &= bytes.get(i + 1).is_some_and(|c| b"\"\\/bfnrtu".contains(c));
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                quote == b'\'' || !plain
+            }
+            c if c.is_ascii_alphanumeric() || matches!(c, b'.' | b'+' | b'-' | b'_' | b'$') => {
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || matches!(bytes[i], b'.' | b'_' | b'$')
+                        || (matches!(bytes[i], b'+' | b'-') && matches!(bytes[i - 1], b'e' | b'E')))
+                {
+                    i += 1;
+                }
+                json5_only(&text[start..i])
+            }
+            _ => {
+                i += 1;
+                false
+            }
+        };
+        if masked {
+            out.push_str(&text[copied..start]);
+            out.push_str(&placeholder(literals.len()));
+            literals.push(text[start..i].to_string());
+            copied = i;
+        }
+    }
+    out.push_str(&text[copied..]);
+    (out, literals)
+}
+
+/// Unquoted keys, `Infinity`, `NaN`, and decimals with a leading or
+/// trailing point.
+fn json5_only(token: &str) -> bool {
+    if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$') {
+        return !matches!(token, "true" | "false" | "null");
+    }
+    let t = token.trim_start_matches(['+', '-']);
+    if t == "Infinity" || t == "NaN" {
+        return true;
+    }
+    let decimal = t.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && !t.starts_with("0x")
+        && !t.starts_with("0X");
+    let mantissa = t.split(['e', 'E']).next().unwrap_or(t);
+    decimal && (mantissa.starts_with('.') || mantissa.ends_with('.'))
+}
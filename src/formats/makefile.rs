@@ -1,71 +1,223 @@
 use std::path::Path;
 
 use anyhow::Result;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, collapse_whitespace, ensure_newline, options};
 
-/// Heuristic pretty formatter for Makefiles:
-/// - trims行尾空白
-/// - 壓縮連續空行為 1
-/// - recipe 行強制以 tab 開頭（偵測上一個目標行）
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let mut out = String::new();
-    let mut prev_blank = false;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MakefileOptions {
+    /// Spaces per conditional (`ifeq` … `endif`) nesting level.
+    pub indent: usize,
+    /// Line up the operators of consecutive variable assignments.
+    pub align_assignments: bool,
+}
+
+impl Default for MakefileOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            align_assignments: false,
+        }
+    }
+}
+
+const ASSIGN_OPS: &[&str] = &[":::=", "::=", ":=", "?=", "+=", "!=", "="];
+const CONDITIONALS: &[&str] = &["ifeq", "ifneq", "ifdef", "ifndef"];
+const ASSIGN_PREFIXES: &[&str] = &["export", "override", "private", "unexport"];
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).makefile)
+}
+
+/// Makefile formatter:
+/// - trims行尾空白、壓縮連續空行為 1
+/// - 辨識變數指派（`=`, `:=`, `::=`, `?=`, `+=`, `!=`）、target-specific 變數與規則
+/// - `ifeq`/`ifdef` 等條件式依層級縮排，`define … endef` 內容原樣保留
+/// - recipe 行強制以 tab 開頭；變數的續行不會被當成 recipe
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &MakefileOptions,
+) -> Result<Option<String>, FormatError> {
+    let physical: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut items: Vec<Item> = Vec::new();
+    let mut depth = 0usize;
     let mut in_rule = false;
+    let mut i = 0;
 
-    for line in text.lines() {
-        let trimmed_end = line.trim_end();
-        let trimmed_start = trimmed_end.trim_start();
+    while i < physical.len() {
+        let line = physical[i];
+        // Group backslash continuations into one logical line.
+        let mut end = i;
+        while physical[end].ends_with('\\') && end + 1 < physical.len() {
+            end += 1;
+        }
+        let rest: Vec<String> = physical[i + 1..=end]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        i = end + 1;
 
-        if trimmed_start.is_empty() {
-            if prev_blank {
-                continue;
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            if !matches!(items.last(), Some(Item::Blank)) {
+                items.push(Item::Blank);
             }
-            prev_blank = true;
-            out.push('\n');
             continue;
         }
-        prev_blank = false;
 
-        if trimmed_start.starts_with('#') {
-            out.push_str(trimmed_start);
-            out.push('\n');
+        if line.starts_with('\t') && in_rule {
+            items.push(Item::Verbatim(
+                std::iter::once(line.to_string()).chain(rest).collect(),
+            ));
             continue;
         }
 
-        // rule detection: line with ':' not starting with tab/space
-        if !trimmed_end.starts_with('\t')
-            && trimmed_end.contains(':')
-            && !trimmed_end.starts_with(' ')
-        {
-            if let Some((targets, deps_raw)) = trimmed_end.split_once(':') {
-                let deps = deps_raw.split_whitespace().collect::<Vec<_>>().join(" ");
-                if deps.is_empty() {
-                    out.push_str(targets.trim_end());
-                    out.push(':');
-                } else {
-                    out.push_str(targets.trim_end());
-                    out.push_str(": ");
-                    out.push_str(&deps);
+        if trimmed.starts_with('#') {
+            items.push(Item::Line(depth, trimmed.to_string(), rest));
+            continue;
+        }
+
+        let keyword = trimmed
+            .split(|c: char| c.is_whitespace() || c == '(')
+            .next()
+            .unwrap_or_default();
+
+        let is_define = keyword == "define"
+            || (ASSIGN_PREFIXES.contains(&keyword)
+                && trimmed.split_whitespace().nth(1) == Some("define"));
+        if is_define {
+            let mut body = vec![pad(depth, opts) + collapse_whitespace(trimmed, &[]).as_str()];
+            body.extend(rest);
+            while i < physical.len() {
+                let l = physical[i];
+                i += 1;
+                if l.trim() == "endef" {
+                    body.push(pad(depth, opts) + "endef");
+                    break;
                 }
-            } else {
-                out.push_str(trimmed_end);
+                body.push(l.to_string());
             }
-            out.push('\n');
-            in_rule = true;
+            items.push(Item::Verbatim(body));
+            in_rule = false;
             continue;
         }
 
-        if in_rule && !trimmed_end.starts_with('\t') && !trimmed_end.is_empty() {
-            // recipe line: enforce leading tab
-            out.push('\t');
-            out.push_str(trimmed_start);
-            out.push('\n');
+        if CONDITIONALS.contains(&keyword) {
+            items.push(Item::Line(depth, normalize_conditional(trimmed), rest));
+            depth += 1;
+            continue;
+        }
+        if keyword == "else" {
+            let outer = depth.saturating_sub(1);
+            let tail = trimmed["else".len()..].trim();
+            let line = if tail.is_empty() {
+                "else".to_string()
+            } else {
+                format!("else {}", normalize_conditional(tail))
+            };
+            items.push(Item::Line(outer, line, rest));
+            continue;
+        }
+        if keyword == "endif" {
+            depth = depth.saturating_sub(1);
+            items.push(Item::Line(depth, "endif".to_string(), rest));
             continue;
         }
 
-        out.push_str(trimmed_end);
-        out.push('\n');
+        match classify(trimmed) {
+            Statement::Assignment { name, op, value } => {
+                items.push(Item::Assign {
+                    depth,
+                    name,
+                    op,
+                    value,
+                    rest,
+                });
+                in_rule = false;
+            }
+            Statement::Rule {
+                targets,
+                colon,
+                prereqs,
+            } => {
+                let (line, target_specific) = match classify(&prereqs) {
+                    Statement::Assignment { name, op, value } => {
+                        (format!("{targets}{colon} {name} {op} {value}"), true)
+                    }
+                    _ if prereqs.trim().is_empty() => (format!("{targets}{colon}"), false),
+                    _ => (
+                        format!("{targets}{colon} {}", normalize_prereqs(&prereqs)),
+                        false,
+                    ),
+                };
+                items.push(Item::Line(depth, line, rest));
+                in_rule = !target_specific && !targets.starts_with(".PHONY");
+            }
+            Statement::Other if in_rule && line.starts_with(' ') => {
+                // Space-indented recipe: make only accepts tabs.
+                let recipe = format!("\t{trimmed}");
+                items.push(Item::Verbatim(
+                    std::iter::once(recipe).chain(rest).collect(),
+                ));
+            }
+            Statement::Other => {
+                let normalized = if matches!(keyword, "include" | "-include" | "sinclude" | "vpath")
+                {
+                    collapse_whitespace(trimmed, &['"', '\''])
+                } else {
+                    trimmed.to_string()
+                };
+                items.push(Item::Line(depth, normalized, rest));
+                in_rule = false;
+            }
+        }
+    }
+
+    let widths = if opts.align_assignments {
+        assignment_widths(&items)
+    } else {
+        vec![0; items.len()]
+    };
+
+    let mut out = String::new();
+    for (item, width) in items.iter().zip(widths) {
+        match item {
+            Item::Blank => out.push('\n'),
+            Item::Verbatim(lines) => {
+                for l in lines {
+                    out.push_str(l);
+                    out.push('\n');
+                }
+            }
+            Item::Line(depth, line, rest) => {
+                out.push_str(&pad(*depth, opts));
+                out.push_str(line);
+                out.push('\n');
+                push_rest(&mut out, rest);
+            }
+            Item::Assign {
+                depth,
+                name,
+                op,
+                value,
+                rest,
+            } => {
+                out.push_str(&pad(*depth, opts));
+                out.push_str(name);
+                let name_len = name.chars().count();
+                out.push_str(&" ".repeat(width.saturating_sub(name_len) + 1));
+                out.push_str(op);
+                if !value.is_empty() {
+                    out.push(' ');
+                    out.push_str(value);
+                }
+                out.push('\n');
+                push_rest(&mut out, rest);
+            }
+        }
     }
 
     if out == text {
@@ -74,3 +226,163 @@ pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
         Ok(Some(ensure_newline(out)))
     }
 }
+
+#[derive(Debug)]
+enum Item {
+    Blank,
+    /// Lines printed exactly as given (recipes, `define` bodies).
+    Verbatim(Vec<String>),
+    /// A directive, rule or comment at a conditional depth, followed by its
+    /// continuation lines.
+    Line(usize, String, Vec<String>),
+    Assign {
+        depth: usize,
+        name: String,
+        op: &'static str,
+        value: String,
+        rest: Vec<String>,
+    },
+}
+
+enum Statement {
+    Assignment {
+        name: String,
+        op: &'static str,
+        value: String,
+    },
+    Rule {
+        targets: String,
+        colon: &'static str,
+        prereqs: String,
+    },
+    Other,
+}
+
+/// Classify a logical line by the first `:` or `=` outside `$(…)`/`${…}`.
+fn classify(line: &str) -> Statement {
+    let bytes = line.as_bytes();
+    let mut depth = 0i32;
+    for (idx, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'{' => depth += 1,
+            b')' | b'}' => depth -= 1,
+            b'#' if depth == 0 => return Statement::Other,
+            b'=' | b':' if depth == 0 => {
+                let rest = &line[idx..];
+                if let Some(op) = ASSIGN_OPS.iter().find(|op| rest.starts_with(**op)) {
+                    return assignment(line, idx, op);
+                }
+                let colon = if rest.starts_with("::") { "::" } else { ":" };
+                return Statement::Rule {
+                    targets: collapse_whitespace(&line[..idx], &[]),
+                    colon,
+                    prereqs: line[idx + colon.len()..].to_string(),
+                };
+            }
+            // `?=`, `+=` and `!=` put their first byte before the `=`.
+            b'?' | b'+' | b'!' if depth == 0 && bytes.get(idx + 1) == Some(&b'=') => {
+                let op = ASSIGN_OPS
+                    .iter()
+                    .find(|op| line[idx..].starts_with(**op))
+                    .expect("two-byte assignment operator");
+                return assignment(line, idx, op);
+            }
+            _ => {}
+        }
+    }
+    Statement::Other
+}
+
+fn assignment(line: &str, idx: usize, op: &'static str) -> Statement {
+    let name = line[..idx].trim();
+    let first = name.split_whitespace().next().unwrap_or_default();
+    // `export FOO = 1` is an assignment; `foo bar = 1` is not.
+    let valid = !name.is_empty()
+        && (!name.contains(char::is_whitespace) || ASSIGN_PREFIXES.contains(&first));
+    if !valid {
+        return Statement::Other;
+    }
+    Statement::Assignment {
+        name: collapse_whitespace(name, &[]),
+        op,
+        value: line[idx + op.len()..].trim().to_string(),
+    }
+}
+
+/// `ifeq($(A),b)` → `ifeq ($(A),b)` and collapsed spacing after the keyword.
+fn normalize_conditional(line: &str) -> String {
+    let keyword_len = line
+        .find(|c: char| c.is_whitespace() || c == '(' || c == '"' || c == '\'')
+        .unwrap_or(line.len());
+    let (keyword, args) = line.split_at(keyword_len);
+    let args = args.trim();
+    if args.is_empty() {
+        keyword.to_string()
+    } else {
+        format!("{keyword} {args}")
+    }
+}
+
+fn normalize_prereqs(prereqs: &str) -> String {
+    match prereqs.split_once(';') {
+        Some((deps, recipe)) => {
+            let deps = collapse_whitespace(deps, &[]);
+            if deps.is_empty() {
+                format!("; {}", recipe.trim())
+            } else {
+                format!("{deps}; {}", recipe.trim())
+            }
+        }
+        None => collapse_whitespace(prereqs, &[]),
+    }
+}
+
+/// Operator column per item: consecutive single-line assignments at the same
+/// depth share the width of their longest name.
+fn assignment_widths(items: &[Item]) -> Vec<usize> {
+    let mut widths = vec![0; items.len()];
+    let mut group: Vec<usize> = Vec::new();
+    let mut group_depth = usize::MAX;
+    let flush = |group: &mut Vec<usize>, widths: &mut Vec<usize>| {
+        let max = group
+            .iter()
+            .filter_map(|&i| match &items[i] {
+                Item::Assign { name, .. } => Some(name.chars().count()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        for &i in group.iter() {
+            widths[i] = max;
+        }
+        group.clear();
+    };
+    for (idx, item) in items.iter().enumerate() {
+        match item {
+            Item::Assign { depth, rest, .. } if rest.is_empty() => {
+                if *depth != group_depth {
+                    flush(&mut group, &mut widths);
+                    group_depth = *depth;
+                }
+                group.push(idx);
+            }
+            _ => {
+                flush(&mut group, &mut widths);
+                group_depth = usize::MAX;
+            }
+        }
+    }
+    flush(&mut group, &mut widths);
+    widths
+}
+
+fn pad(depth: usize, opts: &MakefileOptions) -> String {
+    " ".repeat(depth * opts.indent)
+}
+
+fn push_rest(out: &mut String, rest: &[String]) {
+    for l in rest {
+        out.push_str(l);
+        out.push('\n');
+    }
+}
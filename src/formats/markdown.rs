@@ -1,54 +1,834 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use dprint_plugin_markdown as md;
-use once_cell::sync::Lazy;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, LinkType, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
 
-use super::{FormatError, FormatKind, detect_kind_from_label, ensure_newline, format_dispatch};
+use super::{
+    FormatError, FormatKind, detect_kind_from_label, ensure_newline, format_dispatch, options,
+    toml_fmt, yaml,
+};
 
-static CONF: Lazy<md::configuration::Configuration> =
-    Lazy::new(|| md::configuration::ConfigurationBuilder::new().build());
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MarkdownOptions {
+    pub text_wrap: TextWrap,
+    /// Width used by `text-wrap = "always"`.
+    pub line_width: u32,
+    /// Marker for `_emphasis_`.
+    pub emphasis: Emphasis,
+    /// Marker for `**strong**`.
+    pub strong: Emphasis,
+    /// Bullet of unordered lists; nested lists alternate with the other one.
+    pub list_marker: ListMarker,
+    pub ordered_list: Numbering,
+    /// Format `---` YAML and `+++` TOML front matter with those formatters.
+    pub front_matter: bool,
+    /// Extra fence tags mapped to a language label; `none` leaves the block alone.
+    pub fences: BTreeMap<String, String>,
+    /// Re-pad tables counting emoji sequences as two columns, as editors do.
+    pub align_tables: bool,
+    /// Move link reference definitions to the end sorted by label, dropping
+    /// ones shadowed by an earlier definition of the same label.
+    pub sort_link_definitions: bool,
+    /// Rewrite inline links `[text](url)` as reference links `[text][1]`.
+    pub reference_links: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            text_wrap: TextWrap::Maintain,
+            line_width: 80,
+            emphasis: Emphasis::Underscores,
+            strong: Emphasis::Asterisks,
+            list_marker: ListMarker::Dash,
+            ordered_list: Numbering::Auto,
+            front_matter: true,
+            fences: BTreeMap::new(),
+            align_tables: false,
+            sort_link_definitions: false,
+            reference_links: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextWrap {
+    /// Re-flow paragraphs to `line-width`.
+    Always,
+    /// Join each paragraph onto one line.
+    Never,
+    /// Keep line breaks as written.
+    Maintain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Emphasis {
+    Underscores,
+    Asterisks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ListMarker {
+    Dash,
+    Asterisk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Numbering {
+    /// `1. 1. 1.` when the second item is numbered 1, ascending otherwise.
+    Auto,
+    Ascending,
+    /// Every item numbered 1; lists starting at another number ascend.
+    One,
+}
+
+fn configuration(opts: &MarkdownOptions) -> md::configuration::Configuration {
+    use md::configuration::{EmphasisKind, StrongKind, UnorderedListKind};
+    md::configuration::ConfigurationBuilder::new()
+        .line_width(opts.line_width)
+        .text_wrap(match opts.text_wrap {
+            TextWrap::Always => md::configuration::TextWrap::Always,
+            TextWrap::Never => md::configuration::TextWrap::Never,
+            TextWrap::Maintain => md::configuration::TextWrap::Maintain,
+        })
+        .emphasis_kind(match opts.emphasis {
+            Emphasis::Underscores => EmphasisKind::Underscores,
+            Emphasis::Asterisks => EmphasisKind::Asterisks,
+        })
+        .strong_kind(match opts.strong {
+            Emphasis::Underscores => StrongKind::Underscores,
+            Emphasis::Asterisks => StrongKind::Asterisks,
+        })
+        .unordered_list_kind(match opts.list_marker {
+            ListMarker::Dash => UnorderedListKind::Dashes,
+            ListMarker::Asterisk => UnorderedListKind::Asterisks,
+        })
+        .build()
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).markdown)
+}
+
+/// Code blocks that fail to format are left as written and reported on stderr.
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &MarkdownOptions,
+) -> Result<Option<String>, FormatError> {
+    let (out, warnings) = format_with_warnings(text, opts)?;
+    for warning in warnings {
+        eprintln!("{}: warning: {warning}", path.display());
+    }
+    Ok(out)
+}
+
+pub fn format_with_warnings(
+    text: &str,
+    opts: &MarkdownOptions,
+) -> Result<(Option<String>, Vec<String>), FormatError> {
+    let mut warnings = Vec::new();
+    let out = format_document(text, 1, opts, &mut warnings)?;
+    Ok(((out != text).then_some(out), warnings))
+}
+
+fn format_document(
+    text: &str,
+    first_line: usize,
+    opts: &MarkdownOptions,
+    warnings: &mut Vec<String>,
+) -> Result<String, FormatError> {
+    let front = opts
+        .front_matter
+        .then(|| split_front_matter(text))
+        .flatten();
+    let body = front.map_or(text, |(_, _, body)| body);
+    let body_line = first_line + text[..text.len() - body.len()].matches('\n').count();
+    let body = match opts.ordered_list {
+        Numbering::Auto => Cow::Borrowed(body),
+        numbering => Cow::Owned(renumber(body, numbering)),
+    };
+    let body = match format_fences(&body, body_line, opts, warnings) {
+        Some(formatted) => Cow::Owned(formatted),
+        None => body,
+    };
+    let body = match opts.sort_link_definitions || opts.reference_links {
+        true => Cow::Owned(rewrite_links(&body, opts)),
+        false => body,
+    };
+
+    // Fenced code was handled above; indented blocks have no language.
+    let mut code_cb = |_: &str, _: &str, _: u32| -> anyhow::Result<Option<String>> { Ok(None) };
+    let body = md::format_text(&body, &configuration(opts), &mut code_cb)
+        .map_err(|e| FormatError::Message(e.to_string()))?
+        .unwrap_or_else(|| body.into_owned());
+    let body = match opts.align_tables {
+        true => align_tables(&body),
+        false => body,
+    };
+    let Some((delimiter, matter, _)) = front else {
+        return Ok(ensure_newline(body));
+    };
+
+    let mut out = format!(
+        "{delimiter}\n{}{delimiter}\n",
+        format_front_matter(delimiter, matter)
+    );
+    if !body.trim().is_empty() {
+        out.push('\n');
+        out.push_str(body.trim_start_matches(['\r', '\n']));
+    }
+    Ok(ensure_newline(out))
+}
+
+struct Fence {
+    start: usize,
+    info: String,
+    code: String,
+    content: Option<Range<usize>>,
+}
+
+/// Formats fenced code ahead of dprint, which only passes the first word of
+/// the info string and cannot say which block failed.
+fn format_fences(
+    text: &str,
+    first_line: usize,
+    opts: &MarkdownOptions,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let mut edits = Vec::new();
+    let mut open: Option<Fence> = None;
+    for (event, range) in Parser::new(text).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                open = Some(Fence {
+                    start: range.start,
+                    info: info.to_string(),
+                    code: String::new(),
+                    content: None,
+                });
+            }
+            Event::Text(code) => {
+                if let Some(fence) = open.as_mut() {
+                    fence.code.push_str(&code);
+                    let start = fence.content.as_ref().map_or(range.start, |c| c.start);
+                    fence.content = Some(start..range.end);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let Some(fence) = open.take() else {
+                    continue;
+                };
+                let info = FenceInfo::parse(&fence.info);
+                let (Some(content), false) = (fence.content.clone(), info.no_format) else {
+                    continue;
+                };
+                let Some(kind) = fence_kind(info.lang, opts) else {
+                    continue;
+                };
+                let line = first_line + text[..fence.start].matches('\n').count();
+                let out = if kind == FormatKind::Markdown {
+                    format_document(&fence.code, line + 1, opts, warnings)
+                        .map_err(|err| warnings.push(code_warning(line, info.lang, &err)))
+                        .ok()
+                } else {
+                    format_code(kind, info.lang, &fence.code, line, warnings)
+                };
+                if let Some(out) = out.filter(|out| *out != fence.code) {
+                    edits.extend(replace_code(text, &fence, content, &out));
+                }
+            }
+            _ => {}
+        }
+    }
+    if edits.is_empty() {
+        return None;
+    }
+    let mut out = text.to_string();
+    for (range, code) in edits.into_iter().rev() {
+        out.replace_range(range, &code);
+    }
+    Some(out)
+}
+
+/// Code inside a list item or block quote carries the container's prefix on
+/// every line; blocks whose lines cannot be mapped back are left alone.
+fn replace_code(
+    text: &str,
+    fence: &Fence,
+    content: Range<usize>,
+    code: &str,
+) -> Option<(Range<usize>, String)> {
+    let fence_line = text[..fence.start].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = continuation(&text[fence_line..fence.start]);
+    let start = text[..content.start].rfind('\n').map_or(0, |i| i + 1);
+    let end = content.end;
+
+    let strip = |line: &'_ str| -> Option<String> {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let rest = body
+            .strip_prefix(prefix.as_str())
+            .or_else(|| body.trim_end().strip_prefix(prefix.trim_end()))?;
+        Some(format!("{rest}{}", &line[body.len()..]))
+    };
+    let original = text[start..end]
+        .split_inclusive('\n')
+        .map(strip)
+        .collect::<Option<String>>()?;
+    if original != fence.code {
+        return None;
+    }
+    let mut out = String::new();
+    for line in code.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            out.push_str(prefix.trim_end());
+        } else {
+            out.push_str(&prefix);
+        }
+        out.push_str(line);
+    }
+    Some((start..end, out))
+}
+
+/// The prefix of the lines after the first in a container block: block
+/// quote markers stay, list markers become spaces.
+fn continuation(prefix: &str) -> String {
+    prefix
+        .chars()
+        .map(|c| if c == '>' { '>' } else { ' ' })
+        .collect()
+}
+
+const PARSE_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_FOOTNOTES)
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS);
+
+/// dprint pads tables too, but its width table counts `⚠️` as one column
+/// and a ZWJ family emoji as three.
+fn align_tables(text: &str) -> String {
+    let mut edits = Vec::new();
+    for (event, range) in Parser::new_ext(text, PARSE_OPTIONS).into_offset_iter() {
+        let Event::Start(Tag::Table(alignments)) = event else {
+            continue;
+        };
+        let start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let end = start + text[start..range.end].trim_end_matches(['\r', '\n']).len();
+        let first = &text[start..range.start];
+        let prefix = continuation(first);
+        let rows: Option<Vec<Vec<String>>> = text[start..end]
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let line = line.strip_prefix(if i == 0 { first } else { prefix.as_str() })?;
+                Some(table_cells(line))
+            })
+            .collect();
+        let Some(rows) = rows.filter(|rows| rows.len() >= 2) else {
+            continue;
+        };
+        let aligned = render_table(&rows, &alignments);
+        let lines: Vec<String> = aligned
+            .iter()
+            .enumerate()
+            .map(|(i, row)| format!("{}{row}", if i == 0 { first } else { prefix.as_str() }))
+            .collect();
+        edits.push((start..end, lines.join("\n")));
+    }
+    let mut out = text.to_string();
+    for (range, table) in edits.into_iter().rev() {
+        out.replace_range(range, &table);
+    }
+    out
+}
+
+/// Cells of a table row split on unescaped pipes, outer pipes removed.
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(inner) if !inner.ends_with('\\') => inner,
+        _ => line,
+    };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in line.chars() {
+        if c == '|' && !escaped {
+            cells.push(std::mem::take(&mut cell).trim().to_string());
+        } else {
+            cell.push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Rows laid out the way dprint does, with `unicode-width` 0.2 widths.
+fn render_table(rows: &[Vec<String>], alignments: &[Alignment]) -> Vec<String> {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(alignments.len());
+    let alignment = |i: usize| alignments.get(i).copied().unwrap_or(Alignment::None);
+    let colons = |i: usize| match alignment(i) {
+        Alignment::None => 0,
+        Alignment::Left | Alignment::Right => 1,
+        Alignment::Center => 2,
+    };
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .enumerate()
+                .filter(|&(row, _)| row != 1)
+                .filter_map(|(_, cells)| cells.get(i))
+                .map(|cell| cell.width())
+                .fold(colons(i) + 1, usize::max)
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    for (row, cells) in rows.iter().enumerate() {
+        let rendered: Vec<String> = (0..columns)
+            .map(|i| {
+                let width = widths[i];
+                if row == 1 {
+                    let (left, right) = match alignment(i) {
+                        Alignment::None => ("", ""),
+                        Alignment::Left => (":", ""),
+                        Alignment::Right => ("", ":"),
+                        Alignment::Center => (":", ":"),
+                    };
+                    return format!("{left}{}{right}", "-".repeat(width - colons(i)));
+                }
+                let cell = cells.get(i).map_or("", String::as_str);
+                let gap = width - cell.width();
+                let before = match alignment(i) {
+                    Alignment::Right => gap,
+                    Alignment::Center if gap > 1 => gap / 2,
+                    _ => 0,
+                };
+                let after = match alignment(i) {
+                    Alignment::Right => 0,
+                    Alignment::Center if gap > 1 => gap - gap / 2,
+                    Alignment::Center => gap.div_ceil(2),
+                    _ => gap,
+                };
+                format!("{}{cell}{}", " ".repeat(before), " ".repeat(after))
+            })
+            .collect();
+        out.push(format!("| {} |", rendered.join(" | ")));
+    }
+    out
+}
+
+struct Definition {
+    label: String,
+    target: String,
+    span: Range<usize>,
+}
+
+/// Link reference definitions and inline links, edited in the source before
+/// dprint tidies the blank lines they leave behind.
+fn rewrite_links(text: &str, opts: &MarkdownOptions) -> String {
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut gaps = Vec::new();
+    let mut links = Vec::new();
+    let mut open: Option<(Range<usize>, usize)> = None;
+    let mut depth = 0;
+    let mut last = 0;
+    for (event, range) in Parser::new_ext(text, PARSE_OPTIONS).into_offset_iter() {
+        if let Some((link, text_end)) = open.as_mut() {
+            if range != *link {
+                *text_end = (*text_end).max(range.end);
+            }
+        }
+        match event {
+            Event::Start(tag) => {
+                if depth == 0 {
+                    gaps.push(last..range.start);
+                }
+                depth += 1;
+                if let Tag::Link {
+                    link_type: LinkType::Inline,
+                    ..
+                } = tag
+                {
+                    open = Some((range.clone(), range.start + 1));
+                }
+            }
+            Event::End(tag) => {
+                depth -= 1;
+                if depth == 0 {
+                    last = range.end;
+                }
+                if tag == TagEnd::Link {
+                    links.extend(open.take());
+                }
+            }
+            _ if depth == 0 => {
+                gaps.push(last..range.start);
+                last = range.end;
+            }
+            _ => {}
+        }
+    }
+    gaps.push(last..text.len());
+
+    let mut definitions: Vec<Definition> = Vec::new();
+    for gap in gaps {
+        for definition in link_definitions(text, gap) {
+            if definitions
+                .iter()
+                .any(|d| label_key(&d.label) == label_key(&definition.label))
+            {
+                edits.push((definition.span, String::new()));
+            } else {
+                definitions.push(definition);
+            }
+        }
+    }
+
+    let existing = definitions.len();
+    if opts.reference_links {
+        for (range, text_end) in links {
+            let Some(target) = text[text_end..range.end]
+                .strip_prefix("](")
+                .and_then(|t| t.strip_suffix(')'))
+            else {
+                continue;
+            };
+            let target = target.split_whitespace().collect::<Vec<_>>().join(" ");
+            let label = match definitions.iter().find(|d| d.target == target) {
+                Some(d) => d.label.clone(),
+                None => {
+                    let label = (1..)
+                        .map(|n: u32| n.to_string())
+                        .find(|n| definitions.iter().all(|d| label_key(&d.label) != *n))
+                        .unwrap_or_default();
+                    definitions.push(Definition {
+                        label: label.clone(),
+                        target,
+                        span: 0..0,
+                    });
+                    label
+                }
+            };
+            edits.push((text_end + 1..range.end, format!("[{label}]")));
+        }
+    }
+
+    let mut moved: Vec<&Definition> = definitions[existing..].iter().collect();
+    if opts.sort_link_definitions {
+        for definition in &definitions[..existing] {
+            edits.push((definition.span.clone(), String::new()));
+        }
+        moved = definitions.iter().collect();
+        moved.sort_by_cached_key(|d| {
+            let number = d.label.parse::<u64>().ok();
+            (number.is_none(), number, label_key(&d.label))
+        });
+    }
+
+    let mut out = text.to_string();
+    edits.sort_by_key(|(range, _)| range.start);
+    for (range, replacement) in edits.into_iter().rev() {
+        out.replace_range(range, &replacement);
+    }
+    if !moved.is_empty() {
+        out.truncate(out.trim_end().len());
+        out.push_str("\n\n");
+        for definition in moved {
+            out.push_str(&format!("[{}]: {}\n", definition.label, definition.target));
+        }
+    }
+    out
+}
+
+/// Definitions in the text between top-level blocks, which holds nothing
+/// else but blank lines.
+fn link_definitions(text: &str, gap: Range<usize>) -> Vec<Definition> {
+    let mut out: Vec<Definition> = Vec::new();
+    let mut pos = gap.start;
+    for line in text[gap.clone()].split_inclusive('\n') {
+        let span = pos..pos + line.len();
+        pos += line.len();
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let head = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("]:"))
+            .filter(|_| line.len() - line.trim_start().len() < 4);
+        match (head, out.last_mut()) {
+            (Some((label, target)), _) => out.push(Definition {
+                label: label.to_string(),
+                target: target.split_whitespace().collect::<Vec<_>>().join(" "),
+                span,
+            }),
+            (None, Some(last)) if last.span.end == span.start => {
+                last.target = format!("{} {}", last.target, trimmed).trim().to_string();
+                last.span.end = span.end;
+            }
+            (None, _) => {}
+        }
+    }
+    out
+}
+
+/// Labels match case-insensitively with whitespace collapsed.
+fn label_key(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
 
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let mut code_cb = |lang: &str, code: &str, _line: u32| -> anyhow::Result<Option<String>> {
-        let Some(kind) = detect_kind_from_label(lang) else {
-            return Ok(None);
+/// The language and attributes of a fence info string: `ts title="a.ts"`,
+/// `rust,ignore`, `js{1,3}`, or the pandoc and R Markdown forms
+/// `{.python .numberLines}` and `{r, echo=FALSE}`.
+struct FenceInfo<'a> {
+    lang: &'a str,
+    no_format: bool,
+}
+
+impl<'a> FenceInfo<'a> {
+    fn parse(info: &'a str) -> Self {
+        let info = info.trim();
+        let info = match info.strip_prefix('{') {
+            Some(inner) => inner
+                .strip_suffix('}')
+                .unwrap_or(inner)
+                .trim_start_matches('.'),
+            None => info,
         };
-        if matches!(kind, FormatKind::Markdown) {
-            return Ok(None);
+        let end = info
+            .find(|c: char| c.is_whitespace() || matches!(c, ',' | '{' | '}'))
+            .unwrap_or(info.len());
+        let (lang, attributes) = info.split_at(end);
+        let no_format = fence_attributes(attributes)
+            .into_iter()
+            .any(|(name, value)| {
+                name.trim_start_matches('.') == "no-format" && value != Some("false")
+            });
+        Self { lang, no_format }
+    }
+}
+
+fn fence_attributes(text: &str) -> Vec<(&str, Option<&str>)> {
+    let separator = |c: char| c.is_whitespace() || matches!(c, ',' | '{' | '}');
+    let mut out = Vec::new();
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start_matches(separator);
+        if rest.is_empty() {
+            return out;
         }
-        let fake_path = fake_path_for_kind(kind);
-        match format_dispatch(kind, &fake_path, code) {
-            Ok(Some(out)) => Ok(Some(out)),
-            Ok(None) => Ok(None),
-            Err(e) => Err(anyhow!(e.to_string())),
+        let end = rest
+            .find(|c: char| separator(c) || c == '=')
+            .unwrap_or(rest.len());
+        let name = &rest[..end];
+        rest = &rest[end..];
+        let value = rest.strip_prefix('=').map(|after| {
+            let (value, next) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let close = after[1..].find(quote).map_or(after.len(), |i| i + 1);
+                    (&after[1..close], &after[(close + 1).min(after.len())..])
+                }
+                _ => after.split_at(after.find(separator).unwrap_or(after.len())),
+            };
+            rest = next;
+            value
+        });
+        out.push((name, value));
+    }
+}
+
+fn fence_kind(tag: &str, opts: &MarkdownOptions) -> Option<FormatKind> {
+    match opts
+        .fences
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(tag))
+    {
+        Some((_, label)) => detect_kind_from_label(label),
+        None => code_kind(tag),
+    }
+}
+
+/// Language of a code block tag, which has a few more spellings than file labels.
+pub(super) fn code_kind(tag: &str) -> Option<FormatKind> {
+    match tag.to_lowercase().as_str() {
+        // Prompts and output, not scripts.
+        "console" | "shell-session" | "sh-session" | "shellsession" | "terminal" => None,
+        "zsh" | "ksh" => Some(FormatKind::Bash),
+        "hcl2" => Some(FormatKind::Hcl),
+        "proto2" | "proto3" => Some(FormatKind::Protobuf),
+        "mjs" | "cjs" => Some(FormatKind::JavaScript),
+        "mts" | "cts" => Some(FormatKind::TypeScript),
+        "containerfile" => Some(FormatKind::Dockerfile),
+        tag => detect_kind_from_label(tag),
+    }
+}
+
+/// Formats an embedded code block starting at `line` of its document; a block
+/// that fails is left as written and reported through `warnings`.
+pub(super) fn format_code(
+    kind: FormatKind,
+    tag: &str,
+    code: &str,
+    line: usize,
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let path = if tag.eq_ignore_ascii_case("jsonc") {
+        PathBuf::from("code.jsonc")
+    } else {
+        fake_path_for_kind(kind)
+    };
+    format_dispatch(kind, &path, code)
+        .map_err(|err| warnings.push(code_warning(line, tag, &err)))
+        .ok()
+        .flatten()
+}
+
+fn code_warning(line: usize, tag: &str, err: &FormatError) -> String {
+    let err = err.to_string();
+    format!(
+        "line {line}: `{tag}` code block left unformatted: {}",
+        err.trim_end()
+    )
+}
+
+/// `(delimiter, content, rest)` of a `---` or `+++` block opening the file.
+pub(super) fn split_front_matter(text: &str) -> Option<(&'static str, &str, &str)> {
+    let delimiter = ["---", "+++"].into_iter().find(|d| text.starts_with(d))?;
+    let first = text.find('\n')?;
+    if text[..first].trim_end() != delimiter {
+        return None;
+    }
+    let start = first + 1;
+    let mut pos = start;
+    for line in text[start..].split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            return Some((delimiter, &text[start..pos], &text[pos + line.len()..]));
         }
+        pos += line.len();
+    }
+    None
+}
+
+/// Front matter with template tags (`{{ .Title }}`, `{% if %}`) or that does
+/// not parse is kept as written.
+fn format_front_matter(delimiter: &str, matter: &str) -> String {
+    if matter.trim().is_empty() {
+        return String::new();
+    }
+    if matter.contains("{{") || matter.contains("{%") {
+        return ensure_newline(matter.to_string());
+    }
+    let res = match delimiter {
+        "+++" => toml_fmt::format(&fake_path_for_kind(FormatKind::Toml), matter),
+        _ => yaml::format(&fake_path_for_kind(FormatKind::Yaml), matter),
     };
-    let res = md::format_text(text, &CONF, &mut code_cb)
-        .map_err(|e| FormatError::Message(e.to_string()))?;
-    Ok(res.map(ensure_newline))
+    match res {
+        Ok(Some(out)) => out,
+        _ => ensure_newline(matter.to_string()),
+    }
+}
+
+/// dprint numbers a list `1. 1. 1.` when its second item is a 1 and
+/// ascending otherwise, so setting the markers in the source picks the style.
+fn renumber(text: &str, numbering: Numbering) -> String {
+    let mut edits = Vec::new();
+    let mut lists: Vec<Option<(u64, u64)>> = Vec::new();
+    for (event, range) in Parser::new(text).into_offset_iter() {
+        match event {
+            Event::Start(Tag::List(start)) => lists.push(start.map(|s| (s, 0))),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                if let Some(Some((start, index))) = lists.last_mut() {
+                    let digits = text[range.start..]
+                        .bytes()
+                        .take_while(u8::is_ascii_digit)
+                        .count();
+                    let number = match numbering {
+                        // A list starting elsewhere keeps its start and
+                        // counts up from it.
+                        Numbering::One if *start == 1 => 1,
+                        _ => *start + *index,
+                    };
+                    if digits > 0 {
+                        edits.push((range.start..range.start + digits, number.to_string()));
+                    }
+                    *index += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut out = text.to_string();
+    for (range, number) in edits.into_iter().rev() {
+        out.replace_range(range, &number);
+    }
+    out
 }
 
-fn fake_path_for_kind(kind: FormatKind) -> PathBuf {
+pub(super) fn fake_path_for_kind(kind: FormatKind) -> PathBuf {
     match kind {
         FormatKind::Json => PathBuf::from("code.json"),
+        FormatKind::Json5 => PathBuf::from("code.json5"),
+        FormatKind::Hjson => PathBuf::from("code.hjson"),
+        FormatKind::Ndjson => PathBuf::from("code.ndjson"),
         FormatKind::Yaml => PathBuf::from("code.yaml"),
         FormatKind::Toml => PathBuf::from("code.toml"),
         FormatKind::Xml => PathBuf::from("code.xml"),
         FormatKind::Markdown => PathBuf::from("code.md"),
+        FormatKind::Mdx => PathBuf::from("code.mdx"),
+        FormatKind::Rst => PathBuf::from("code.rst"),
+        FormatKind::AsciiDoc => PathBuf::from("code.adoc"),
         FormatKind::Bash => PathBuf::from("code.sh"),
         FormatKind::Dockerfile => PathBuf::from("Dockerfile"),
         FormatKind::Makefile => PathBuf::from("Makefile"),
         FormatKind::Ini => PathBuf::from("code.ini"),
-        FormatKind::Nginx => PathBuf::from("code.conf"),
+        FormatKind::Nginx => PathBuf::from("nginx.conf"),
+        FormatKind::Apache => PathBuf::from("httpd.conf"),
+        FormatKind::Haproxy => PathBuf::from("haproxy.cfg"),
+        FormatKind::Caddyfile => PathBuf::from("Caddyfile"),
         FormatKind::Html => PathBuf::from("code.html"),
+        FormatKind::Jinja => PathBuf::from("code.html.j2"),
+        FormatKind::GoTemplate => PathBuf::from("code.gohtml"),
+        FormatKind::Handlebars => PathBuf::from("code.hbs"),
+        FormatKind::Vue => PathBuf::from("code.vue"),
+        FormatKind::Svelte => PathBuf::from("code.svelte"),
+        FormatKind::Astro => PathBuf::from("code.astro"),
         FormatKind::Css => PathBuf::from("code.css"),
+        FormatKind::Scss => PathBuf::from("code.scss"),
+        FormatKind::Sass => PathBuf::from("code.sass"),
+        FormatKind::Less => PathBuf::from("code.less"),
         FormatKind::TypeScript => PathBuf::from("code.ts"),
         FormatKind::JavaScript => PathBuf::from("code.js"),
         FormatKind::Golang => PathBuf::from("code.go"),
         FormatKind::Rust => PathBuf::from("code.rs"),
         FormatKind::Python => PathBuf::from("code.py"),
         FormatKind::Protobuf => PathBuf::from("code.proto"),
+        FormatKind::TextProto => PathBuf::from("code.textproto"),
         FormatKind::Graphql => PathBuf::from("code.graphql"),
         FormatKind::Hcl => PathBuf::from("code.hcl"),
         FormatKind::Lua => PathBuf::from("code.lua"),
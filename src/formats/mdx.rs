@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use anyhow::Result;
+use pulldown_cmark::{Event, Parser, Tag};
+
+use super::{FormatError, ensure_newline, markdown, options, typescript};
+
+/// MDX is Markdown plus `import`/`export` statements and JSX blocks. Those go
+/// through the TypeScript backend and wait behind HTML comments, which
+/// dprint keeps verbatim, while the Markdown around them is formatted.
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    let (out, warnings) = format_with_warnings(text, &options::for_path(path).markdown)?;
+    for warning in warnings {
+        eprintln!("{}: warning: {warning}", path.display());
+    }
+    Ok(out)
+}
+
+pub fn format_with_warnings(
+    text: &str,
+    opts: &markdown::MarkdownOptions,
+) -> Result<(Option<String>, Vec<String>), FormatError> {
+    let mut warnings = Vec::new();
+    let (masked, blocks) = mask(text, &mut warnings);
+    let (out, markdown_warnings) = markdown::format_with_warnings(&masked, opts)?;
+    warnings.extend(markdown_warnings);
+
+    let mut out = out.unwrap_or(masked);
+    for (i, block) in blocks.iter().enumerate() {
+        let lines = block.original.matches('\n').count();
+        out = out.replacen(&placeholder(i, lines), &block.formatted, 1);
+    }
+    let out = ensure_newline(out);
+    Ok(((out != text).then_some(out), warnings))
+}
+
+struct Block {
+    original: String,
+    formatted: String,
+}
+
+/// Spans as many lines as the block it stands for, so line numbers in
+/// Markdown warnings still point into the source.
+fn placeholder(index: usize, lines: usize) -> String {
+    format!("<!-- mdx:{index}{} -->", "\n".repeat(lines))
+}
+
+fn mask(text: &str, warnings: &mut Vec<String>) -> (String, Vec<Block>) {
+    let offset =
+        markdown::split_front_matter(text).map_or(0, |(_, _, body)| text.len() - body.len());
+    let mut blocks = Vec::new();
+    let mut out = String::from(&text[..offset]);
+    let mut last = offset;
+    let mut depth = 0;
+    for (event, range) in Parser::new(&text[offset..]).into_offset_iter() {
+        let top_level = depth == 0;
+        match event {
+            Event::Start(tag) => {
+                depth += 1;
+                if !top_level || !matches!(tag, Tag::Paragraph | Tag::HtmlBlock) {
+                    continue;
+                }
+            }
+            Event::End(_) => {
+                depth -= 1;
+                continue;
+            }
+            _ => continue,
+        }
+        let start = offset + range.start;
+        let end = offset + range.end;
+        let original = text[start..end].trim_end_matches(['\r', '\n']);
+        let line = 1 + text[..start].matches('\n').count();
+        let Some(formatted) = format_block(original, line, warnings) else {
+            continue;
+        };
+        out.push_str(&text[last..start]);
+        out.push_str(&placeholder(blocks.len(), original.matches('\n').count()));
+        last = start + original.len();
+        blocks.push(Block {
+            original: original.to_string(),
+            formatted,
+        });
+    }
+    out.push_str(&text[last..]);
+    (out, blocks)
+}
+
+/// ESM is always kept out of Markdown's hands; a JSX block that does not
+/// parse on its own, such as `<Note>` wrapping Markdown children, is left to
+/// Markdown as HTML.
+fn format_block(block: &str, line: usize, warnings: &mut Vec<String>) -> Option<String> {
+    let tsx = Path::new("code.tsx");
+    if ["import", "export"].iter().any(|k| {
+        block
+            .strip_prefix(k)
+            .is_some_and(|rest| rest.starts_with([' ', '{', '*']))
+    }) {
+        return Some(match typescript::format(tsx, block) {
+            Ok(out) => out.map_or_else(|| block.to_string(), |out| out.trim_end().to_string()),
+            Err(err) => {
+                warnings.push(format!("line {line}: ESM left unformatted: {err}"));
+                block.to_string()
+            }
+        });
+    }
+
+    let mut chars = block.chars();
+    if chars.next() != Some('<')
+        || !chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '>')
+    {
+        return None;
+    }
+    let wrapped = format!("<>\n{block}\n</>;\n");
+    let out = typescript::format(tsx, &wrapped).ok()?.unwrap_or(wrapped);
+    let inner = out.strip_prefix("<>\n")?.trim_end();
+    let inner = inner
+        .strip_suffix(';')
+        .unwrap_or(inner)
+        .strip_suffix("</>")?;
+    let margin = inner
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = inner
+        .lines()
+        .map(|l| l.get(margin..).unwrap_or(""))
+        .collect();
+    Some(lines.join("\n"))
+}
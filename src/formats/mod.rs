@@ -2,25 +2,38 @@ use std::path::Path;
 
 use anyhow::Result;
 
+pub mod apache;
+pub mod asciidoc;
 pub mod bash;
+pub mod caddyfile;
 pub mod css;
 pub mod dockerfile;
 pub mod go;
 pub mod graphql;
+pub mod haproxy;
 pub mod hcl;
+pub mod hjson;
 pub mod html;
 pub mod ini;
 pub mod javascript;
 pub mod json;
+pub mod json5;
 pub mod lua;
 pub mod makefile;
 pub mod markdown;
+pub mod mdx;
+pub mod ndjson;
 pub mod nginx;
+pub mod options;
 pub mod protobuf;
 pub mod python;
 pub mod rlang;
+pub mod rst;
 pub mod rustfmt;
+pub mod scss;
+pub mod sfc;
 pub mod sql;
+pub mod textproto;
 pub mod toml_fmt;
 pub mod typescript;
 pub mod xml;
@@ -29,23 +42,42 @@ pub mod yaml;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FormatKind {
     Json,
+    Json5,
+    Hjson,
+    Ndjson,
     Yaml,
     Toml,
     Xml,
     Markdown,
+    Mdx,
+    Rst,
+    AsciiDoc,
     Bash,
     Dockerfile,
     Makefile,
     Ini,
     Nginx,
+    Apache,
+    Haproxy,
+    Caddyfile,
     Html,
+    Jinja,
+    GoTemplate,
+    Handlebars,
+    Vue,
+    Svelte,
+    Astro,
     Css,
+    Scss,
+    Sass,
+    Less,
     TypeScript,
     JavaScript,
     Golang,
     Rust,
     Python,
     Protobuf,
+    TextProto,
     Graphql,
     Hcl,
     Lua,
@@ -78,12 +110,21 @@ pub fn format_dispatch(
 ) -> Result<Option<String>, FormatError> {
     let out = match kind {
         FormatKind::Json => json::format(path, text),
+        FormatKind::Json5 => json5::format(path, text),
+        FormatKind::Hjson => hjson::format(path, text),
+        FormatKind::Ndjson => ndjson::format(path, text),
         FormatKind::Yaml => yaml::format(path, text),
         FormatKind::Toml => toml_fmt::format(path, text),
         FormatKind::Xml => xml::format(path, text),
         FormatKind::Markdown => markdown::format(path, text),
+        FormatKind::Mdx => mdx::format(path, text),
+        FormatKind::Rst => rst::format(path, text),
+        FormatKind::AsciiDoc => asciidoc::format(path, text),
         FormatKind::Bash => bash::format(path, text),
         FormatKind::Css => css::format(path, text),
+        FormatKind::Scss => scss::format(path, text, scss::Syntax::Scss),
+        FormatKind::Sass => scss::format(path, text, scss::Syntax::Sass),
+        FormatKind::Less => scss::format(path, text, scss::Syntax::Less),
         FormatKind::TypeScript => typescript::format(path, text),
         FormatKind::JavaScript => javascript::format(path, text),
         FormatKind::Dockerfile => dockerfile::format(path, text),
@@ -96,39 +137,83 @@ pub fn format_dispatch(
         FormatKind::Hcl => hcl::format(path, text),
         FormatKind::Lua => lua::format(path, text),
         FormatKind::Html => html::format(path, text),
+        FormatKind::Jinja => html::format_template(path, text, html::Template::Jinja),
+        FormatKind::GoTemplate => html::format_template(path, text, html::Template::Go),
+        FormatKind::Handlebars => html::format_template(path, text, html::Template::Handlebars),
+        FormatKind::Vue => sfc::format(path, text, sfc::Framework::Vue),
+        FormatKind::Svelte => sfc::format(path, text, sfc::Framework::Svelte),
+        FormatKind::Astro => sfc::format(path, text, sfc::Framework::Astro),
         FormatKind::Makefile => makefile::format(path, text),
-        FormatKind::Nginx => nginx::format(path, text),
+        FormatKind::Nginx => match conf_dialect(path, text) {
+            FormatKind::Apache => apache::format(path, text),
+            FormatKind::Haproxy => haproxy::format(path, text),
+            _ => nginx::format(path, text),
+        },
+        FormatKind::Apache => apache::format(path, text),
+        FormatKind::Haproxy => haproxy::format(path, text),
+        FormatKind::Caddyfile => caddyfile::format(path, text),
         FormatKind::Protobuf => protobuf::format(path, text),
+        FormatKind::TextProto => textproto::format(path, text),
         FormatKind::R => rlang::format(path, text),
     }?;
     Ok(out)
 }
 
+/// Explanations `--check` prints next to "would reformat", such as the TOML
+/// keys a configured sort would move.
+pub fn check_notes(kind: FormatKind, path: &Path, text: &str) -> Vec<String> {
+    match kind {
+        FormatKind::Toml => toml_fmt::out_of_order(path, text),
+        _ => Vec::new(),
+    }
+}
+
 pub fn detect_kind_from_label(label: &str) -> Option<FormatKind> {
     let l = label.to_lowercase();
     match l.as_str() {
-        "json" => Some(FormatKind::Json),
+        "json" | "jsonc" => Some(FormatKind::Json),
+        "json5" => Some(FormatKind::Json5),
+        "hjson" => Some(FormatKind::Hjson),
+        "ndjson" | "jsonl" | "jsonlines" => Some(FormatKind::Ndjson),
         "yaml" | "yml" => Some(FormatKind::Yaml),
         "toml" => Some(FormatKind::Toml),
-        "xml" => Some(FormatKind::Xml),
+        "xml" | "svg" | "xsd" | "xsl" | "xslt" | "plist" | "xaml" | "csproj" | "msbuild" => {
+            Some(FormatKind::Xml)
+        }
         "md" | "markdown" => Some(FormatKind::Markdown),
+        "mdx" => Some(FormatKind::Mdx),
+        "rst" | "rest" | "restructuredtext" => Some(FormatKind::Rst),
+        "adoc" | "asciidoc" => Some(FormatKind::AsciiDoc),
         "bash" | "sh" | "shell" => Some(FormatKind::Bash),
         "docker" | "dockerfile" => Some(FormatKind::Dockerfile),
         "makefile" | "mk" => Some(FormatKind::Makefile),
-        "ini" => Some(FormatKind::Ini),
+        "ini" | "cfg" | "gitconfig" | "systemd" => Some(FormatKind::Ini),
         "nginx" => Some(FormatKind::Nginx),
+        "apache" | "apacheconf" | "httpd" | "htaccess" => Some(FormatKind::Apache),
+        "haproxy" => Some(FormatKind::Haproxy),
+        "caddy" | "caddyfile" => Some(FormatKind::Caddyfile),
         "html" | "htm" => Some(FormatKind::Html),
+        "jinja" | "jinja2" | "j2" | "django" | "nunjucks" => Some(FormatKind::Jinja),
+        "gohtml" | "gotmpl" | "go-html-template" => Some(FormatKind::GoTemplate),
+        "hbs" | "handlebars" | "mustache" => Some(FormatKind::Handlebars),
+        "vue" => Some(FormatKind::Vue),
+        "svelte" => Some(FormatKind::Svelte),
+        "astro" => Some(FormatKind::Astro),
         "css" => Some(FormatKind::Css),
+        "scss" | "postcss" | "pcss" => Some(FormatKind::Scss),
+        "sass" => Some(FormatKind::Sass),
+        "less" => Some(FormatKind::Less),
         "ts" | "tsx" | "typescript" => Some(FormatKind::TypeScript),
         "js" | "jsx" | "javascript" => Some(FormatKind::JavaScript),
         "go" | "golang" => Some(FormatKind::Golang),
         "rs" | "rust" => Some(FormatKind::Rust),
         "py" | "python" => Some(FormatKind::Python),
         "proto" | "protobuf" => Some(FormatKind::Protobuf),
+        "textproto" | "txtpb" | "pbtxt" => Some(FormatKind::TextProto),
         "gql" | "graphql" => Some(FormatKind::Graphql),
-        "hcl" | "tf" => Some(FormatKind::Hcl),
+        "hcl" | "tf" | "terraform" | "tfvars" | "packer" | "nomad" => Some(FormatKind::Hcl),
         "lua" => Some(FormatKind::Lua),
-        "r" => Some(FormatKind::R),
+        "r" | "rmd" => Some(FormatKind::R),
         "sql" => Some(FormatKind::Sql),
         _ => None,
     }
@@ -145,40 +230,191 @@ pub fn detect_kind(path: &Path) -> Option<FormatKind> {
     if file_name == "nginx.conf" || file_name.ends_with(".nginx") {
         return Some(FormatKind::Nginx);
     }
+    if file_name == "caddyfile"
+        || file_name.starts_with("caddyfile.")
+        || file_name.ends_with(".caddyfile")
+    {
+        return Some(FormatKind::Caddyfile);
+    }
+    if matches!(
+        file_name.as_str(),
+        "httpd.conf" | "apache.conf" | "apache2.conf" | ".htaccess"
+    ) {
+        return Some(FormatKind::Apache);
+    }
+    if file_name.starts_with("haproxy") && file_name.ends_with(".cfg") {
+        return Some(FormatKind::Haproxy);
+    }
+    if matches!(
+        file_name.as_str(),
+        "setup.cfg"
+            | ".gitconfig"
+            | ".gitmodules"
+            | ".editorconfig"
+            | ".flake8"
+            | ".pylintrc"
+            | ".coveragerc"
+    ) || (file_name == "config"
+        && path.parent().and_then(Path::file_name) == Some(".git".as_ref()))
+    {
+        return Some(FormatKind::Ini);
+    }
 
     let ext = path
         .extension()
         .map(|s| s.to_string_lossy().to_lowercase())
         .unwrap_or_default();
 
+    // HTML templates: `page.html.j2`, `page.jinja`, `page.gohtml`, `page.html.tmpl`.
+    // A template of another language (`values.yaml.j2`) is left alone.
+    let inner_ext = Path::new(&file_name)
+        .file_stem()
+        .and_then(|stem| Path::new(stem).extension())
+        .map(|e| e.to_string_lossy().to_string());
+    let inner_html = matches!(inner_ext.as_deref(), Some("html" | "htm" | "xhtml"));
+    match ext.as_str() {
+        "j2" | "jinja" | "jinja2" | "njk" | "djhtml" => {
+            let untyped = inner_ext.is_none() && ext != "j2";
+            return (inner_html || untyped).then_some(FormatKind::Jinja);
+        }
+        "tmpl" | "gotmpl" | "tpl" if inner_html => return Some(FormatKind::GoTemplate),
+        "gohtml" => return Some(FormatKind::GoTemplate),
+        "hbs" | "handlebars" | "mustache" => return Some(FormatKind::Handlebars),
+        _ => {}
+    }
+
+    if ext == "conf" && in_dir(path, &["apache", "apache2", "httpd"]) {
+        return Some(FormatKind::Apache);
+    }
+    if ext == "cfg" && in_dir(path, &["haproxy"]) {
+        return Some(FormatKind::Haproxy);
+    }
+
     match ext.as_str() {
         "json" | "jsonc" => Some(FormatKind::Json),
+        "json5" => Some(FormatKind::Json5),
+        "hjson" => Some(FormatKind::Hjson),
+        "ndjson" | "jsonl" => Some(FormatKind::Ndjson),
         "yaml" | "yml" => Some(FormatKind::Yaml),
         "toml" => Some(FormatKind::Toml),
-        "xml" => Some(FormatKind::Xml),
+        "xml" | "svg" | "xsd" | "xsl" | "xslt" | "plist" | "xaml" | "resx" => Some(FormatKind::Xml),
+        "csproj" | "fsproj" | "vbproj" | "props" | "targets" => Some(FormatKind::Xml),
         "md" | "markdown" => Some(FormatKind::Markdown),
+        "mdx" => Some(FormatKind::Mdx),
+        "rst" => Some(FormatKind::Rst),
+        "adoc" | "asciidoc" => Some(FormatKind::AsciiDoc),
         "sh" | "bash" => Some(FormatKind::Bash),
         "dockerfile" => Some(FormatKind::Dockerfile),
         "mk" => Some(FormatKind::Makefile),
-        "ini" => Some(FormatKind::Ini),
+        "ini" | "cnf" => Some(FormatKind::Ini),
+        "service" | "socket" | "timer" | "mount" | "automount" | "slice" | "swap" | "network"
+        | "netdev" => Some(FormatKind::Ini),
         "conf" => Some(FormatKind::Nginx),
+        "htaccess" => Some(FormatKind::Apache),
+        "haproxy" => Some(FormatKind::Haproxy),
+        "caddy" => Some(FormatKind::Caddyfile),
         "html" | "htm" => Some(FormatKind::Html),
+        "vue" => Some(FormatKind::Vue),
+        "svelte" => Some(FormatKind::Svelte),
+        "astro" => Some(FormatKind::Astro),
         "css" => Some(FormatKind::Css),
+        // PostCSS nesting and mixin plugins follow SCSS syntax.
+        "scss" | "pcss" | "postcss" => Some(FormatKind::Scss),
+        "sass" => Some(FormatKind::Sass),
+        "less" => Some(FormatKind::Less),
         "ts" | "tsx" => Some(FormatKind::TypeScript),
         "js" | "jsx" | "mjs" | "cjs" => Some(FormatKind::JavaScript),
         "go" => Some(FormatKind::Golang),
         "rs" => Some(FormatKind::Rust),
         "py" => Some(FormatKind::Python),
         "proto" => Some(FormatKind::Protobuf),
+        "textproto" | "txtpb" | "pbtxt" => Some(FormatKind::TextProto),
         "graphql" | "gql" => Some(FormatKind::Graphql),
-        "hcl" | "tf" => Some(FormatKind::Hcl),
+        "hcl" | "tf" | "tfvars" | "nomad" => Some(FormatKind::Hcl),
         "lua" => Some(FormatKind::Lua),
-        "r" => Some(FormatKind::R),
+        "r" | "rmd" | "qmd" => Some(FormatKind::R),
         "sql" => Some(FormatKind::Sql),
         _ => None,
     }
 }
 
+/// Whether any parent directory of `path` has one of `names`.
+fn in_dir(path: &Path, names: &[&str]) -> bool {
+    path.parent().is_some_and(|dir| {
+        dir.components().any(|c| {
+            let c = c.as_os_str().to_string_lossy().to_lowercase();
+            names.contains(&c.as_str())
+        })
+    })
+}
+
+/// Generic `*.conf` files outside an nginx directory may hold Apache or
+/// HAProxy configuration; pick the dialect from their content.
+fn conf_dialect(path: &Path, text: &str) -> FormatKind {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if file_name == "nginx.conf" || file_name.ends_with(".nginx") || in_dir(path, &["nginx"]) {
+        return FormatKind::Nginx;
+    }
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let Some(first) = lines.next() else {
+        return FormatKind::Nginx;
+    };
+    if haproxy::is_section_header(first) && !first.contains(['{', ';']) {
+        return FormatKind::Haproxy;
+    }
+    let is_section_tag = |l: &str| {
+        l.ends_with('>')
+            && l.strip_prefix('<')
+                .map(|r| r.trim_start_matches('/'))
+                .is_some_and(|r| r.starts_with(|c: char| c.is_ascii_alphabetic()))
+    };
+    if std::iter::once(first).chain(lines).any(is_section_tag) {
+        return FormatKind::Apache;
+    }
+    FormatKind::Nginx
+}
+
+/// Collapse runs of whitespace outside `quotes` into single spaces and trim
+/// the ends. Backslash escapes inside quotes are honored.
+pub(crate) fn collapse_whitespace(line: &str, quotes: &[char]) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut pending_space = false;
+    for ch in line.trim().chars() {
+        if let Some(q) = quote {
+            out.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == q {
+                quote = None;
+            }
+            continue;
+        }
+        if ch.is_whitespace() {
+            pending_space = true;
+            continue;
+        }
+        if pending_space {
+            out.push(' ');
+            pending_space = false;
+        }
+        if quotes.contains(&ch) {
+            quote = Some(ch);
+        }
+        out.push(ch);
+    }
+    out
+}
+
 // helper shared
 pub fn ensure_newline(mut text: String) -> String {
     if !text.ends_with('\n') {
@@ -0,0 +1,107 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use jsonc_parser::ast::{ObjectPropName, Value};
+use jsonc_parser::common::Ranged;
+use jsonc_parser::{CollectOptions, ParseOptions, parse_to_ast};
+
+use super::FormatError;
+
+/// NDJSON / JSON Lines: every record is compacted onto its own line
+/// (`jq -c` style, numbers and strings kept as written). Blank lines and
+/// line endings are preserved; the line number is reported on bad records.
+pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    let mut out = Vec::with_capacity(text.len());
+    if !format_stream(text.as_bytes(), &mut out)? {
+        return Ok(None);
+    }
+    String::from_utf8(out)
+        .map(Some)
+        .map_err(|e| FormatError::Message(e.to_string()))
+}
+
+/// Formats record by record so that multi-GB logs never sit in memory.
+/// Returns whether the output differs from the input.
+pub fn format_stream(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+) -> Result<bool, FormatError> {
+    let mut line = String::new();
+    let mut record = String::new();
+    let mut number = 0;
+    let mut changed = false;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        number += 1;
+        let (content, ending) = if let Some(content) = line.strip_suffix("\r\n") {
+            (content, "\r\n")
+        } else if let Some(content) = line.strip_suffix('\n') {
+            (content, "\n")
+        } else {
+            changed = true;
+            (line.as_str(), "\n")
+        };
+        record.clear();
+        if !content.trim().is_empty() {
+            compact(content, &mut record)
+                .map_err(|e| FormatError::Message(format!("line {number}: {e}")))?;
+        }
+        changed |= record != content;
+        writer.write_all(record.as_bytes())?;
+        writer.write_all(ending.as_bytes())?;
+    }
+    writer.flush()?;
+    Ok(changed)
+}
+
+fn compact(text: &str, out: &mut String) -> Result<(), String> {
+    let strict = ParseOptions {
+        allow_comments: false,
+        allow_loose_object_property_names: false,
+        allow_trailing_commas: false,
+    };
+    let ast = parse_to_ast(text, &CollectOptions::default(), &strict).map_err(|e| e.to_string())?;
+    let Some(root) = ast.value else {
+        return Err("expected a JSON value".to_string());
+    };
+    write_value(text, &root, out);
+    Ok(())
+}
+
+fn write_value(text: &str, value: &Value, out: &mut String) {
+    match value {
+        Value::Object(obj) => {
+            out.push('{');
+            for (i, prop) in obj.properties.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                match &prop.name {
+                    ObjectPropName::String(s) => out.push_str(&text[s.range.start..s.range.end]),
+                    ObjectPropName::Word(w) => out.push_str(&text[w.range.start..w.range.end]),
+                }
+                out.push(':');
+                write_value(text, &prop.value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(arr) => {
+            out.push('[');
+            for (i, element) in arr.elements.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(text, element, out);
+            }
+            out.push(']');
+        }
+        other => {
+            let range = other.range();
+            out.push_str(&text[range.start..range.end]);
+        }
+    }
+}
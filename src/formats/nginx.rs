@@ -1,59 +1,459 @@
 use std::path::Path;
 
 use anyhow::Result;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, ensure_newline, options};
 
-/// Simple brace/semicolon-based formatter for nginx.conf
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NginxOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    /// Pad directive names so values of consecutive directives line up.
+    pub align_values: bool,
+}
+
+impl Default for NginxOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            align_values: false,
+        }
+    }
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).nginx)
+}
+
+/// Parse nginx.conf syntax (quoted strings, `${var}`, comments, `*_by_lua_block`
+/// bodies) and print one directive per line, keeping single blank lines
+/// between directive groups.
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &NginxOptions,
+) -> Result<Option<String>, FormatError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser::new(text);
+    let nodes = parser.parse_block(false)?;
     let mut out = String::new();
-    let mut indent: i32 = 0;
+    print_nodes(&nodes, 0, opts, &mut out);
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+#[derive(Debug)]
+enum Node {
+    Directive(Directive),
+    Comment(String),
+    Blank,
+}
+
+#[derive(Debug)]
+struct Directive {
+    name: String,
+    /// Arguments with a flag telling whether a line break preceded them.
+    args: Vec<(String, bool)>,
+    body: Body,
+    trailing: Option<String>,
+}
+
+#[derive(Debug)]
+enum Body {
+    None,
+    Block(Vec<Node>),
+    /// Verbatim body of a `*_by_lua_block`, without the outer braces.
+    Raw(String),
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+enum Tok {
+    Word(String),
+    Semi,
+    Open,
+    Close,
+    Comment(String),
+    Eof,
+}
 
-    for raw_line in text.lines() {
-        let mut line = raw_line.trim();
-        if line.is_empty() {
-            continue;
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
         }
-        // comments keep alignment
-        if line.starts_with('#') {
-            out.push_str(&"    ".repeat(indent.max(0) as usize));
-            out.push_str(line);
-            out.push('\n');
-            continue;
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
         }
+        Some(c)
+    }
 
-        // decrease indent if line starts with }
-        if line.starts_with('}') {
-            indent -= 1;
+    fn error(&self, msg: &str) -> FormatError {
+        FormatError::Message(format!("nginx parse error on line {}: {msg}", self.line))
+    }
+
+    /// Skip whitespace and return how many newlines were crossed.
+    fn skip_ws(&mut self) -> usize {
+        let mut newlines = 0;
+        while let Some(c) = self.peek_char() {
+            if !c.is_whitespace() {
+                break;
+            }
+            if c == '\n' {
+                newlines += 1;
+            }
+            self.bump();
         }
-        if indent < 0 {
-            indent = 0;
+        newlines
+    }
+
+    fn next_token(&mut self) -> Result<Tok, FormatError> {
+        let Some(c) = self.peek_char() else {
+            return Ok(Tok::Eof);
+        };
+        match c {
+            ';' => {
+                self.bump();
+                Ok(Tok::Semi)
+            }
+            '{' => {
+                self.bump();
+                Ok(Tok::Open)
+            }
+            '}' => {
+                self.bump();
+                Ok(Tok::Close)
+            }
+            '#' => {
+                let start = self.pos;
+                while self.peek_char().is_some_and(|c| c != '\n') {
+                    self.bump();
+                }
+                Ok(Tok::Comment(
+                    self.src[start..self.pos].trim_end().to_string(),
+                ))
+            }
+            _ => self.read_word().map(Tok::Word),
         }
-        out.push_str(&"    ".repeat(indent as usize));
-        // ensure space before { and ; spacing trimmed
-        line = line.trim_end_matches(';');
-        let mut to_write = line.trim().replace('{', " {");
-        to_write = to_write.split_whitespace().collect::<Vec<_>>().join(" ");
-        if raw_line.trim_end().ends_with('{') {
-            to_write.push_str(" {");
+    }
+
+    fn read_word(&mut self) -> Result<String, FormatError> {
+        let start = self.pos;
+        let mut quote: Option<char> = None;
+        let mut in_var_brace = false;
+        let mut prev = '\0';
+        while let Some(c) = self.peek_char() {
+            match quote {
+                Some(q) => {
+                    self.bump();
+                    if c == '\\' {
+                        self.bump();
+                        prev = '\0';
+                        continue;
+                    }
+                    if c == q {
+                        quote = None;
+                    }
+                }
+                None => {
+                    if c == '"' || c == '\'' {
+                        quote = Some(c);
+                    } else if c == '{' && prev == '$' {
+                        in_var_brace = true;
+                    } else if c == '}' && in_var_brace {
+                        in_var_brace = false;
+                    } else if c.is_whitespace() || c == ';' || c == '{' || c == '}' {
+                        break;
+                    } else if c == '\\' {
+                        self.bump();
+                    }
+                    self.bump();
+                }
+            }
+            prev = c;
         }
-        if raw_line.trim_end().ends_with(';') {
-            to_write.push(';');
+        if quote.is_some() {
+            return Err(self.error("unterminated string"));
         }
-        out.push_str(&to_write);
-        out.push('\n');
+        Ok(self.src[start..self.pos].to_string())
+    }
 
-        if raw_line.contains('{') {
-            indent += 1;
+    fn parse_block(&mut self, nested: bool) -> Result<Vec<Node>, FormatError> {
+        let mut nodes = Vec::new();
+        loop {
+            let newlines = self.skip_ws();
+            if newlines > 1 && !nodes.is_empty() && !matches!(nodes.last(), Some(Node::Blank)) {
+                nodes.push(Node::Blank);
+            }
+            match self.next_token()? {
+                Tok::Eof if nested => return Err(self.error("missing closing `}`")),
+                Tok::Eof => break,
+                Tok::Close if nested => break,
+                Tok::Close => return Err(self.error("unexpected `}`")),
+                Tok::Comment(c) => {
+                    let same_line = newlines == 0 && !nodes.is_empty();
+                    match nodes.last_mut() {
+                        Some(Node::Directive(d)) if same_line && d.trailing.is_none() => {
+                            d.trailing = Some(c)
+                        }
+                        _ => nodes.push(Node::Comment(c)),
+                    }
+                }
+                Tok::Semi => {}
+                Tok::Open => return Err(self.error("block without a directive name")),
+                Tok::Word(name) => nodes.push(Node::Directive(self.parse_directive(name)?)),
+            }
         }
-        if raw_line.contains('}') {
-            // handled at start
+        while matches!(nodes.last(), Some(Node::Blank)) {
+            nodes.pop();
         }
+        Ok(nodes)
     }
 
-    if out == text {
-        Ok(None)
+    fn parse_directive(&mut self, name: String) -> Result<Directive, FormatError> {
+        let mut args = Vec::new();
+        loop {
+            let newlines = self.skip_ws();
+            match self.next_token()? {
+                Tok::Word(w) => args.push((w, newlines > 0)),
+                Tok::Semi => {
+                    return Ok(Directive {
+                        name,
+                        args,
+                        body: Body::None,
+                        trailing: self.same_line_comment(),
+                    });
+                }
+                Tok::Open => {
+                    let body = if name.ends_with("_by_lua_block") {
+                        Body::Raw(self.read_lua_body()?)
+                    } else {
+                        Body::Block(self.parse_block(true)?)
+                    };
+                    return Ok(Directive {
+                        name,
+                        args,
+                        body,
+                        trailing: self.same_line_comment(),
+                    });
+                }
+                // A comment between arguments, e.g. inside a multi-line log_format.
+                Tok::Comment(c) => args.push((c, true)),
+                Tok::Close | Tok::Eof => {
+                    return Err(self.error(&format!("directive `{name}` is missing `;`")));
+                }
+            }
+        }
+    }
+
+    fn same_line_comment(&mut self) -> Option<String> {
+        let save = (self.pos, self.line);
+        if self.skip_ws() == 0 && self.peek_char() == Some('#') {
+            if let Ok(Tok::Comment(c)) = self.next_token() {
+                return Some(c);
+            }
+        }
+        (self.pos, self.line) = save;
+        None
+    }
+
+    /// Capture a Lua body up to its matching `}`, skipping Lua strings and
+    /// comments so braces inside them do not count.
+    fn read_lua_body(&mut self) -> Result<String, FormatError> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek_char() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    let body = self.src[start..self.pos].to_string();
+                    self.bump();
+                    return Ok(body);
+                }
+                '}' => depth -= 1,
+                '"' | '\'' => {
+                    self.bump();
+                    while let Some(n) = self.bump() {
+                        if n == '\\' {
+                            self.bump();
+                        } else if n == c || n == '\n' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                '-' if self.src[self.pos..].starts_with("--") => {
+                    while self.peek_char().is_some_and(|c| c != '\n') {
+                        self.bump();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            self.bump();
+        }
+        Err(self.error("unterminated lua block"))
+    }
+}
+
+fn print_nodes(nodes: &[Node], depth: usize, opts: &NginxOptions, out: &mut String) {
+    let pad = " ".repeat(depth * opts.indent);
+    let widths = if opts.align_values {
+        alignment_widths(nodes)
     } else {
-        Ok(Some(ensure_newline(out)))
+        vec![0; nodes.len()]
+    };
+    for (node, width) in nodes.iter().zip(widths) {
+        match node {
+            Node::Blank => out.push('\n'),
+            Node::Comment(c) => {
+                out.push_str(&pad);
+                out.push_str(c);
+                out.push('\n');
+            }
+            Node::Directive(d) => print_directive(d, width, depth, opts, out),
+        }
+    }
+}
+
+/// Name column width per node: consecutive simple directives (not separated
+/// by blank lines or blocks) share the width of their longest name.
+fn alignment_widths(nodes: &[Node]) -> Vec<usize> {
+    let mut widths = vec![0; nodes.len()];
+    let mut group: Vec<usize> = Vec::new();
+    let flush = |group: &mut Vec<usize>, widths: &mut Vec<usize>| {
+        if group.len() > 1 {
+            let max = group
+                .iter()
+                .map(|&i| match &nodes[i] {
+                    Node::Directive(d) => d.name.chars().count(),
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0);
+            for &i in group.iter() {
+                widths[i] = max;
+            }
+        }
+        group.clear();
+    };
+    for (i, node) in nodes.iter().enumerate() {
+        match node {
+            Node::Directive(d)
+                if matches!(d.body, Body::None)
+                    && !d.args.is_empty()
+                    && !d.args.iter().any(|(_, nl)| *nl) =>
+            {
+                group.push(i)
+            }
+            Node::Comment(_) => {}
+            _ => flush(&mut group, &mut widths),
+        }
+    }
+    flush(&mut group, &mut widths);
+    widths
+}
+
+fn print_directive(
+    d: &Directive,
+    width: usize,
+    depth: usize,
+    opts: &NginxOptions,
+    out: &mut String,
+) {
+    let pad = " ".repeat(depth * opts.indent);
+    let cont = " ".repeat((depth + 1) * opts.indent);
+    out.push_str(&pad);
+    out.push_str(&d.name);
+    let name_len = d.name.chars().count();
+    let mut after_comment = false;
+    for (idx, (arg, newline)) in d.args.iter().enumerate() {
+        // Anything after a comment argument has to start on a fresh line.
+        if *newline || after_comment {
+            out.push('\n');
+            out.push_str(&cont);
+        } else if idx == 0 && width > name_len {
+            out.push_str(&" ".repeat(width - name_len + 1));
+        } else {
+            out.push(' ');
+        }
+        out.push_str(arg);
+        after_comment = arg.starts_with('#');
+    }
+    if after_comment {
+        out.push('\n');
+        out.push_str(&pad);
+    }
+    match &d.body {
+        Body::None => out.push(';'),
+        Body::Block(children) if children.is_empty() => {
+            out.push_str(" {\n");
+            out.push_str(&pad);
+            out.push('}');
+        }
+        Body::Block(children) => {
+            out.push_str(" {\n");
+            print_nodes(children, depth + 1, opts, out);
+            out.push_str(&pad);
+            out.push('}');
+        }
+        Body::Raw(body) => {
+            out.push_str(" {\n");
+            push_reindented(body, &cont, out);
+            out.push_str(&pad);
+            out.push('}');
+        }
+    }
+    if let Some(c) = &d.trailing {
+        out.push(' ');
+        out.push_str(c);
+    }
+    out.push('\n');
+}
+
+/// Re-indent an opaque block body: drop surrounding blank lines, strip the
+/// common leading indentation and prefix every line with `pad`.
+fn push_reindented(body: &str, pad: &str, out: &mut String) {
+    let lines: Vec<&str> = body.lines().map(str::trim_end).collect();
+    let start = lines
+        .iter()
+        .position(|l| !l.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(start, |i| i + 1);
+    let lines = &lines[start..end];
+    let common = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    for line in lines {
+        if !line.is_empty() {
+            out.push_str(pad);
+            out.push_str(&line[common..]);
+        }
+        out.push('\n');
     }
 }
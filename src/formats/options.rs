@@ -0,0 +1,196 @@
+//! Per-path formatter options loaded from `.formatter.toml`.
+//!
+//! ```toml
+//! [protobuf]
+//! lint = true
+//!
+//! [[overrides]]
+//! files = ["legacy/**/*.proto"]
+//! protobuf = { lint = false }
+//! ```
+//!
+//! Top-level tables configure every file; `[[overrides]]` entries are merged on
+//! top, in order, for paths matching one of their `files` globs. Globs without
+//! a `/` match the file name anywhere in the tree.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use super::apache::ApacheOptions;
+use super::caddyfile::CaddyfileOptions;
+use super::css::CssOptions;
+use super::haproxy::HaproxyOptions;
+use super::html::HtmlOptions;
+use super::ini::IniOptions;
+use super::json::JsonOptions;
+use super::makefile::MakefileOptions;
+use super::markdown::MarkdownOptions;
+use super::nginx::NginxOptions;
+use super::protobuf::ProtobufOptions;
+use super::rlang::RlangOptions;
+use super::toml_fmt::TomlOptions;
+use super::typescript::TypeScriptOptions;
+use super::xml::XmlOptions;
+use super::yaml::YamlOptions;
+
+pub const CONFIG_FILE_NAME: &str = ".formatter.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Options {
+    pub apache: ApacheOptions,
+    pub caddyfile: CaddyfileOptions,
+    pub css: CssOptions,
+    pub haproxy: HaproxyOptions,
+    pub html: HtmlOptions,
+    pub ini: IniOptions,
+    pub json: JsonOptions,
+    pub makefile: MakefileOptions,
+    pub markdown: MarkdownOptions,
+    pub nginx: NginxOptions,
+    pub protobuf: ProtobufOptions,
+    pub r: RlangOptions,
+    pub toml: TomlOptions,
+    pub typescript: TypeScriptOptions,
+    pub xml: XmlOptions,
+    pub yaml: YamlOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    root: PathBuf,
+    base: toml::Table,
+    overrides: Vec<Override>,
+}
+
+#[derive(Debug, Clone)]
+struct Override {
+    files: GlobSet,
+    table: toml::Table,
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let root = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let root = fs::canonicalize(&root).unwrap_or(root);
+        Self::parse(&text, root).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Parse config text; override globs are matched relative to `root`.
+    pub fn parse(text: &str, root: PathBuf) -> Result<Self> {
+        let mut base: toml::Table = toml::from_str(text)?;
+        let mut overrides = Vec::new();
+        if let Some(raw) = base.remove("overrides") {
+            let toml::Value::Array(entries) = raw else {
+                anyhow::bail!("`overrides` must be an array of tables");
+            };
+            for entry in entries {
+                let toml::Value::Table(mut table) = entry else {
+                    anyhow::bail!("`overrides` entries must be tables");
+                };
+                let files = match table.remove("files") {
+                    Some(toml::Value::Array(globs)) => globs
+                        .into_iter()
+                        .map(|g| match g {
+                            toml::Value::String(s) => Ok(s),
+                            other => Err(anyhow::anyhow!("invalid glob in `files`: {other}")),
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                    Some(toml::Value::String(s)) => vec![s],
+                    _ => anyhow::bail!("`overrides` entries need a `files` glob list"),
+                };
+                overrides.push(Override {
+                    files: build_globset(&files)?,
+                    table,
+                });
+            }
+        }
+
+        let config = Config {
+            root,
+            base,
+            overrides,
+        };
+        // Surface typos and type errors once at load time instead of per file.
+        config.deserialize(config.base.clone())?;
+        for ov in &config.overrides {
+            let mut merged = config.base.clone();
+            merge_tables(&mut merged, ov.table.clone());
+            config.deserialize(merged)?;
+        }
+        Ok(config)
+    }
+
+    pub fn options_for(&self, path: &Path) -> Options {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let mut merged = self.base.clone();
+        for ov in &self.overrides {
+            if ov.files.is_match(relative) {
+                merge_tables(&mut merged, ov.table.clone());
+            }
+        }
+        self.deserialize(merged).unwrap_or_default()
+    }
+
+    fn deserialize(&self, table: toml::Table) -> Result<Options> {
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+}
+
+/// Install the process-wide configuration used by [`for_path`]. Only the
+/// first call has an effect.
+pub fn install(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// Options for `path`, or the defaults when no configuration was installed.
+pub fn for_path(path: &Path) -> Options {
+    CONFIG
+        .get()
+        .map(|c| c.options_for(path))
+        .unwrap_or_default()
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pat in patterns {
+        let pat = pat.trim_start_matches("./");
+        let full = if pat.contains('/') {
+            pat.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{pat}")
+        };
+        let glob = GlobBuilder::new(&full)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("invalid glob `{pat}`"))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+fn merge_tables(base: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(incoming)) => {
+                merge_tables(existing, incoming)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
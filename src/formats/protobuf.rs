@@ -1,11 +1,42 @@
+use std::fmt;
 use std::path::Path;
 
 use anyhow::Result;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, ensure_newline, options};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProtobufOptions {
+    /// Fail files that break the buf-style naming rules checked by [`lint`].
+    pub lint: bool,
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).protobuf)
+}
 
 /// Heuristic pretty formatter for .proto files (brace/semicolon indentation, 2 spaces)
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+pub fn format_with_options(
+    _path: &Path,
+    text: &str,
+    opts: &ProtobufOptions,
+) -> Result<Option<String>, FormatError> {
+    if opts.lint {
+        let violations = lint(text);
+        if !violations.is_empty() {
+            let report = violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n");
+            return Err(FormatError::Message(format!(
+                "protobuf lint failed:\n{report}"
+            )));
+        }
+    }
+
     let mut out = String::new();
     let mut indent: i32 = 0;
 
@@ -49,3 +80,291 @@ pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
         Ok(Some(ensure_newline(out)))
     }
 }
+
+/// A naming-rule violation reported by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintViolation {
+    pub line: usize,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for LintViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.message, self.rule)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Message,
+    Enum { seen_value: bool },
+    Service,
+    Oneof,
+    Other,
+}
+
+/// Check a .proto file against the naming rules of `buf lint`'s default
+/// category: PascalCase messages, enums, services and RPCs, lower_snake_case
+/// fields and oneofs, UPPER_SNAKE_CASE enum values and a zero first enum value.
+pub fn lint(text: &str) -> Vec<LintViolation> {
+    let tokens = tokenize_proto(text);
+    let mut violations = Vec::new();
+    let mut scopes: Vec<Scope> = Vec::new();
+    let mut stmt: Vec<(&str, usize)> = Vec::new();
+
+    let mut report = |line: usize, rule: &'static str, message: String| {
+        violations.push(LintViolation {
+            line,
+            rule,
+            message,
+        });
+    };
+
+    for (tok, line) in tokens {
+        match tok {
+            "{" => {
+                let scope = match stmt.first().map(|(t, _)| *t) {
+                    Some("message") => {
+                        check_name(
+                            &stmt,
+                            is_pascal_case,
+                            "MESSAGE_PASCAL_CASE",
+                            "message",
+                            &mut report,
+                        );
+                        Scope::Message
+                    }
+                    Some("enum") => {
+                        check_name(
+                            &stmt,
+                            is_pascal_case,
+                            "ENUM_PASCAL_CASE",
+                            "enum",
+                            &mut report,
+                        );
+                        Scope::Enum { seen_value: false }
+                    }
+                    Some("service") => {
+                        check_name(
+                            &stmt,
+                            is_pascal_case,
+                            "SERVICE_PASCAL_CASE",
+                            "service",
+                            &mut report,
+                        );
+                        Scope::Service
+                    }
+                    Some("oneof") => {
+                        check_name(
+                            &stmt,
+                            is_lower_snake_case,
+                            "ONEOF_LOWER_SNAKE_CASE",
+                            "oneof",
+                            &mut report,
+                        );
+                        Scope::Oneof
+                    }
+                    Some("rpc") => {
+                        check_name(&stmt, is_pascal_case, "RPC_PASCAL_CASE", "rpc", &mut report);
+                        Scope::Other
+                    }
+                    _ => Scope::Other,
+                };
+                scopes.push(scope);
+                stmt.clear();
+            }
+            "}" => {
+                scopes.pop();
+                stmt.clear();
+            }
+            ";" => {
+                match scopes.last_mut() {
+                    Some(Scope::Message | Scope::Oneof) => lint_field(&stmt, &mut report),
+                    Some(Scope::Enum { seen_value }) => {
+                        lint_enum_value(&stmt, seen_value, &mut report)
+                    }
+                    Some(Scope::Service) if stmt.first().map(|(t, _)| *t) == Some("rpc") => {
+                        check_name(&stmt, is_pascal_case, "RPC_PASCAL_CASE", "rpc", &mut report);
+                    }
+                    _ => {}
+                }
+                stmt.clear();
+            }
+            _ => stmt.push((tok, line)),
+        }
+    }
+    violations
+}
+
+fn check_name(
+    stmt: &[(&str, usize)],
+    check: fn(&str) -> bool,
+    rule: &'static str,
+    what: &str,
+    report: &mut impl FnMut(usize, &'static str, String),
+) {
+    if let Some((name, line)) = stmt.get(1) {
+        if !check(name) {
+            let style = if rule.ends_with("PASCAL_CASE") {
+                "PascalCase"
+            } else {
+                "lower_snake_case"
+            };
+            report(
+                *line,
+                rule,
+                format!("{what} name \"{name}\" should be {style}"),
+            );
+        }
+    }
+}
+
+fn lint_field(stmt: &[(&str, usize)], report: &mut impl FnMut(usize, &'static str, String)) {
+    let Some((first, _)) = stmt.first() else {
+        return;
+    };
+    if matches!(
+        *first,
+        "option" | "reserved" | "extensions" | "import" | "package" | "syntax" | "edition"
+    ) {
+        return;
+    }
+    let Some(eq) = stmt.iter().position(|(t, _)| *t == "=") else {
+        return;
+    };
+    if eq == 0 {
+        return;
+    }
+    let (name, line) = stmt[eq - 1];
+    if !is_lower_snake_case(name) {
+        report(
+            line,
+            "FIELD_LOWER_SNAKE_CASE",
+            format!("field name \"{name}\" should be lower_snake_case"),
+        );
+    }
+}
+
+fn lint_enum_value(
+    stmt: &[(&str, usize)],
+    seen_value: &mut bool,
+    report: &mut impl FnMut(usize, &'static str, String),
+) {
+    let Some((name, line)) = stmt.first().copied() else {
+        return;
+    };
+    if matches!(name, "option" | "reserved") || stmt.get(1).map(|(t, _)| *t) != Some("=") {
+        return;
+    }
+    if !is_upper_snake_case(name) {
+        report(
+            line,
+            "ENUM_VALUE_UPPER_SNAKE_CASE",
+            format!("enum value \"{name}\" should be UPPER_SNAKE_CASE"),
+        );
+    }
+    if !*seen_value {
+        *seen_value = true;
+        let number = stmt.get(2).map(|(t, _)| *t).unwrap_or_default();
+        if parse_int(number) != Some(0) {
+            report(
+                line,
+                "ENUM_FIRST_VALUE_ZERO",
+                format!("first enum value \"{name}\" should be zero"),
+            );
+        }
+    }
+}
+
+fn parse_int(s: &str) -> Option<i64> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        return i64::from_str_radix(hex, 16).ok();
+    }
+    s.parse().ok()
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_lower_snake_case(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn is_upper_snake_case(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Split .proto source into identifier and punctuation tokens with their line
+/// numbers, dropping comments and string literals.
+fn tokenize_proto(text: &str) -> Vec<(&str, usize)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    if bytes[i] == b'\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                i += 2;
+            }
+            b'"' | b'\'' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != c && bytes[i] != b'\n' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                tokens.push((&text[start..i.min(text.len())], line));
+            }
+            b'{' | b'}' | b';' | b'=' | b'<' | b'>' | b'(' | b')' | b'[' | b']' | b',' => {
+                tokens.push((&text[i..i + 1], line));
+                i += 1;
+            }
+            _ if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                let start = i;
+                while i < bytes.len()
+                    && !bytes[i].is_ascii_whitespace()
+                    && !b"{};=<>()[],\"'/".contains(&bytes[i])
+                {
+                    i += 1;
+                }
+                if i == start {
+                    i += 1;
+                    continue;
+                }
+                tokens.push((&text[start..i], line));
+            }
+        }
+    }
+    tokens
+}
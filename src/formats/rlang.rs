@@ -1,37 +1,584 @@
 use std::path::Path;
 
 use anyhow::Result;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, ensure_newline, options};
 
-/// Minimal R formatter using brace indentation and trimming.
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let mut out = String::new();
-    let mut indent: i32 = 0;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RlangOptions {
+    /// Spaces per indentation level inside `{`, `(` and `[`.
+    pub indent: usize,
+    /// Calls on lines longer than this get one argument per line.
+    pub line_width: usize,
+}
 
-    for raw in text.lines() {
-        let line = raw.trim_end();
-        if line.is_empty() {
-            continue;
+impl Default for RlangOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            line_width: 80,
         }
+    }
+}
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).r)
+}
+
+/// R formatter in the spirit of the tidyverse style guide / styler: spaces
+/// around assignment and infix operators (including `%>%` and `|>`), none
+/// around `^`, `:`, `::` and `$`, `if (`/`function(` spacing, indentation by
+/// open brackets and continued pipelines, comments and blank lines kept.
+/// `.Rmd`/`.qmd` files only have their `{r}` chunks formatted.
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &RlangOptions,
+) -> Result<Option<String>, FormatError> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let out = if matches!(ext.as_str(), "rmd" | "qmd") {
+        format_rmd(text, opts)?
+    } else {
+        format_r(text, opts)?
+    };
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+/// Format R source text; also used for R Markdown chunks.
+pub fn format_r(text: &str, opts: &RlangOptions) -> Result<String, FormatError> {
+    if text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let tokens = tokenize(text)?;
+    let lines = wrap_long_calls(split_lines(tokens), opts);
+    Ok(ensure_newline(print_lines(&lines, opts)))
+}
+
+fn format_rmd(text: &str, opts: &RlangOptions) -> Result<String, FormatError> {
+    let mut out = String::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        out.push_str(line);
+        out.push('\n');
         let trimmed = line.trim_start();
-        if trimmed.starts_with('}') {
-            indent -= 1;
+        let fence_len = trimmed.chars().take_while(|&c| c == '`').count();
+        let header = trimmed[fence_len..].trim_start();
+        let is_r_chunk = fence_len >= 3
+            && header.starts_with('{')
+            && header[1..]
+                .trim_start()
+                .split(|c: char| c == ',' || c == '}' || c.is_whitespace())
+                .next()
+                .is_some_and(|lang| lang.eq_ignore_ascii_case("r"));
+        if !is_r_chunk {
+            continue;
         }
-        if indent < 0 {
-            indent = 0;
+        let fence = &trimmed[..fence_len];
+        let mut body = String::new();
+        let mut closing = None;
+        for inner in lines.by_ref() {
+            if inner.trim() == fence {
+                closing = Some(inner);
+                break;
+            }
+            body.push_str(inner);
+            body.push('\n');
         }
-        out.push_str(&"  ".repeat(indent as usize));
-        out.push_str(trimmed);
-        out.push('\n');
-        if trimmed.ends_with('{') {
-            indent += 1;
+        out.push_str(&format_r(&body, opts)?);
+        if let Some(close) = closing {
+            out.push_str(close);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Keyword,
+    Number,
+    Str,
+    Comment,
+    Op,
+    Open,
+    Close,
+    Comma,
+    Semi,
+}
+
+#[derive(Debug, Clone)]
+struct Tok {
+    kind: Kind,
+    text: String,
+    /// For `+`, `-`, `!`, `~`, `?`: used as a prefix operator.
+    unary: bool,
+    /// Written right after the previous token, as in tidy eval's `{{ x }}`.
+    glued: bool,
+}
+
+/// A source line: its tokens and whether a blank line precedes it.
+#[derive(Debug, Default)]
+struct Line {
+    tokens: Vec<Tok>,
+    blank_before: bool,
+}
+
+const KEYWORDS: &[&str] = &[
+    "if", "else", "repeat", "while", "function", "for", "in", "next", "break",
+];
+
+const OPERATORS: &[&str] = &[
+    "<<-", "->>", ":::", "<-", "->", "|>", "==", "!=", "<=", ">=", "&&", "||", "::", "**", "+",
+    "-", "*", "/", "^", "<", ">", "!", "&", "|", "~", "?", ":", "=", "$", "@", "\\",
+];
+
+/// Operators printed without surrounding spaces.
+const TIGHT: &[&str] = &["^", ":", "::", ":::", "$", "@"];
+
+fn tokenize(text: &str) -> Result<Vec<(Tok, usize)>, FormatError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<(Tok, usize)> = Vec::new();
+    let mut brackets: Vec<&str> = Vec::new();
+    // Depths of the `(` opening an `if`/`for`/`while` condition or a
+    // function's parameters: an operator after the `)` starts the body.
+    let mut heads: Vec<usize> = Vec::new();
+    let mut after_head = false;
+    let mut newlines = 0;
+    let mut line = 1;
+    let mut i = 0;
+
+    let err = |line: usize, msg: &str| {
+        FormatError::Message(format!("R parse error on line {line}: {msg}"))
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            newlines += 1;
+            line += 1;
+            i += 1;
+            continue;
         }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut closes_head = false;
+        let kind = if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            Kind::Comment
+        } else if is_raw_string_start(&chars[i..]) {
+            // Raw string r"(...)", r"-[...]-", ...
+            let quote = chars[i + 1];
+            let mut j = i + 2;
+            let mut dashes = 0;
+            while chars.get(j) == Some(&'-') {
+                dashes += 1;
+                j += 1;
+            }
+            let close = match chars[j] {
+                '(' => ')',
+                '[' => ']',
+                _ => '}',
+            };
+            let terminator: Vec<char> = std::iter::once(close)
+                .chain(std::iter::repeat_n('-', dashes))
+                .chain(std::iter::once(quote))
+                .collect();
+            j += 1;
+            loop {
+                if j >= chars.len() {
+                    return Err(err(line, "unterminated raw string"));
+                }
+                if chars[j..].starts_with(&terminator) {
+                    j += terminator.len();
+                    break;
+                }
+                if chars[j] == '\n' {
+                    line += 1;
+                }
+                j += 1;
+            }
+            i = j;
+            Kind::Str
+        } else if c == '"' || c == '\'' || c == '`' {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(err(line, "unterminated string")),
+                    Some('\\') => i += 2,
+                    Some(&q) if q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some('\n') => {
+                        line += 1;
+                        i += 1;
+                    }
+                    Some(_) => i += 1,
+                }
+            }
+            if c == '`' { Kind::Ident } else { Kind::Str }
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            if c == '0' && matches!(chars.get(i + 1), Some('x') | Some('X')) {
+                i += 2;
+                while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                    i += 1;
+                }
+            } else {
+                while i < chars.len() {
+                    let d = chars[i];
+                    let exp_sign = (d == '-' || d == '+') && matches!(chars[i - 1], 'e' | 'E');
+                    if d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exp_sign {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            if matches!(chars.get(i), Some('L') | Some('i')) {
+                i += 1;
+            }
+            Kind::Number
+        } else if c.is_alphabetic() || c == '.' || c == '_' {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                Kind::Keyword
+            } else {
+                Kind::Ident
+            }
+        } else if c == '%' {
+            i += 1;
+            while i < chars.len() && chars[i] != '%' {
+                if chars[i] == '\n' {
+                    return Err(err(line, "unterminated %op%"));
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(err(line, "unterminated %op%"));
+            }
+            i += 1;
+            Kind::Op
+        } else if c == '(' || c == '{' {
+            let head = tokens.last().is_some_and(|(prev, _)| {
+                matches!(
+                    prev.text.as_str(),
+                    "if" | "for" | "while" | "function" | "\\"
+                )
+            });
+            if c == '(' && head {
+                heads.push(brackets.len());
+            }
+            brackets.push(if c == '(' { "(" } else { "{" });
+            i += 1;
+            Kind::Open
+        } else if c == '[' {
+            if chars.get(i + 1) == Some(&'[') {
+                brackets.push("[[");
+                i += 2;
+            } else {
+                brackets.push("[");
+                i += 1;
+            }
+            Kind::Open
+        } else if c == ')' || c == '}' || c == ']' {
+            let open = brackets
+                .pop()
+                .ok_or_else(|| err(line, &format!("unexpected `{c}`")))?;
+            if heads.last() == Some(&brackets.len()) {
+                heads.pop();
+                closes_head = true;
+            }
+            let expected = match open {
+                "(" => ')',
+                "{" => '}',
+                _ => ']',
+            };
+            if c != expected {
+                return Err(err(line, &format!("`{c}` does not close `{open}`")));
+            }
+            i += if open == "[[" {
+                if chars.get(i + 1) != Some(&']') {
+                    return Err(err(line, "`[[` must be closed by `]]`"));
+                }
+                2
+            } else {
+                1
+            };
+            Kind::Close
+        } else if c == ',' {
+            i += 1;
+            Kind::Comma
+        } else if c == ';' {
+            i += 1;
+            Kind::Semi
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 3)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| err(line, &format!("unexpected character `{c}`")))?;
+            i += op.chars().count();
+            Kind::Op
+        };
+        let text: String = chars[start..i].iter().collect();
+        // A statement starting on a new line outside `(`/`[` begins with a
+        // prefix operator; inside brackets the newline does not end it.
+        let statement_start = newlines > 0 && brackets.last().is_none_or(|b| *b == "{");
+        let unary = kind == Kind::Op
+            && (text == "\\"
+                || matches!(text.as_str(), "+" | "-" | "!" | "~" | "?")
+                    && tokens.last().is_none_or(|(prev, _)| {
+                        statement_start || after_head || is_prefix_position(prev)
+                    }));
+        after_head = closes_head;
+        tokens.push((
+            Tok {
+                kind,
+                text: if kind == Kind::Comment {
+                    normalize_comment(text.trim_end())
+                } else {
+                    text
+                },
+                unary,
+                glued: start > 0 && !chars[start - 1].is_whitespace(),
+            },
+            newlines,
+        ));
+        newlines = 0;
+    }
+    if let Some(open) = brackets.last() {
+        return Err(err(line, &format!("`{open}` is never closed")));
     }
+    Ok(tokens)
+}
+
+/// `r"(...)"`, `R'[...]'`, `r"--{...}--"`.
+fn is_raw_string_start(chars: &[char]) -> bool {
+    matches!(chars.first(), Some('r') | Some('R'))
+        && matches!(chars.get(1), Some('"') | Some('\''))
+        && matches!(
+            chars.iter().skip(2).find(|&&d| d != '-'),
+            Some('(') | Some('[') | Some('{')
+        )
+}
 
-    if out == text {
-        Ok(None)
+/// Whether an operator following `prev` must be a prefix operator.
+fn is_prefix_position(prev: &Tok) -> bool {
+    match prev.kind {
+        Kind::Op => true,
+        Kind::Open | Kind::Comma | Kind::Semi => true,
+        Kind::Keyword => matches!(prev.text.as_str(), "in" | "else" | "repeat"),
+        _ => false,
+    }
+}
+
+/// `#comment` → `# comment`, keeping roxygen (`#'`), shebang and `##` markers.
+fn normalize_comment(text: &str) -> String {
+    let body = &text[1..];
+    if body.is_empty() || body.starts_with([' ', '\'', '!', '#', '-', '+']) {
+        text.to_string()
     } else {
-        Ok(Some(ensure_newline(out)))
+        format!("# {body}")
+    }
+}
+
+fn split_lines(tokens: Vec<(Tok, usize)>) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut current = Line::default();
+    for (tok, newlines) in tokens {
+        if newlines > 0 && !current.tokens.is_empty() {
+            lines.push(std::mem::take(&mut current));
+            current.blank_before = newlines > 1;
+        }
+        current.tokens.push(tok);
+    }
+    if !current.tokens.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Space between two adjacent tokens on one line.
+fn spaced(prev: &Tok, next: &Tok) -> bool {
+    match (prev.kind, next.kind) {
+        (_, Kind::Comment) => true,
+        (_, Kind::Comma | Kind::Semi) => false,
+        (Kind::Comma | Kind::Semi, _) => true,
+        // `{ x }` on one line, but `({`, `[[`, `((` and `{{ x }}` stay tight.
+        (Kind::Open, Kind::Close) => false,
+        (Kind::Open, Kind::Open) | (Kind::Close, Kind::Close)
+            if prev.text == next.text && next.glued =>
+        {
+            false
+        }
+        (Kind::Open, _) => prev.text == "{",
+        (_, Kind::Close) => next.text == "}",
+        (_, Kind::Open) if next.text == "{" => prev.kind != Kind::Open,
+        (Kind::Op, Kind::Open) if prev.text == "\\" => false,
+        (Kind::Ident | Kind::Close | Kind::Str, Kind::Open) => false,
+        (Kind::Keyword, Kind::Open) => prev.text != "function",
+        (Kind::Op, _) if prev.unary => false,
+        (Kind::Op, _) => !TIGHT.contains(&prev.text.as_str()),
+        (_, Kind::Op) if next.unary => true,
+        (_, Kind::Op) => !TIGHT.contains(&next.text.as_str()),
+        _ => true,
+    }
+}
+
+fn render(tokens: &[Tok]) -> String {
+    let mut out = String::new();
+    for (idx, tok) in tokens.iter().enumerate() {
+        if idx > 0 && spaced(&tokens[idx - 1], tok) {
+            out.push(' ');
+        }
+        out.push_str(&tok.text);
+    }
+    out
+}
+
+/// Break over-long lines inside the outermost call that has several
+/// arguments: one argument per line and the closing parenthesis on its own.
+fn wrap_long_calls(lines: Vec<Line>, opts: &RlangOptions) -> Vec<Line> {
+    let mut out = Vec::with_capacity(lines.len());
+    let mut depth = 0usize;
+    for line in lines {
+        // Bracket depth approximates the indentation the line will get.
+        let width = depth * opts.indent + render(&line.tokens).chars().count();
+        for tok in &line.tokens {
+            match tok.kind {
+                Kind::Open => depth += 1,
+                Kind::Close => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if width <= opts.line_width || line.tokens.iter().any(|t| t.kind == Kind::Comment) {
+            out.push(line);
+            continue;
+        }
+        match split_call(&line.tokens) {
+            Some(parts) => {
+                let mut first = true;
+                for tokens in parts {
+                    out.push(Line {
+                        tokens,
+                        blank_before: first && line.blank_before,
+                    });
+                    first = false;
+                }
+            }
+            None => out.push(line),
+        }
+    }
+    out
+}
+
+fn split_call(tokens: &[Tok]) -> Option<Vec<Vec<Tok>>> {
+    for (open_idx, tok) in tokens.iter().enumerate() {
+        if tok.text != "(" || open_idx == 0 || tokens[open_idx - 1].kind != Kind::Ident {
+            continue;
+        }
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        let mut close_idx = None;
+        for (j, t) in tokens.iter().enumerate().skip(open_idx) {
+            match t.kind {
+                Kind::Open => depth += 1,
+                Kind::Close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close_idx = Some(j);
+                        break;
+                    }
+                }
+                Kind::Comma if depth == 1 => commas.push(j),
+                _ => {}
+            }
+        }
+        let Some(close_idx) = close_idx else {
+            // The call continues on the next line; leave it alone.
+            return None;
+        };
+        if commas.is_empty() {
+            continue;
+        }
+        let mut parts = vec![tokens[..=open_idx].to_vec()];
+        let mut start = open_idx + 1;
+        for comma in commas {
+            parts.push(tokens[start..=comma].to_vec());
+            start = comma + 1;
+        }
+        parts.push(tokens[start..close_idx].to_vec());
+        parts.push(tokens[close_idx..].to_vec());
+        return Some(parts);
+    }
+    None
+}
+
+fn print_lines(lines: &[Line], opts: &RlangOptions) -> String {
+    let mut out = String::new();
+    // Indentation level of every printed line, and the open brackets with the
+    // index of the line that opened them.
+    let mut levels: Vec<usize> = Vec::with_capacity(lines.len());
+    let mut open: Vec<(usize, char)> = Vec::new();
+    let mut continued = false;
+    for (idx, line) in lines.iter().enumerate() {
+        let first = &line.tokens[0];
+        let closes_first = first.kind == Kind::Close;
+        let after_open = idx > 0 && lines[idx - 1].tokens.last().is_some_and(|t| t.text == "{");
+        if line.blank_before && !closes_first && !after_open && !out.is_empty() {
+            out.push('\n');
+        }
+
+        // Brackets opened together on one line add a single level; a closing
+        // bracket lines up with the line that opened it.
+        let level = match open.last() {
+            Some(&(opener, _)) if closes_first => levels[opener],
+            Some(&(opener, _)) => levels[opener] + 1 + usize::from(continued),
+            None => usize::from(continued && !closes_first),
+        };
+        levels.push(level);
+
+        out.push_str(&" ".repeat(level * opts.indent));
+        out.push_str(&render(&line.tokens));
+        out.push('\n');
+
+        for tok in &line.tokens {
+            match tok.kind {
+                Kind::Open => open.push((idx, tok.text.chars().next().unwrap_or('('))),
+                Kind::Close => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        let last = line
+            .tokens
+            .iter()
+            .rev()
+            .find(|t| t.kind != Kind::Comment)
+            .unwrap_or(first);
+        // A trailing infix operator continues the expression on the next line;
+        // inside `(`/`[` the bracket already provides the indentation.
+        let in_call = open.last().is_some_and(|(_, c)| *c != '{');
+        continued = last.kind == Kind::Op && !last.unary && !in_call;
     }
+    out
 }
@@ -0,0 +1,374 @@
+use std::path::Path;
+
+use anyhow::Result;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::{FormatError, ensure_newline, markdown};
+
+/// reStructuredText: section adornments sized to their titles, `-` bullets,
+/// single blank lines, re-padded grid tables and `code-block` content run
+/// through the matching formatter. Literal blocks, comments and raw
+/// directives are copied as written.
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    let (out, warnings) = format_with_warnings(text);
+    for warning in warnings {
+        eprintln!("{}: warning: {warning}", path.display());
+    }
+    Ok(out)
+}
+
+pub fn format_with_warnings(text: &str) -> (Option<String>, Vec<String>) {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let mut f = Formatter {
+        lines: &lines,
+        out: Vec::new(),
+        warnings: Vec::new(),
+    };
+    f.run();
+    let out = ensure_newline(f.out.join("\n"));
+    ((out != text).then_some(out), f.warnings)
+}
+
+/// Directives whose content is not reStructuredText.
+const RAW_DIRECTIVES: &[&str] = &[
+    "raw",
+    "math",
+    "csv-table",
+    "parsed-literal",
+    "productionlist",
+];
+const CODE_DIRECTIVES: &[&str] = &["code-block", "code", "sourcecode"];
+
+struct Formatter<'a> {
+    lines: &'a [&'a str],
+    out: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Formatter<'_> {
+    fn run(&mut self) {
+        let mut i = 0;
+        while i < self.lines.len() {
+            let line = self.lines[i];
+            if line.is_empty() {
+                i += 1;
+                continue;
+            }
+            if i > 0 && self.lines[i - 1].is_empty() && !self.out.is_empty() {
+                self.out.push(String::new());
+            }
+            if let Some(next) = self.heading(i) {
+                i = next;
+                continue;
+            }
+            if let Some(next) = self.grid_table(i) {
+                i = next;
+                continue;
+            }
+
+            let depth = indent(line);
+            let trimmed = &line[depth..];
+            self.out
+                .push(self.bullet(i).unwrap_or_else(|| line.to_string()));
+            i += 1;
+            match directive(trimmed) {
+                Some((name, args)) if CODE_DIRECTIVES.contains(&name) => {
+                    i = self.code_block(i, depth, args.split_whitespace().next());
+                }
+                Some((name, _)) if RAW_DIRECTIVES.contains(&name) => i = self.verbatim(i, depth),
+                Some(_) => {}
+                None if is_comment(trimmed) || trimmed.ends_with("::") => {
+                    i = self.verbatim(i, depth)
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// A title with an underline, or with matching over- and underlines.
+    fn heading(&mut self, i: usize) -> Option<usize> {
+        let lines = self.lines;
+        let next = *lines.get(i + 1)?;
+        let (title, marker, overline, end) = match (adornment(lines[i]), lines.get(i + 2)) {
+            (Some(over), Some(&under))
+                if adornment(next).is_none()
+                    && !next.trim().is_empty()
+                    && adornment(under) == Some(over) =>
+            {
+                (next.trim(), over, true, i + 3)
+            }
+            (None, _)
+                if (i == 0 || lines[i - 1].is_empty())
+                    && indent(lines[i]) == 0
+                    && bullet_marker(lines[i]).is_none() =>
+            {
+                let marker = adornment(next)?;
+                if next.len() < 4 && next.len() < lines[i].width() {
+                    return None;
+                }
+                (lines[i], marker, false, i + 2)
+            }
+            _ => return None,
+        };
+        if self.out.last().is_some_and(|l| !l.is_empty()) {
+            self.out.push(String::new());
+        }
+        let rule = marker.to_string().repeat(title.width());
+        if overline {
+            self.out.push(rule.clone());
+        }
+        self.out.push(title.to_string());
+        self.out.push(rule);
+        if lines.get(end).is_some_and(|l| !l.is_empty()) {
+            self.out.push(String::new());
+        }
+        Some(end)
+    }
+
+    /// `*`, `+` and `•` bullets become `-`, which keeps item text in place.
+    /// A list is rewritten whole, and not when that would merge it with a
+    /// `-` list next to it, since a change of bullet ends a list.
+    fn bullet(&self, i: usize) -> Option<String> {
+        let lines = self.lines;
+        let line = lines[i];
+        let depth = indent(line);
+        let marker = bullet_marker(line)?;
+        let starts_item = i == 0
+            || match lines[i - 1] {
+                "" => true,
+                prev => {
+                    (bullet_marker(prev).is_some() && indent(prev) == depth)
+                        || indent(prev) > depth
+                        || adornment(prev).is_some()
+                }
+            };
+        if !starts_item || marker == '-' {
+            return None;
+        }
+        // Blank lines, item bodies and items with the same bullet make up
+        // the list.
+        let in_list = |l: &str| {
+            l.is_empty()
+                || indent(l) > depth
+                || (indent(l) == depth && bullet_marker(l) == Some(marker))
+        };
+        let first = (0..i)
+            .rev()
+            .take_while(|&j| in_list(lines[j]))
+            .last()
+            .unwrap_or(i);
+        let end = (i..lines.len())
+            .find(|&j| !in_list(lines[j]))
+            .unwrap_or(lines.len());
+        // The first line outside the list on either side, past blank lines
+        // and the bodies of a neighbouring list's last item.
+        let dash_list = |j: Option<usize>| {
+            j.is_some_and(|j| indent(lines[j]) == depth && bullet_marker(lines[j]) == Some('-'))
+        };
+        let before = (0..first)
+            .rev()
+            .find(|&j| !lines[j].is_empty() && indent(lines[j]) <= depth);
+        let after = (end..lines.len()).find(|&j| !lines[j].is_empty());
+        if dash_list(before) || dash_list(after) {
+            return None;
+        }
+        Some(format!(
+            "{}-{}",
+            &line[..depth],
+            &line[depth + marker.len_utf8()..]
+        ))
+    }
+
+    /// Copies the block indented under the line before `i`, keeping its blank
+    /// lines except those that end it.
+    fn verbatim(&mut self, mut i: usize, parent: usize) -> usize {
+        let end = block_end(self.lines, i, parent);
+        while i < end {
+            self.out.push(self.lines[i].to_string());
+            i += 1;
+        }
+        i
+    }
+
+    fn code_block(&mut self, mut i: usize, parent: usize, lang: Option<&str>) -> usize {
+        let lines = self.lines;
+        let end = block_end(lines, i, parent);
+        while i < end && !lines[i].is_empty() && lines[i].trim_start().starts_with(':') {
+            self.out.push(lines[i].to_string());
+            i += 1;
+        }
+        while i < end && lines[i].is_empty() {
+            self.out.push(String::new());
+            i += 1;
+        }
+        let body = &lines[i..end];
+        let width = body
+            .iter()
+            .filter(|l| !l.is_empty())
+            .map(|l| indent(l))
+            .min()
+            .unwrap_or(0);
+        let kind = lang.and_then(markdown::code_kind);
+        let formatted = kind.and_then(|kind| {
+            let code: String = body
+                .iter()
+                .map(|l| format!("{}\n", l.get(width..).unwrap_or("")))
+                .collect();
+            markdown::format_code(kind, lang?, &code, i + 1, &mut self.warnings)
+        });
+        match formatted {
+            Some(code) => self.out.extend(code.lines().map(|l| match l {
+                "" => String::new(),
+                l => format!("{}{l}", " ".repeat(width)),
+            })),
+            None => self.out.extend(body.iter().map(|l| l.to_string())),
+        }
+        end
+    }
+
+    /// Re-pads a grid table whose rows all share the same column borders;
+    /// tables with spanning cells are copied as written.
+    fn grid_table(&mut self, i: usize) -> Option<usize> {
+        let lines = self.lines;
+        let margin = indent(lines[i]);
+        if !is_border(&lines[i][margin..]) {
+            return None;
+        }
+        let end = (i..lines.len())
+            .find(|&j| {
+                lines[j].len() <= margin
+                    || indent(lines[j]) != margin
+                    || !lines[j][margin..].starts_with(['+', '|'])
+            })
+            .unwrap_or(lines.len());
+        let table: Vec<&str> = lines[i..end].iter().map(|l| &l[margin..]).collect();
+        match align_grid(&table) {
+            Some(rows) => {
+                let pad = " ".repeat(margin);
+                self.out
+                    .extend(rows.into_iter().map(|row| format!("{pad}{row}")));
+            }
+            None => self.out.extend(lines[i..end].iter().map(|l| l.to_string())),
+        }
+        Some(end)
+    }
+}
+
+fn align_grid(table: &[&str]) -> Option<Vec<String>> {
+    let plus = |line: &str| -> Vec<usize> {
+        line.char_indices()
+            .filter(|&(_, c)| c == '+')
+            .map(|(at, _)| at)
+            .collect()
+    };
+    let columns = plus(table[0]);
+    let rows = table
+        .iter()
+        .map(|line| match is_border(line) {
+            true => (plus(line) == columns).then_some(None),
+            false => split_row(line, &columns).map(Some),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let mut widths = vec![1; columns.len() - 1];
+    for cells in rows.iter().flatten() {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.width());
+        }
+    }
+
+    let aligned = table.iter().zip(&rows).map(|(line, row)| match row {
+        None => {
+            let fill = if line.contains('=') { "=" } else { "-" };
+            widths.iter().fold(String::from("+"), |out, width| {
+                format!("{out}{}+", fill.repeat(width + 2))
+            })
+        }
+        Some(cells) => widths
+            .iter()
+            .zip(cells)
+            .fold(String::from("|"), |out, (width, cell)| {
+                format!("{out} {cell}{}|", " ".repeat(width - cell.width() + 1))
+            }),
+    });
+    Some(aligned.collect())
+}
+
+/// Cells of a table row cut at the display columns of the border's `+`.
+fn split_row(line: &str, columns: &[usize]) -> Option<Vec<String>> {
+    let mut cells = Vec::new();
+    let mut column = 0;
+    let mut start = None;
+    for (at, c) in line.char_indices() {
+        if columns.contains(&column) {
+            if c != '|' {
+                return None;
+            }
+            if let Some(start) = start {
+                let cell: &str = &line[start..at];
+                cells.push(
+                    cell.strip_prefix(' ')
+                        .unwrap_or(cell)
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+            start = Some(at + 1);
+        }
+        column += c.width().unwrap_or(0);
+    }
+    (column == columns.last()? + 1 && cells.len() == columns.len() - 1).then_some(cells)
+}
+
+fn block_end(lines: &[&str], start: usize, parent: usize) -> usize {
+    let mut end = start;
+    for (j, line) in lines.iter().enumerate().skip(start) {
+        if line.is_empty() {
+            continue;
+        }
+        if indent(line) <= parent {
+            break;
+        }
+        end = j + 1;
+    }
+    end
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_border(line: &str) -> bool {
+    line.len() > 2
+        && line.starts_with('+')
+        && line.ends_with('+')
+        && line.chars().all(|c| matches!(c, '+' | '-' | '='))
+}
+
+/// The character of a section adornment line such as `=====`.
+fn adornment(line: &str) -> Option<char> {
+    let first = line.chars().next()?;
+    (first.is_ascii_punctuation() && line.len() >= 2 && line.chars().all(|c| c == first))
+        .then_some(first)
+}
+
+fn bullet_marker(line: &str) -> Option<char> {
+    let mut chars = line.trim_start().chars();
+    let marker = chars
+        .next()
+        .filter(|c| matches!(c, '*' | '+' | '-' | '•'))?;
+    (chars.next() == Some(' ')).then_some(marker)
+}
+
+/// `(name, arguments)` of `.. name:: arguments`.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let (name, args) = line.strip_prefix(".. ")?.split_once("::")?;
+    (!name.is_empty() && !name.contains(char::is_whitespace) && !name.starts_with(['|', '_', '[']))
+        .then_some((name, args.trim()))
+}
+
+fn is_comment(line: &str) -> bool {
+    line == ".."
+        || line
+            .strip_prefix(".. ")
+            .is_some_and(|rest| !rest.starts_with(['|', '_', '[']))
+}
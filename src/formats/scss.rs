@@ -0,0 +1,901 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::css::{ColorCase, CssOptions, Quotes, SortProperties};
+use super::{FormatError, ensure_newline, options};
+
+/// Stylesheet dialect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Plain CSS, where `//` does not start a comment.
+    Css,
+    /// SCSS, and PostCSS with nesting and mixin plugins.
+    Scss,
+    Less,
+    /// The indented Sass syntax.
+    Sass,
+}
+
+pub fn format(path: &Path, text: &str, syntax: Syntax) -> Result<Option<String>, FormatError> {
+    format_with_options(text, syntax, &options::for_path(path).css)
+}
+
+/// Stylesheet formatter that keeps preprocessor syntax as written: nesting,
+/// `@mixin`/`@include`, `@extend`, `$`/`@` variables, maps, interpolation
+/// and Less guards. It lays out rules the way the CSS formatter does:
+/// - one selector per line, one declaration per line as `name: value;`
+/// - blocks indented by `indent`, `} @else {` joined
+/// - whitespace collapsed and `, ` after commas outside strings and `url()`
+/// - multi-line values such as maps indented by parenthesis depth
+/// - comments kept in place, blank lines kept but collapsed to one
+pub fn format_with_options(
+    text: &str,
+    syntax: Syntax,
+    opts: &CssOptions,
+) -> Result<Option<String>, FormatError> {
+    let out = match syntax {
+        Syntax::Sass => indented(text, opts),
+        _ => {
+            let mut parser = Parser {
+                text,
+                pos: 0,
+                syntax,
+            };
+            let items = parser.block(false)?;
+            let mut printer = Printer {
+                opts,
+                syntax,
+                lines: Vec::new(),
+            };
+            printer.items(&items, 0);
+            printer.lines.join("\n")
+        }
+    };
+    let out = ensure_newline(out);
+    Ok((out != text).then_some(out))
+}
+
+struct Item {
+    node: Node,
+    /// A blank line separates it from the previous item.
+    blank_before: bool,
+    /// Comment after the item on the same line.
+    trailing: Option<String>,
+}
+
+enum Node {
+    Comment(String),
+    /// Declaration, variable, `@include x;` or another statement, without `;`.
+    Statement(String),
+    Block {
+        prelude: String,
+        children: Vec<Item>,
+    },
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    syntax: Syntax,
+}
+
+impl Parser<'_> {
+    /// Items up to the `}` closing a `nested` block or the end of the text.
+    fn block(&mut self, nested: bool) -> Result<Vec<Item>, FormatError> {
+        let mut items: Vec<Item> = Vec::new();
+        let mut buf = String::new();
+        // Line breaks since the last item; only counted between items.
+        let mut newlines = 0;
+        let mut depth = 0usize;
+        let flush = |items: &mut Vec<Item>, buf: &mut String, newlines: &mut usize| {
+            let statement = buf.trim();
+            if !statement.is_empty() {
+                items.push(Item {
+                    node: Node::Statement(statement.to_string()),
+                    blank_before: *newlines > 1,
+                    trailing: None,
+                });
+            }
+            buf.clear();
+            *newlines = 0;
+        };
+
+        while let Some(c) = self.text[self.pos..].chars().next() {
+            let rest = &self.text[self.pos..];
+            if c.is_whitespace() {
+                match buf.is_empty() {
+                    true if c == '\n' => newlines += 1,
+                    true => {}
+                    false => buf.push(c),
+                }
+                self.pos += c.len_utf8();
+                continue;
+            }
+            if let Some(len) = opaque_len(rest, self.syntax, buf.chars().next_back()) {
+                let piece = &rest[..len];
+                self.pos += len;
+                if !piece.starts_with("/*") && !piece.starts_with("//") || !buf.is_empty() {
+                    buf.push_str(piece);
+                    continue;
+                }
+                let piece = piece.trim_end().to_string();
+                match items.last_mut() {
+                    Some(last)
+                        if newlines == 0
+                            && last.trailing.is_none()
+                            && !matches!(last.node, Node::Comment(_)) =>
+                    {
+                        last.trailing = Some(piece);
+                    }
+                    _ => {
+                        items.push(Item {
+                            node: Node::Comment(piece),
+                            blank_before: newlines > 1,
+                            trailing: None,
+                        });
+                        newlines = 0;
+                    }
+                }
+                continue;
+            }
+
+            self.pos += c.len_utf8();
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth = depth.saturating_sub(1),
+                ';' if depth == 0 => {
+                    flush(&mut items, &mut buf, &mut newlines);
+                    continue;
+                }
+                '{' if depth == 0 => {
+                    let prelude = buf.trim().to_string();
+                    let blank_before = newlines > 1;
+                    let children = self.block(true)?;
+                    items.push(Item {
+                        node: Node::Block { prelude, children },
+                        blank_before,
+                        trailing: None,
+                    });
+                    buf.clear();
+                    newlines = 0;
+                    continue;
+                }
+                '}' if depth == 0 => {
+                    if !nested {
+                        return Err(error(self.text, self.pos - 1, "unexpected `}`"));
+                    }
+                    flush(&mut items, &mut buf, &mut newlines);
+                    return Ok(items);
+                }
+                _ => {}
+            }
+            buf.push(c);
+        }
+        if nested {
+            return Err(error(self.text, self.text.len(), "unclosed block"));
+        }
+        flush(&mut items, &mut buf, &mut newlines);
+        Ok(items)
+    }
+}
+
+fn error(text: &str, at: usize, message: &str) -> FormatError {
+    let line = 1 + text[..at].matches('\n').count();
+    FormatError::Message(format!("line {line}: {message}"))
+}
+
+/// Length of the string, comment, interpolation or unquoted `url()` starting
+/// `rest`, which formatting leaves alone. `prev` is the character before.
+fn opaque_len(rest: &str, syntax: Syntax, prev: Option<char>) -> Option<usize> {
+    let line_end = rest.find('\n').unwrap_or(rest.len());
+    let b = rest.as_bytes();
+    match b.first()? {
+        q @ (b'"' | b'\'') => {
+            let mut i = 1;
+            while i < line_end {
+                match b[i] {
+                    b'\\' => i += 1,
+                    c if c == *q => return Some(i + 1),
+                    _ => {}
+                }
+                i += 1;
+            }
+            Some(line_end)
+        }
+        b'/' if rest.starts_with("/*") => Some(rest.find("*/").map_or(rest.len(), |p| p + 2)),
+        b'/' if rest.starts_with("//")
+            && syntax != Syntax::Css
+            && prev.is_none_or(char::is_whitespace) =>
+        {
+            Some(line_end)
+        }
+        b'#' | b'@' if b.get(1) == Some(&b'{') && (b[0] == b'@') == (syntax == Syntax::Less) => {
+            let mut depth = 0;
+            let mut quote = None;
+            for (at, c) in rest.char_indices().skip(1) {
+                match (quote, c) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '"' | '\'') => quote = Some(c),
+                    (None, '{') => depth += 1,
+                    (None, '}') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(at + 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Some(rest.len())
+        }
+        b'u' | b'U'
+            if rest.len() > 4
+                && rest.as_bytes()[..4].eq_ignore_ascii_case(b"url(")
+                && !prev.is_some_and(|p| p.is_alphanumeric() || p == '-' || p == '_') =>
+        {
+            let arg = rest[4..].trim_start();
+            if arg.starts_with(['"', '\'']) {
+                return None;
+            }
+            rest.find(')').map(|p| p + 1)
+        }
+        _ => None,
+    }
+}
+
+enum Span<'a> {
+    Code(&'a str),
+    Opaque(&'a str),
+}
+
+fn spans(text: &str, syntax: Syntax) -> Vec<Span<'_>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        if c == '\\' {
+            i += 1 + text[i + 1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        let prev = text[..i].chars().next_back();
+        match opaque_len(&text[i..], syntax, prev) {
+            Some(len) => {
+                if start < i {
+                    out.push(Span::Code(&text[start..i]));
+                }
+                out.push(Span::Opaque(&text[i..i + len]));
+                i += len;
+                start = i;
+            }
+            None => i += c.len_utf8(),
+        }
+    }
+    if start < text.len() {
+        out.push(Span::Code(&text[start..]));
+    }
+    out
+}
+
+/// First `target` outside parentheses, brackets and opaque spans.
+fn top_level(text: &str, syntax: Syntax, target: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut offset = 0;
+    for span in spans(text, syntax) {
+        if let Span::Code(code) = span {
+            for (at, c) in code.char_indices() {
+                match c {
+                    '(' | '[' => depth += 1,
+                    ')' | ']' => depth = depth.saturating_sub(1),
+                    c if c == target && depth == 0 => return Some(offset + at),
+                    _ => {}
+                }
+            }
+        }
+        offset += match span {
+            Span::Code(s) | Span::Opaque(s) => s.len(),
+        };
+    }
+    None
+}
+
+/// `(name, value)` of a declaration, including `$var: x` and Less `@var: x`.
+fn declaration(text: &str, syntax: Syntax) -> Option<(&str, &str)> {
+    let colon = top_level(text, syntax, ':')?;
+    let name = text[..colon].trim();
+    let value = &text[colon + 1..];
+    let body = match name.strip_prefix('@') {
+        Some(var) if syntax == Syntax::Less => var,
+        Some(_) => return None,
+        None => name,
+    };
+    let valid = !body.is_empty()
+        && !value.starts_with(':')
+        && body.chars().all(|c| {
+            c.is_alphanumeric() || matches!(c, '-' | '_' | '$' | '*' | '+' | '#' | '{' | '}')
+        });
+    valid.then_some((name, value))
+}
+
+/// Name of a plain property declaration, whose place may change.
+fn sortable(item: &Item, syntax: Syntax) -> Option<&str> {
+    let Node::Statement(text) = &item.node else {
+        return None;
+    };
+    declaration(text, syntax)
+        .map(|(name, _)| name)
+        .filter(|name| is_property(name))
+}
+
+fn is_property(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '-')
+        && !name.starts_with("--")
+        && !name.contains('#')
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// A declaration value: hex colors take `color-case`.
+    Value,
+    /// A selector list, split at top-level commas; combinators get spaces.
+    Selector,
+    /// `@media`-like preludes: `(width:1px)` gets a space after the colon.
+    Media,
+    Plain,
+}
+
+struct Printer<'a> {
+    opts: &'a CssOptions,
+    syntax: Syntax,
+    lines: Vec<String>,
+}
+
+impl Printer<'_> {
+    fn pad(&self, level: usize) -> String {
+        " ".repeat(level * self.opts.indent)
+    }
+
+    fn items(&mut self, items: &[Item], level: usize) {
+        let ordered = order(items, self.opts.sort_properties, self.syntax);
+        let pad = self.pad(level);
+        for (i, &(item, blank_before)) in ordered.iter().enumerate() {
+            if i > 0 && blank_before {
+                self.lines.push(String::new());
+            }
+            match &item.node {
+                Node::Comment(text) => self.comment(text, level),
+                Node::Statement(text) => {
+                    let mut lines = self.statement(text, level);
+                    if let Some(last) = lines.last_mut() {
+                        terminate(last, self.syntax);
+                    }
+                    self.lines.extend(lines);
+                }
+                Node::Block { prelude, children } => {
+                    let mut head = self.prelude(prelude, level);
+                    let joins_else = prelude.starts_with("@else")
+                        && i > 0
+                        && !blank_before
+                        && self.lines.last() == Some(&format!("{pad}}}"));
+                    if joins_else {
+                        self.lines.pop();
+                        head[0] = format!("{pad}}} {}", head[0].trim_start());
+                    }
+                    let last = head.pop().unwrap_or_default();
+                    self.lines.extend(head);
+                    let last = if last.trim().is_empty() {
+                        format!("{pad}{{")
+                    } else {
+                        format!("{last} {{")
+                    };
+                    if children.is_empty() {
+                        self.lines.push(format!("{last}}}"));
+                    } else {
+                        self.lines.push(last);
+                        self.items(children, level + 1);
+                        self.lines.push(format!("{pad}}}"));
+                    }
+                }
+            }
+            if let (Some(trailing), Some(last)) = (&item.trailing, self.lines.last_mut()) {
+                last.push(' ');
+                last.push_str(trailing);
+            }
+        }
+    }
+
+    fn comment(&mut self, text: &str, level: usize) {
+        let pad = self.pad(level);
+        for (i, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            self.lines.push(match (i, trimmed.starts_with('*')) {
+                (0, _) => format!("{pad}{trimmed}"),
+                (_, true) => format!("{pad} {trimmed}"),
+                _ => line.trim_end().to_string(),
+            });
+        }
+    }
+
+    fn statement(&self, text: &str, level: usize) -> Vec<String> {
+        let (prefix, body, mode) = match declaration(text, self.syntax) {
+            // Custom property values are arbitrary tokens.
+            Some((name, value)) if name.starts_with("--") => {
+                let text = format!("{}{name}: {}", self.pad(level), value.trim());
+                return text.lines().map(|l| l.trim_end().to_string()).collect();
+            }
+            Some((name, value)) => (format!("{name}: "), value.trim(), Mode::Value),
+            None => (String::new(), text, Mode::Plain),
+        };
+        self.continued(&prefix, body, mode, level)
+    }
+
+    /// Lines of `body` after `prefix`, continuation lines indented by the
+    /// parentheses open at their start.
+    fn continued(&self, prefix: &str, body: &str, mode: Mode, level: usize) -> Vec<String> {
+        let mut depth = 0;
+        let mut out = Vec::new();
+        for (i, line) in body.lines().enumerate() {
+            let start = depth;
+            let line = self.normalize(line, mode, &mut depth).join(", ");
+            if i == 0 {
+                let first = format!("{}{prefix}{line}", self.pad(level));
+                out.push(first.trim_end().to_string());
+            } else if !line.is_empty() {
+                let extra = match line.starts_with([')', ']']) {
+                    true => start.saturating_sub(1),
+                    false => start.max(1),
+                };
+                out.push(format!("{}{line}", self.pad(level + extra)));
+            }
+        }
+        out
+    }
+
+    fn prelude(&self, prelude: &str, level: usize) -> Vec<String> {
+        let pad = self.pad(level);
+        let line_comment = spans(prelude, self.syntax)
+            .iter()
+            .any(|s| matches!(s, Span::Opaque(s) if s.starts_with("//")));
+        if line_comment {
+            return self.continued("", prelude, Mode::Plain, level);
+        }
+        let joined = prelude.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        let keyword = joined.split_whitespace().next().unwrap_or_default();
+        let mode = match keyword {
+            "@media" | "@container" | "@custom-media" => Mode::Media,
+            k if k.starts_with('@') || joined.ends_with(':') => Mode::Plain,
+            // A Less guard's commas mean "or".
+            _ if joined.contains(" when ") => Mode::Plain,
+            _ => Mode::Selector,
+        };
+        let parts = self.normalize(&joined, mode, &mut 0);
+        let count = parts.len();
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, part)| match i + 1 < count {
+                true => format!("{pad}{part},"),
+                false => format!("{pad}{part}"),
+            })
+            .collect()
+    }
+
+    /// Whitespace and punctuation spacing of one line. Returns the
+    /// selectors of a selector list, or the line as its only element.
+    fn normalize(&self, line: &str, mode: Mode, depth: &mut usize) -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut out = String::new();
+        let mut space = false;
+        for span in spans(line, self.syntax) {
+            let code = match span {
+                Span::Opaque(text) => {
+                    if space && !out.is_empty() && !out.ends_with(['(', '[']) {
+                        out.push(' ');
+                    }
+                    space = false;
+                    out.push_str(&self.quote(text));
+                    continue;
+                }
+                Span::Code(code) => code,
+            };
+            let mut chars = code.char_indices().peekable();
+            while let Some((at, c)) = chars.next() {
+                if c.is_whitespace() {
+                    space = true;
+                    continue;
+                }
+                let combinator =
+                    mode == Mode::Selector && *depth == 0 && matches!(c, '>' | '+' | '~');
+                if c == ',' && mode == Mode::Selector && *depth == 0 {
+                    parts.push(std::mem::take(&mut out));
+                    space = false;
+                    continue;
+                }
+                if combinator {
+                    if !out.is_empty() {
+                        out.push(' ');
+                    }
+                    out.push(c);
+                    space = true;
+                    continue;
+                }
+                let glued =
+                    matches!(c, ')' | ']' | ',') || (c == ':' && mode == Mode::Media && *depth > 0);
+                if space && !out.is_empty() && !out.ends_with(['(', '[']) && !glued {
+                    out.push(' ');
+                }
+                space = false;
+                match c {
+                    '\\' => {
+                        out.push(c);
+                        if let Some((_, next)) = chars.next() {
+                            out.push(next);
+                        }
+                        continue;
+                    }
+                    '(' | '[' => *depth += 1,
+                    ')' | ']' => *depth = depth.saturating_sub(1),
+                    ',' => space = true,
+                    ':' if mode == Mode::Media && *depth > 0 => space = true,
+                    '#' if mode == Mode::Value => {
+                        let hex = code[at + 1..]
+                            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+                            .map_or(&code[at + 1..], |end| &code[at + 1..at + 1 + end]);
+                        if matches!(hex.len(), 3 | 4 | 6 | 8)
+                            && hex.chars().all(|c| c.is_ascii_hexdigit())
+                        {
+                            out.push('#');
+                            out.push_str(&match self.opts.color_case {
+                                ColorCase::Keep => hex.to_string(),
+                                ColorCase::Lower => hex.to_ascii_lowercase(),
+                                ColorCase::Upper => hex.to_ascii_uppercase(),
+                            });
+                            for _ in 0..hex.len() {
+                                chars.next();
+                            }
+                            continue;
+                        }
+                    }
+                    _ => {}
+                }
+                out.push(c);
+            }
+        }
+        parts.push(out);
+        parts
+    }
+
+    fn quote(&self, text: &str) -> String {
+        let (from, to) = match self.opts.quotes {
+            Quotes::Keep => return text.to_string(),
+            Quotes::Double => ('\'', '"'),
+            Quotes::Single => ('"', '\''),
+        };
+        let Some(inner) = text
+            .strip_prefix(from)
+            .and_then(|t| t.strip_suffix(from))
+            .filter(|_| text.len() >= 2)
+        else {
+            return text.to_string();
+        };
+        if inner.contains(to) {
+            return text.to_string();
+        }
+        format!(
+            "{to}{}{to}",
+            inner.replace(&format!("\\{from}"), &from.to_string())
+        )
+    }
+}
+
+/// Adds the `;`, before a trailing `//` comment if there is one.
+fn terminate(line: &mut String, syntax: Syntax) {
+    let comment = spans(line, syntax).last().and_then(|s| match s {
+        Span::Opaque(s) if s.starts_with("//") => Some(s.len()),
+        _ => None,
+    });
+    match comment {
+        Some(len) => {
+            let comment = line.split_off(line.len() - len);
+            let code = line.trim_end().to_string();
+            *line = format!("{code}; {comment}");
+        }
+        None => line.push(';'),
+    }
+}
+
+/// Items paired with whether a blank line goes before them. Runs of
+/// properties are sorted; a blank line above a run stays above it.
+fn order(items: &[Item], sort: SortProperties, syntax: Syntax) -> Vec<(&Item, bool)> {
+    let mut out: Vec<(&Item, bool)> = items.iter().map(|i| (i, i.blank_before)).collect();
+    if sort == SortProperties::None {
+        return out;
+    }
+    let mut start = 0;
+    while start < out.len() {
+        let mut end = start;
+        while end < out.len()
+            && sortable(out[end].0, syntax).is_some()
+            && (end == start || !out[end].1)
+        {
+            end += 1;
+        }
+        let blank = out[start].1;
+        out[start..end].sort_by_cached_key(|(item, _)| {
+            sort_key(sortable(item, syntax).unwrap_or_default(), sort)
+        });
+        for (i, entry) in out[start..end].iter_mut().enumerate() {
+            entry.1 = i == 0 && blank;
+        }
+        start = end.max(start + 1);
+    }
+    out
+}
+
+fn sort_key(name: &str, sort: SortProperties) -> (usize, usize, String, bool) {
+    let name = name.to_ascii_lowercase();
+    let bare = ["-webkit-", "-moz-", "-ms-", "-o-"]
+        .iter()
+        .find_map(|p| name.strip_prefix(p))
+        .unwrap_or(&name)
+        .to_string();
+    // Prefixed properties go before the standard one.
+    let standard = bare == name;
+    if sort == SortProperties::Alphabetical {
+        return (0, 0, bare, standard);
+    }
+    let mut key = bare.as_str();
+    loop {
+        for (group, names) in GROUPS.iter().enumerate() {
+            if let Some(pos) = names.iter().position(|n| *n == key) {
+                return (group, pos, bare, standard);
+            }
+        }
+        match key.rsplit_once('-') {
+            Some((parent, _)) if !parent.is_empty() => key = parent,
+            _ => return (GROUPS.len(), 0, bare, standard),
+        }
+    }
+}
+
+/// Property groups for `sort-properties = "grouped"`. Longhands not listed
+/// follow their shorthand, e.g. `border-top-left-radius` after `border-top`.
+const GROUPS: &[&[&str]] = &[
+    &[
+        "position", "inset", "top", "right", "bottom", "left", "z-index",
+    ],
+    &[
+        "display",
+        "flex",
+        "flex-direction",
+        "flex-flow",
+        "flex-wrap",
+        "flex-grow",
+        "flex-shrink",
+        "flex-basis",
+        "grid",
+        "grid-area",
+        "grid-template",
+        "grid-template-areas",
+        "grid-template-rows",
+        "grid-template-columns",
+        "grid-row",
+        "grid-column",
+        "grid-auto-flow",
+        "grid-auto-rows",
+        "grid-auto-columns",
+        "gap",
+        "row-gap",
+        "column-gap",
+        "place-content",
+        "place-items",
+        "place-self",
+        "align-content",
+        "align-items",
+        "align-self",
+        "justify-content",
+        "justify-items",
+        "justify-self",
+        "order",
+        "float",
+        "clear",
+        "box-sizing",
+        "width",
+        "min-width",
+        "max-width",
+        "height",
+        "min-height",
+        "max-height",
+        "aspect-ratio",
+        "margin",
+        "padding",
+        "overflow",
+        "overflow-x",
+        "overflow-y",
+    ],
+    &[
+        "font",
+        "font-family",
+        "font-size",
+        "font-style",
+        "font-weight",
+        "font-variant",
+        "line-height",
+        "letter-spacing",
+        "word-spacing",
+        "color",
+        "text-align",
+        "text-decoration",
+        "text-indent",
+        "text-overflow",
+        "text-transform",
+        "text-shadow",
+        "white-space",
+        "word-break",
+        "overflow-wrap",
+        "word-wrap",
+        "vertical-align",
+        "list-style",
+        "content",
+        "quotes",
+    ],
+    &[
+        "visibility",
+        "opacity",
+        "background",
+        "border",
+        "border-top",
+        "border-right",
+        "border-bottom",
+        "border-left",
+        "border-width",
+        "border-style",
+        "border-color",
+        "border-radius",
+        "outline",
+        "box-shadow",
+        "filter",
+        "backdrop-filter",
+        "cursor",
+        "pointer-events",
+        "user-select",
+    ],
+    &[
+        "transform",
+        "transform-origin",
+        "transition",
+        "animation",
+        "will-change",
+    ],
+];
+
+/// The indented syntax: one rule, declaration or statement per line, nesting
+/// by indentation.
+fn indented(text: &str, opts: &CssOptions) -> String {
+    let printer = Printer {
+        opts,
+        syntax: Syntax::Sass,
+        lines: Vec::new(),
+    };
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    let column = |line: &str| line.len() - line.trim_start().len();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut stack = vec![0];
+    let mut blank = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.is_empty() {
+            blank = !entries.is_empty();
+            i += 1;
+            continue;
+        }
+        let col = column(line);
+        while stack.len() > 1 && col < stack[stack.len() - 1] {
+            stack.pop();
+        }
+        if col > stack[stack.len() - 1] {
+            stack.push(col);
+        }
+        let level = stack.len() - 1;
+        let content = line.trim_start();
+        let pad = printer.pad(level);
+        i += 1;
+
+        if content.starts_with("//") || content.starts_with("/*") {
+            // Comment lines continue while indented past the comment.
+            let mut text = format!("{pad}{content}");
+            while i < lines.len() && !lines[i].is_empty() && column(lines[i]) > col {
+                text.push('\n');
+                text.push_str(&pad);
+                text.push_str(&lines[i][col..]);
+                i += 1;
+            }
+            entries.push(Entry {
+                level,
+                text,
+                property: None,
+                blank,
+            });
+            blank = false;
+            continue;
+        }
+        let text = content;
+
+        let has_children = lines[i..]
+            .iter()
+            .find(|l| !l.is_empty())
+            .is_some_and(|l| column(l) > col);
+        let (out, property) = match declaration(text, Syntax::Sass) {
+            _ if text.starts_with(['@', '+', '=']) || text.ends_with(',') => (
+                printer.normalize(text, Mode::Plain, &mut 0).join(", "),
+                None,
+            ),
+            Some((name, value)) if !has_children => {
+                let value = printer
+                    .normalize(value.trim(), Mode::Value, &mut 0)
+                    .join(", ");
+                let property = is_property(name).then(|| name.to_string());
+                (format!("{name}: {value}").trim_end().to_string(), property)
+            }
+            _ if has_children && !text.ends_with(':') => (
+                printer.normalize(text, Mode::Selector, &mut 0).join(", "),
+                None,
+            ),
+            _ => (
+                printer.normalize(text, Mode::Plain, &mut 0).join(", "),
+                None,
+            ),
+        };
+        entries.push(Entry {
+            level,
+            text: format!("{pad}{out}"),
+            property,
+            blank,
+        });
+        blank = false;
+    }
+
+    if opts.sort_properties != SortProperties::None {
+        let mut start = 0;
+        while start < entries.len() {
+            let mut end = start;
+            while end < entries.len()
+                && entries[end].property.is_some()
+                && entries[end].level == entries[start].level
+                && (end == start || !entries[end].blank)
+            {
+                end += 1;
+            }
+            let blank = entries[start].blank;
+            entries[start..end].sort_by_cached_key(|e| {
+                sort_key(
+                    e.property.as_deref().unwrap_or_default(),
+                    opts.sort_properties,
+                )
+            });
+            for (i, entry) in entries[start..end].iter_mut().enumerate() {
+                entry.blank = i == 0 && blank;
+            }
+            start = end.max(start + 1);
+        }
+    }
+
+    let mut out = Vec::new();
+    for entry in entries {
+        if entry.blank {
+            out.push(String::new());
+        }
+        out.push(entry.text);
+    }
+    out.join("\n")
+}
+
+struct Entry {
+    level: usize,
+    text: String,
+    /// Name of a plain property declaration.
+    property: Option<String>,
+    blank: bool,
+}
@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::html::{self, HtmlOptions, Template};
+use super::scss::{self, Syntax};
+use super::{FormatError, css, ensure_newline, javascript, options, typescript};
+
+/// Single-file component flavour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framework {
+    Vue,
+    Svelte,
+    Astro,
+}
+
+pub fn format(
+    path: &Path,
+    text: &str,
+    framework: Framework,
+) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).html, framework)
+}
+
+/// Single-file components are split into top-level sections, each formatted
+/// on its own and joined with one blank line:
+/// - `<script>` with the TypeScript (`lang="ts"`, and always in Astro) or
+///   JavaScript backend, unindented
+/// - `<style>` with the CSS, SCSS, Less or Sass formatter
+/// - Vue's `<template>`, and the markup around Svelte and Astro blocks, with
+///   the HTML printer in the framework's template mode
+/// - Astro's `---` component script as TypeScript
+///
+/// Vue custom blocks such as `<i18n>` are copied as written. Only tags that
+/// start a line are top-level sections; an indented `<script>` belongs to
+/// the markup around it.
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &HtmlOptions,
+    framework: Framework,
+) -> Result<Option<String>, FormatError> {
+    let mut out = Vec::new();
+    for part in split(text, framework)? {
+        let formatted = match part {
+            Part::Frontmatter(code) => match code.trim().is_empty() {
+                true => "---\n---".to_string(),
+                false => {
+                    let code = typescript::format(&path.with_extension("ts"), code)
+                        .map_err(|e| FormatError::Message(format!("frontmatter: {e}")))?
+                        .unwrap_or_else(|| code.to_string());
+                    format!("---\n{}\n---", code.trim_end())
+                }
+            },
+            Part::Markup(markup) => {
+                let template = match framework {
+                    // Comments between Vue blocks.
+                    Framework::Vue => {
+                        out.push(markup.trim().to_string());
+                        continue;
+                    }
+                    Framework::Svelte => Template::Svelte,
+                    Framework::Astro => Template::Astro,
+                };
+                html::format_template_with_options(path, markup, opts, template)?
+                    .unwrap_or_else(|| markup.to_string())
+                    .trim()
+                    .to_string()
+            }
+            Part::Block(block) => format_block(path, opts, framework, &block)?,
+        };
+        out.push(formatted);
+    }
+    let out = ensure_newline(out.join("\n\n"));
+    Ok((out != text).then_some(out))
+}
+
+enum Part<'a> {
+    /// Astro's `---` fenced component script.
+    Frontmatter(&'a str),
+    /// Anything between blocks.
+    Markup(&'a str),
+    Block(Block<'a>),
+}
+
+struct Block<'a> {
+    tag: String,
+    open: &'a str,
+    body: &'a str,
+    close: &'a str,
+}
+
+impl Block<'_> {
+    fn verbatim(&self) -> String {
+        format!("{}{}{}", self.open, self.body, self.close)
+    }
+}
+
+fn format_block(
+    path: &Path,
+    opts: &HtmlOptions,
+    framework: Framework,
+    block: &Block,
+) -> Result<String, FormatError> {
+    let lang = attr(block.open, "lang").map(str::to_ascii_lowercase);
+    let code = match block.tag.as_str() {
+        "template" if matches!(lang.as_deref(), None | Some("html")) => {
+            let whole = block.verbatim();
+            let out = html::format_template_with_options(path, &whole, opts, Template::Vue)?;
+            return Ok(out.unwrap_or(whole).trim_end().to_string());
+        }
+        _ if block.body.trim().is_empty() => None,
+        "script" => {
+            let (ext, ts) = match lang.as_deref() {
+                Some("ts" | "typescript") => ("ts", true),
+                Some("tsx") => ("tsx", true),
+                Some("jsx") => ("jsx", false),
+                None if framework == Framework::Astro => ("ts", true),
+                None | Some("js" | "javascript") => ("js", false),
+                Some(_) => return Ok(block.verbatim()),
+            };
+            let path = path.with_extension(ext);
+            let out = match ts {
+                true => typescript::format(&path, block.body),
+                false => javascript::format(&path, block.body),
+            }
+            .map_err(|e| FormatError::Message(format!("<script> block: {e}")))?;
+            Some(out.unwrap_or_else(|| block.body.to_string()))
+        }
+        "style" => {
+            let path = path.with_extension("css");
+            match lang.as_deref() {
+                None | Some("css" | "postcss") => Some(
+                    css::format(&path, block.body)
+                        .map_err(|e| FormatError::Message(format!("<style> block: {e}")))?
+                        .unwrap_or_else(|| block.body.to_string()),
+                ),
+                Some(lang @ ("scss" | "less" | "sass")) => {
+                    let syntax = match lang {
+                        "less" => Syntax::Less,
+                        "sass" => Syntax::Sass,
+                        _ => Syntax::Scss,
+                    };
+                    Some(
+                        scss::format(&path, block.body, syntax)
+                            .map_err(|e| FormatError::Message(format!("<style> block: {e}")))?
+                            .unwrap_or_else(|| block.body.to_string()),
+                    )
+                }
+                Some(_) => None,
+            }
+        }
+        _ => None,
+    };
+    Ok(match code {
+        Some(code) => format!("{}\n{}\n{}", block.open, code.trim(), block.close),
+        None if block.body.trim().is_empty() => format!("{}{}", block.open, block.close),
+        None => block.verbatim(),
+    })
+}
+
+fn split(text: &str, framework: Framework) -> Result<Vec<Part<'_>>, FormatError> {
+    let mut parts = Vec::new();
+    let mut i = 0;
+    if framework == Framework::Astro {
+        if let Some(rest) = text.strip_prefix("---\n") {
+            let end = match rest.starts_with("---") {
+                true => Some(0),
+                false => rest.find("\n---").map(|p| p + 1),
+            }
+            .ok_or_else(|| FormatError::Message("unterminated frontmatter".to_string()))?;
+            parts.push(Part::Frontmatter(&rest[..end]));
+            let after = 4 + end + 3;
+            i = text[after..]
+                .find('\n')
+                .map_or(text.len(), |p| after + p + 1);
+        }
+    }
+
+    let mut markup = i;
+    while i < text.len() {
+        let Some(tag) = block_tag(&text[i..], framework) else {
+            i = text[i..].find('\n').map_or(text.len(), |p| i + p + 1);
+            continue;
+        };
+        let open_end = start_tag_end(text, i)
+            .ok_or_else(|| FormatError::Message(format!("unterminated <{tag}> start tag")))?;
+        let open = &text[i..open_end];
+        let (body_end, end) = if open.ends_with("/>") {
+            (open_end, open_end)
+        } else {
+            let body_end = end_tag(text, open_end, &tag)
+                .ok_or_else(|| FormatError::Message(format!("unterminated <{tag}> block")))?;
+            let end = text[body_end..]
+                .find('>')
+                .map_or(text.len(), |p| body_end + p + 1);
+            (body_end, end)
+        };
+        push_markup(&mut parts, &text[markup..i]);
+        parts.push(Part::Block(Block {
+            tag,
+            open,
+            body: &text[open_end..body_end],
+            close: &text[body_end..end],
+        }));
+        i = end;
+        markup = end;
+    }
+    push_markup(&mut parts, &text[markup..]);
+    Ok(parts)
+}
+
+fn push_markup<'a>(parts: &mut Vec<Part<'a>>, markup: &'a str) {
+    if !markup.trim().is_empty() {
+        parts.push(Part::Markup(markup));
+    }
+}
+
+/// Lowercased name of the section starting `rest`: any element in Vue,
+/// `<script>` and `<style>` elsewhere.
+fn block_tag(rest: &str, framework: Framework) -> Option<String> {
+    let name_len = rest
+        .strip_prefix('<')?
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))?;
+    let name = rest[1..1 + name_len].to_ascii_lowercase();
+    let next = rest[1 + name_len..].chars().next()?;
+    if name.is_empty()
+        || !name.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !(next.is_whitespace() || next == '>' || next == '/')
+    {
+        return None;
+    }
+    (framework == Framework::Vue || matches!(name.as_str(), "script" | "style")).then_some(name)
+}
+
+/// Offset just past the `>` of the start tag at `start`, skipping quoted
+/// attribute values.
+fn start_tag_end(text: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (at, c) in text[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(start + at + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Start of the end tag closing `tag`; nested `<template>`s are counted.
+fn end_tag(text: &str, from: usize, tag: &str) -> Option<usize> {
+    let lower = text[from..].to_ascii_lowercase();
+    let open = format!("<{tag}");
+    let close = format!("</{tag}");
+    let boundary = |at: usize| {
+        lower[at..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_whitespace() || c == '>' || c == '/')
+    };
+    let mut depth = 0;
+    let mut at = 0;
+    while at < lower.len() {
+        if lower[at..].starts_with(&close) && boundary(at + close.len()) {
+            if depth == 0 {
+                return Some(from + at);
+            }
+            depth -= 1;
+            at += close.len();
+        } else if tag == "template" && lower[at..].starts_with(&open) && boundary(at + open.len()) {
+            depth += 1;
+            at += open.len();
+        } else {
+            at += lower[at..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+/// Value of attribute `name` in a start tag.
+fn attr<'a>(open: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = open;
+    while let Some(p) = rest.find(name) {
+        let before = rest[..p].chars().next_back();
+        let after = rest[p + name.len()..].trim_start();
+        rest = &rest[p + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        return match value.chars().next()? {
+            q @ ('"' | '\'') => value[1..].split(q).next(),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next(),
+        };
+    }
+    None
+}
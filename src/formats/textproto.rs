@@ -0,0 +1,487 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::{FormatError, ensure_newline};
+
+const INDENT: &str = "  ";
+
+/// Formatter for protobuf text format (`.textproto`, `.txtpb`, `.pbtxt`).
+///
+/// Output follows txtpbfmt conventions: one field per line, `name: value` for
+/// scalars, `name {` for nested messages (angle brackets become braces),
+/// separators dropped, comments and single blank lines kept. String literals
+/// are copied verbatim so escapes are never reinterpreted.
+pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let tokens = tokenize(text)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let root = parser.parse_block(None)?;
+
+    let mut out = String::new();
+    print_block(&root, 0, &mut out);
+    let out = ensure_newline(out);
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokKind {
+    Word,
+    Str,
+    Comment,
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokKind,
+    text: String,
+    line: usize,
+    /// Newlines between the previous token and this one.
+    newlines: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, FormatError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut newlines = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            newlines += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let kind = match c {
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                TokKind::Comment
+            }
+            '"' | '\'' => {
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None | Some('\n') => {
+                            return Err(FormatError::Message(format!(
+                                "textproto parse error: unterminated string on line {line}"
+                            )));
+                        }
+                        Some('\\') => i += 2,
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+                TokKind::Str
+            }
+            '{' | '}' | '<' | '>' | '[' | ']' | ':' | ',' | ';' => {
+                i += 1;
+                TokKind::Punct(c)
+            }
+            _ => {
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !"{}<>[]:,;#\"'".contains(chars[i])
+                {
+                    i += 1;
+                }
+                TokKind::Word
+            }
+        };
+        let text: String = chars[start..i].iter().collect();
+        tokens.push(Token {
+            kind,
+            text: if matches!(kind, TokKind::Comment) {
+                text.trim_end().to_string()
+            } else {
+                text
+            },
+            line,
+            newlines,
+        });
+        newlines = 0;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Default)]
+struct Block {
+    fields: Vec<Field>,
+    /// Comment on the same line as the opening brace.
+    open_comment: Option<String>,
+    /// Comments after the last field, before the closing brace.
+    end_comments: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Field {
+    leading: Vec<String>,
+    blank_before: bool,
+    name: String,
+    value: Value,
+    trailing: Option<String>,
+}
+
+#[derive(Debug)]
+enum Value {
+    /// One scalar, or several adjacent string literals with a flag telling
+    /// whether they were split across lines.
+    Scalar(Vec<String>, bool),
+    Message(Block),
+    List(Vec<ListItem>),
+}
+
+#[derive(Debug)]
+struct ListItem {
+    leading: Vec<String>,
+    value: Value,
+    trailing: Option<String>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn error(&self, what: &str) -> FormatError {
+        match self.peek() {
+            Some(tok) => FormatError::Message(format!(
+                "textproto parse error on line {}: {what}, found `{}`",
+                tok.line, tok.text
+            )),
+            None => {
+                FormatError::Message(format!("textproto parse error: {what}, found end of input"))
+            }
+        }
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        if self.peek().is_some_and(|t| t.kind == TokKind::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn trailing_comment(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(tok) if tok.kind == TokKind::Comment && tok.newlines == 0 => {
+                self.next().map(|t| t.text)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse fields until `close` (or end of input for the root message).
+    fn parse_block(&mut self, close: Option<char>) -> Result<Block, FormatError> {
+        let mut block = Block {
+            open_comment: if close.is_some() {
+                self.trailing_comment()
+            } else {
+                None
+            },
+            ..Block::default()
+        };
+        let mut leading = Vec::new();
+        let mut blank_before = false;
+        loop {
+            let Some(tok) = self.peek().cloned() else {
+                if close.is_some() {
+                    return Err(self.error("unclosed message"));
+                }
+                block.end_comments = leading;
+                return Ok(block);
+            };
+            if leading.is_empty() && (tok.newlines > 1) && !block.fields.is_empty() {
+                blank_before = true;
+            }
+            match tok.kind {
+                TokKind::Comment => {
+                    if tok.newlines > 1 && !leading.is_empty() {
+                        // Keep a blank line that separates comment groups.
+                        leading.push(String::new());
+                    }
+                    leading.push(tok.text);
+                    self.pos += 1;
+                }
+                TokKind::Punct(c) if Some(c) == close => {
+                    self.pos += 1;
+                    block.end_comments = leading;
+                    return Ok(block);
+                }
+                TokKind::Word | TokKind::Punct('[') => {
+                    let name = self.parse_field_name()?;
+                    let had_colon = self.eat_punct(':');
+                    let value = self.parse_value(had_colon)?;
+                    if !self.eat_punct(',') {
+                        self.eat_punct(';');
+                    }
+                    let trailing = self.trailing_comment();
+                    block.fields.push(Field {
+                        leading: std::mem::take(&mut leading),
+                        blank_before,
+                        name,
+                        value,
+                        trailing,
+                    });
+                    blank_before = false;
+                }
+                _ => return Err(self.error("expected field name")),
+            }
+        }
+    }
+
+    fn parse_field_name(&mut self) -> Result<String, FormatError> {
+        if self.eat_punct('[') {
+            // Extension or Any type URL: `[foo.bar]`, `[type.googleapis.com/x.Y]`.
+            let mut name = String::from("[");
+            loop {
+                match self.next() {
+                    Some(tok) if tok.kind == TokKind::Punct(']') => break,
+                    Some(tok) if tok.kind == TokKind::Word => name.push_str(&tok.text),
+                    _ => return Err(self.error("malformed extension name")),
+                }
+            }
+            name.push(']');
+            return Ok(name);
+        }
+        match self.next() {
+            Some(tok) if tok.kind == TokKind::Word => Ok(tok.text),
+            _ => Err(self.error("expected field name")),
+        }
+    }
+
+    fn parse_value(&mut self, had_colon: bool) -> Result<Value, FormatError> {
+        let Some(tok) = self.peek().cloned() else {
+            return Err(self.error("expected value"));
+        };
+        match tok.kind {
+            TokKind::Punct('{') | TokKind::Punct('<') => {
+                self.pos += 1;
+                let close = if tok.kind == TokKind::Punct('{') {
+                    '}'
+                } else {
+                    '>'
+                };
+                Ok(Value::Message(self.parse_block(Some(close))?))
+            }
+            TokKind::Punct('[') if had_colon => {
+                self.pos += 1;
+                self.parse_list()
+            }
+            TokKind::Str => {
+                let mut parts = Vec::new();
+                let mut multiline = false;
+                while let Some(t) = self.peek() {
+                    if t.kind != TokKind::Str {
+                        break;
+                    }
+                    if !parts.is_empty() && t.newlines > 0 {
+                        multiline = true;
+                    }
+                    parts.push(t.text.clone());
+                    self.pos += 1;
+                }
+                Ok(Value::Scalar(parts, multiline))
+            }
+            TokKind::Word if had_colon => {
+                self.pos += 1;
+                Ok(Value::Scalar(vec![tok.text], false))
+            }
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Value, FormatError> {
+        let mut items = Vec::new();
+        let mut leading = Vec::new();
+        loop {
+            let Some(tok) = self.peek().cloned() else {
+                return Err(self.error("unclosed list"));
+            };
+            match tok.kind {
+                TokKind::Punct(']') => {
+                    self.pos += 1;
+                    if !leading.is_empty() {
+                        items.push(ListItem {
+                            leading,
+                            value: Value::Scalar(Vec::new(), false),
+                            trailing: None,
+                        });
+                    }
+                    return Ok(Value::List(items));
+                }
+                TokKind::Comment => {
+                    leading.push(tok.text);
+                    self.pos += 1;
+                }
+                TokKind::Punct('[') => return Err(self.error("lists cannot be nested")),
+                _ => {
+                    let value = self.parse_value(true)?;
+                    self.eat_punct(',');
+                    let trailing = self.trailing_comment();
+                    items.push(ListItem {
+                        leading: std::mem::take(&mut leading),
+                        value,
+                        trailing,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn push_line(out: &mut String, indent: usize, text: &str) {
+    if text.is_empty() {
+        out.push('\n');
+        return;
+    }
+    out.push_str(&INDENT.repeat(indent));
+    out.push_str(text);
+    out.push('\n');
+}
+
+fn with_comment(text: String, comment: &Option<String>) -> String {
+    match comment {
+        Some(c) => format!("{text} {c}"),
+        None => text,
+    }
+}
+
+fn print_block(block: &Block, indent: usize, out: &mut String) {
+    for field in &block.fields {
+        if field.blank_before {
+            out.push('\n');
+        }
+        for c in &field.leading {
+            push_line(out, indent, c);
+        }
+        print_field(field, indent, out);
+    }
+    for c in &block.end_comments {
+        push_line(out, indent, c);
+    }
+}
+
+fn print_field(field: &Field, indent: usize, out: &mut String) {
+    match &field.value {
+        Value::Scalar(parts, multiline) => {
+            if *multiline {
+                push_line(out, indent, &format!("{}:", field.name));
+                let last = parts.len() - 1;
+                for (i, p) in parts.iter().enumerate() {
+                    let comment = if i == last { &field.trailing } else { &None };
+                    push_line(out, indent + 1, &with_comment(p.clone(), comment));
+                }
+            } else {
+                let line = format!("{}: {}", field.name, parts.join(" "));
+                push_line(out, indent, &with_comment(line, &field.trailing));
+            }
+        }
+        Value::Message(block) => print_message(&field.name, block, &field.trailing, indent, out),
+        Value::List(items) => {
+            let simple = items.iter().all(|item| {
+                item.leading.is_empty()
+                    && item.trailing.is_none()
+                    && matches!(item.value, Value::Scalar(_, false))
+            });
+            if simple {
+                let values = items
+                    .iter()
+                    .map(|item| match &item.value {
+                        Value::Scalar(parts, _) => parts.join(" "),
+                        _ => unreachable!(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let line = format!("{}: [{values}]", field.name);
+                push_line(out, indent, &with_comment(line, &field.trailing));
+                return;
+            }
+            push_line(out, indent, &format!("{}: [", field.name));
+            let count = items
+                .iter()
+                .filter(|i| !matches!(&i.value, Value::Scalar(p, _) if p.is_empty()))
+                .count();
+            let mut seen = 0;
+            for item in items {
+                for c in &item.leading {
+                    push_line(out, indent + 1, c);
+                }
+                let sep = match &item.value {
+                    Value::Scalar(p, _) if p.is_empty() => continue,
+                    _ => {
+                        seen += 1;
+                        if seen < count { "," } else { "" }
+                    }
+                };
+                match &item.value {
+                    Value::Message(block) => {
+                        print_message_body("{", block, indent + 1, out);
+                        push_line(
+                            out,
+                            indent + 1,
+                            &with_comment(format!("}}{sep}"), &item.trailing),
+                        );
+                    }
+                    Value::Scalar(parts, _) => {
+                        let line = format!("{}{sep}", parts.join(" "));
+                        push_line(out, indent + 1, &with_comment(line, &item.trailing));
+                    }
+                    Value::List(_) => unreachable!("the parser rejects nested lists"),
+                }
+            }
+            push_line(out, indent, &with_comment("]".to_string(), &field.trailing));
+        }
+    }
+}
+
+fn print_message(
+    name: &str,
+    block: &Block,
+    trailing: &Option<String>,
+    indent: usize,
+    out: &mut String,
+) {
+    if block.fields.is_empty() && block.end_comments.is_empty() && block.open_comment.is_none() {
+        push_line(out, indent, &with_comment(format!("{name} {{}}"), trailing));
+        return;
+    }
+    print_message_body(&format!("{name} {{"), block, indent, out);
+    push_line(out, indent, &with_comment("}".to_string(), trailing));
+}
+
+fn print_message_body(open: &str, block: &Block, indent: usize, out: &mut String) {
+    push_line(
+        out,
+        indent,
+        &with_comment(open.to_string(), &block.open_comment),
+    );
+    print_block(block, indent + 1, out);
+}
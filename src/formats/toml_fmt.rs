@@ -3,15 +3,646 @@ use std::path::Path;
 use anyhow::Result;
 use dprint_plugin_toml as toml;
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 
 use super::FormatError;
-use super::ensure_newline;
+use super::{ensure_newline, options};
 
 static CONF: Lazy<toml::configuration::Configuration> =
     Lazy::new(|| toml::configuration::ConfigurationBuilder::new().build());
 
+/// Presets order `[package]` themselves; dprint's Cargo conventions would
+/// undo that.
+static PRESET_CONF: Lazy<toml::configuration::Configuration> = Lazy::new(|| {
+    toml::configuration::ConfigurationBuilder::new()
+        .cargo_apply_conventions(false)
+        .build()
+});
+
+/// Sorting happens within groups of entries separated by blank lines, the
+/// way `cargo-sort` and dprint do it; comments move with the line below.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TomlOptions {
+    /// Pad keys so that the `=` of consecutive entries line up.
+    pub align_entries: bool,
+    pub wrap_arrays: WrapArrays,
+    /// Sort the keys of every table.
+    pub sort_keys: bool,
+    /// Sort tables by name; an `[[array]]` table keeps its sub-tables.
+    pub sort_tables: bool,
+    /// Canonical table and key order for `Cargo.toml` and `pyproject.toml`.
+    pub presets: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapArrays {
+    /// One line or several, as written.
+    #[default]
+    Preserve,
+    /// Every element of a non-empty array on its own line.
+    Always,
+    /// Join arrays onto one line unless they contain comments.
+    Never,
+}
+
+/// Table and key order of a well-known file. Tables and keys that are not
+/// listed follow the listed ones in their original order.
+struct Preset {
+    tables: &'static [&'static str],
+    /// Top-level tables whose sub-tables are sorted by name.
+    sorted_tables: &'static [&'static str],
+    /// Key order of particular tables, by dotted name.
+    fields: &'static [(&'static str, &'static [&'static str])],
+    /// Tables whose keys are sorted, by last name component.
+    sorted_keys: &'static [&'static str],
+}
+
+const CARGO_PACKAGE: &[&str] = &[
+    "name",
+    "version",
+    "authors",
+    "edition",
+    "rust-version",
+    "description",
+    "documentation",
+    "readme",
+    "homepage",
+    "repository",
+    "license",
+    "license-file",
+    "keywords",
+    "categories",
+    "workspace",
+    "build",
+    "links",
+    "exclude",
+    "include",
+    "publish",
+    "metadata",
+    "default-run",
+    "autobins",
+    "autoexamples",
+    "autotests",
+    "autobenches",
+    "resolver",
+];
+
+/// The order of the Cargo manifest reference; dependency tables sorted like
+/// `cargo-sort`.
+const CARGO_TOML: Preset = Preset {
+    tables: &[
+        "package",
+        "workspace",
+        "lib",
+        "bin",
+        "example",
+        "test",
+        "bench",
+        "features",
+        "dependencies",
+        "dev-dependencies",
+        "build-dependencies",
+        "target",
+        "badges",
+        "lints",
+        "profile",
+        "patch",
+        "replace",
+    ],
+    sorted_tables: &[
+        "dependencies",
+        "dev-dependencies",
+        "build-dependencies",
+        "target",
+    ],
+    fields: &[
+        ("package", CARGO_PACKAGE),
+        ("workspace.package", CARGO_PACKAGE),
+    ],
+    sorted_keys: &["dependencies", "dev-dependencies", "build-dependencies"],
+};
+
+/// PEP 517/621 tables first, then `[tool.*]` by tool name.
+const PYPROJECT_TOML: Preset = Preset {
+    tables: &["build-system", "project", "dependency-groups", "tool"],
+    sorted_tables: &["tool"],
+    fields: &[
+        (
+            "build-system",
+            &["requires", "build-backend", "backend-path"],
+        ),
+        (
+            "project",
+            &[
+                "name",
+                "version",
+                "description",
+                "readme",
+                "requires-python",
+                "license",
+                "license-files",
+                "authors",
+                "maintainers",
+                "keywords",
+                "classifiers",
+                "urls",
+                "scripts",
+                "gui-scripts",
+                "entry-points",
+                "dependencies",
+                "optional-dependencies",
+                "dynamic",
+            ],
+        ),
+    ],
+    sorted_keys: &[],
+};
+
+fn preset_for(path: &Path) -> Option<&'static Preset> {
+    match path.file_name()?.to_str()? {
+        "Cargo.toml" => Some(&CARGO_TOML),
+        "pyproject.toml" => Some(&PYPROJECT_TOML),
+        _ => None,
+    }
+}
+
 pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).toml)
+}
+
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &TomlOptions,
+) -> Result<Option<String>, FormatError> {
+    let preset = opts.presets.then(|| preset_for(path)).flatten();
+    let reorders = opts.sort_keys || opts.sort_tables || preset.is_some();
+    if !reorders && opts.wrap_arrays == WrapArrays::Preserve && !opts.align_entries {
+        let res = toml::format_text(path, text, &CONF)
+            .map_err(|e| FormatError::Message(e.to_string()))?;
+        return Ok(res.map(ensure_newline));
+    }
+
+    let mut sections = parse(text);
+    if reorders {
+        reorder(&mut sections, opts, preset);
+    }
+    if opts.wrap_arrays != WrapArrays::Preserve {
+        for entry in sections.iter_mut().flat_map(|s| s.entries.iter_mut()) {
+            entry.body = wrap_array(&entry.body, opts.wrap_arrays);
+        }
+    }
+    let source = emit(&sections);
+    let conf = if preset.is_some() {
+        &PRESET_CONF
+    } else {
+        &CONF
+    };
     let res =
-        toml::format_text(path, text, &CONF).map_err(|e| FormatError::Message(e.to_string()))?;
-    Ok(res.map(ensure_newline))
+        toml::format_text(path, &source, conf).map_err(|e| FormatError::Message(e.to_string()))?;
+    let mut out = res.unwrap_or(source);
+    if opts.align_entries {
+        out = align_entries(&out);
+    }
+    let out = ensure_newline(out);
+    Ok((out != text).then_some(out))
+}
+
+/// What `--check` reports besides "would reformat": the tables and keys the
+/// configured sorting would move.
+pub fn out_of_order(path: &Path, text: &str) -> Vec<String> {
+    let opts = &options::for_path(path).toml;
+    let preset = opts.presets.then(|| preset_for(path)).flatten();
+    if !(opts.sort_keys || opts.sort_tables || preset.is_some()) {
+        return Vec::new();
+    }
+    reorder(&mut parse(text), opts, preset)
+}
+
+struct Section<'t> {
+    /// Comments and blank lines above the header.
+    lead: Vec<&'t str>,
+    /// `None` for the entries before the first header.
+    header: Option<Header<'t>>,
+    entries: Vec<Entry<'t>>,
+    tail: Vec<&'t str>,
+}
+
+struct Header<'t> {
+    line: &'t str,
+    path: Vec<String>,
+    array: bool,
+}
+
+struct Entry<'t> {
+    lead: Vec<&'t str>,
+    key: String,
+    /// The entry's lines, several for multi-line arrays and strings.
+    body: String,
+}
+
+impl Section<'_> {
+    fn name(&self) -> String {
+        match &self.header {
+            Some(h) if h.array => format!("[[{}]]", h.path.join(".")),
+            Some(h) => format!("[{}]", h.path.join(".")),
+            None => "top level".to_string(),
+        }
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn parse(text: &str) -> Vec<Section<'_>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut sections = vec![Section {
+        lead: Vec::new(),
+        header: None,
+        entries: Vec::new(),
+        tail: Vec::new(),
+    }];
+    let mut pending: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            pending.push(line);
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            // A comment block at the top of the file, set off by a blank
+            // line, belongs to the file rather than to the first table.
+            if sections.len() == 1 && sections[0].entries.is_empty() {
+                if let Some(blank) = pending.iter().rposition(|l| is_blank(l)) {
+                    sections[0].tail = pending.drain(..=blank).collect();
+                }
+            }
+            let array = trimmed.starts_with("[[");
+            let inner = trimmed.trim_start_matches('[');
+            let end = find_outside_quotes(inner, ']').unwrap_or(inner.len());
+            sections.push(Section {
+                lead: std::mem::take(&mut pending),
+                header: Some(Header {
+                    line,
+                    path: split_key(&inner[..end]),
+                    array,
+                }),
+                entries: Vec::new(),
+                tail: Vec::new(),
+            });
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut scan = Scan::default();
+        while i < lines.len() {
+            let done = scan.line(lines[i]);
+            i += 1;
+            if done {
+                break;
+            }
+        }
+        let eq = find_outside_quotes(trimmed, '=').unwrap_or(trimmed.len());
+        let section = sections.last_mut().expect("root section");
+        section.entries.push(Entry {
+            lead: std::mem::take(&mut pending),
+            key: split_key(&trimmed[..eq]).join("."),
+            body: lines[start..i].join("\n"),
+        });
+    }
+    sections
+        .last_mut()
+        .expect("root section")
+        .tail
+        .append(&mut pending);
+    sections
+}
+
+fn emit(sections: &[Section]) -> String {
+    let mut out = String::new();
+    let mut push = |line: &str| {
+        out.push_str(line);
+        out.push('\n');
+    };
+    for section in sections {
+        section.lead.iter().for_each(|l| push(l));
+        if let Some(header) = &section.header {
+            push(header.line);
+        }
+        for entry in &section.entries {
+            entry.lead.iter().for_each(|l| push(l));
+            push(&entry.body);
+        }
+        section.tail.iter().for_each(|l| push(l));
+    }
+    out
+}
+
+/// Byte index of `target` outside quoted strings.
+fn find_outside_quotes(s: &str, target: char) -> Option<usize> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) => {
+                if c == q && !escaped {
+                    quote = None;
+                }
+                escaped = q == '"' && c == '\\' && !escaped;
+            }
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == target => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// The parts of a dotted key, without their quotes.
+fn split_key(key: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut rest = key;
+    loop {
+        let end = find_outside_quotes(rest, '.').unwrap_or(rest.len());
+        let part = rest[..end].trim();
+        let unquoted = part
+            .strip_prefix('"')
+            .and_then(|p| p.strip_suffix('"'))
+            .or_else(|| part.strip_prefix('\'').and_then(|p| p.strip_suffix('\'')))
+            .unwrap_or(part);
+        parts.push(unquoted.to_string());
+        if end == rest.len() {
+            return parts;
+        }
+        rest = &rest[end + 1..];
+    }
+}
+
+/// Tracks brackets and multi-line strings to find where a value ends.
+#[derive(Default)]
+struct Scan {
+    depth: i32,
+    multiline: Option<&'static str>,
+    comment: bool,
+}
+
+impl Scan {
+    /// Feeds one line; returns whether the value is complete at its end.
+    fn line(&mut self, line: &str) -> bool {
+        let mut rest = line;
+        loop {
+            if let Some(close) = self.multiline {
+                match rest.find(close) {
+                    Some(i) => {
+                        rest = &rest[i + 3..];
+                        self.multiline = None;
+                    }
+                    None => return false,
+                }
+            }
+            let Some(i) = rest.find(['"', '\'', '[', ']', '{', '}', '#']) else {
+                break;
+            };
+            let c = rest.as_bytes()[i];
+            rest = &rest[i..];
+            match c {
+                b'#' => {
+                    self.comment = true;
+                    break;
+                }
+                b'"' | b'\'' => {
+                    let triple = if c == b'"' { "\"\"\"" } else { "'''" };
+                    if rest.starts_with(triple) {
+                        self.multiline = Some(triple);
+                        rest = &rest[3..];
+                        continue;
+                    }
+                    let q = c as char;
+                    let mut escaped = false;
+                    let end = rest[1..]
+                        .char_indices()
+                        .find(|&(_, ch)| {
+                            let close = ch == q && !escaped;
+                            escaped = q == '"' && ch == '\\' && !escaped;
+                            close
+                        })
+                        .map_or(rest.len(), |(j, _)| j + 2);
+                    rest = &rest[end..];
+                }
+                b'[' | b'{' => {
+                    self.depth += 1;
+                    rest = &rest[1..];
+                }
+                _ => {
+                    self.depth -= 1;
+                    rest = &rest[1..];
+                }
+            }
+        }
+        self.depth <= 0 && self.multiline.is_none()
+    }
+}
+
+/// Reorders tables and keys in place; returns one note per table or group
+/// that was out of order.
+fn reorder(
+    sections: &mut Vec<Section>,
+    opts: &TomlOptions,
+    preset: Option<&Preset>,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+
+    for section in sections.iter_mut() {
+        let path = section
+            .header
+            .as_ref()
+            .map(|h| h.path.clone())
+            .unwrap_or_default();
+        let dotted = path.join(".");
+        let fields = preset.and_then(|p| {
+            p.fields
+                .iter()
+                .find(|(name, _)| *name == dotted)
+                .map(|(_, fields)| *fields)
+        });
+        let sorted = opts.sort_keys
+            || preset.is_some_and(|p| {
+                path.last()
+                    .is_some_and(|l| p.sorted_keys.contains(&l.as_str()))
+            });
+        let rank = |key: &str| match fields {
+            Some(fields) => (
+                fields.iter().position(|f| *f == key).unwrap_or(usize::MAX),
+                String::new(),
+            ),
+            None if sorted => (0, key.to_string()),
+            None => (0, String::new()),
+        };
+        if fields.is_none() && !sorted {
+            continue;
+        }
+        let name = section.name();
+        let mut groups: Vec<Vec<Entry>> = Vec::new();
+        for entry in section.entries.drain(..) {
+            if groups.is_empty() || entry.lead.iter().any(|l| is_blank(l)) {
+                groups.push(Vec::new());
+            }
+            groups.last_mut().expect("group").push(entry);
+        }
+        for group in &mut groups {
+            let keys: Vec<_> = group.iter().map(|e| rank(&e.key)).collect();
+            if let Some(i) = (1..keys.len()).find(|&i| keys[i] < keys[i - 1]) {
+                notes.push(format!(
+                    "{name}: \"{}\" should come before \"{}\"",
+                    group[i].key,
+                    group[i - 1].key
+                ));
+                // Blank lines that open the group stay at its start.
+                let first = &mut group[0].lead;
+                let opening = first.iter().rposition(|l| is_blank(l)).map_or(0, |b| b + 1);
+                let opening: Vec<_> = first.drain(..opening).collect();
+                group.sort_by_cached_key(|e| rank(&e.key));
+                group[0].lead.splice(0..0, opening);
+            }
+        }
+        section.entries = groups.into_iter().flatten().collect();
+    }
+
+    // Tables move as units: an `[[array]]` table keeps the sub-tables that
+    // follow it, since they belong to its last element.
+    let mut units: Vec<Vec<Section>> = Vec::new();
+    let mut open_array: Option<Vec<String>> = None;
+    for section in sections.drain(1..) {
+        let header = section.header.as_ref().expect("table header");
+        let nested = open_array
+            .as_ref()
+            .is_some_and(|a| header.path.len() > a.len() && header.path.starts_with(a));
+        if nested {
+            units.last_mut().expect("unit").push(section);
+            continue;
+        }
+        open_array = header.array.then(|| header.path.clone());
+        units.push(vec![section]);
+    }
+    let key = |unit: &Vec<Section>| {
+        let path = &unit[0].header.as_ref().expect("table header").path;
+        let top = path[0].as_str();
+        let rank = preset.map_or(0, |p| {
+            p.tables
+                .iter()
+                .position(|t| *t == top)
+                .unwrap_or(usize::MAX)
+        });
+        let by_name = opts.sort_tables || preset.is_some_and(|p| p.sorted_tables.contains(&top));
+        (rank, by_name.then(|| path.clone()))
+    };
+    let keys: Vec<_> = units.iter().map(key).collect();
+    if let Some(i) = (1..keys.len()).find(|&i| keys[i] < keys[i - 1]) {
+        notes.push(format!(
+            "table {} should come before {}",
+            units[i][0].name(),
+            units[i - 1][0].name()
+        ));
+        units.sort_by_cached_key(key);
+        // Moved tables are set off by exactly one blank line.
+        for (i, unit) in units.iter_mut().enumerate() {
+            let lead = &mut unit[0].lead;
+            let blanks = lead.iter().take_while(|l| is_blank(l)).count();
+            lead.drain(..blanks);
+            let first = i == 0 && sections[0].entries.is_empty() && sections[0].tail.is_empty();
+            if !first {
+                lead.insert(0, "");
+            }
+        }
+        // The last table may have carried the file's closing blank lines.
+        for unit in &mut units {
+            let tail = &mut unit.last_mut().expect("unit").tail;
+            while tail.last().is_some_and(|l| is_blank(l)) {
+                tail.pop();
+            }
+        }
+    }
+    sections.extend(units.into_iter().flatten());
+    notes
+}
+
+fn wrap_array(body: &str, wrap: WrapArrays) -> String {
+    let Some(eq) = find_outside_quotes(body, '=') else {
+        return body.to_string();
+    };
+    let value = body[eq + 1..].trim_start();
+    if !value.starts_with('[') {
+        return body.to_string();
+    }
+    let open = body.len() - value.len();
+    let inner = value[1..].trim_start_matches([' ', '\t']);
+    match wrap {
+        WrapArrays::Always if !inner.starts_with(['\n', ']', '#']) => {
+            format!("{}\n{}", &body[..=open], &body[open + 1..])
+        }
+        WrapArrays::Never if body.contains('\n') => {
+            let mut scan = Scan::default();
+            body.lines().for_each(|l| {
+                scan.line(l);
+            });
+            if scan.comment {
+                return body.to_string();
+            }
+            body.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+        }
+        _ => body.to_string(),
+    }
+}
+
+/// Pads the keys of consecutive single-line entries to the same width.
+fn align_entries(text: &str) -> String {
+    let mut sections = parse(text);
+    for section in &mut sections {
+        let mut start = 0;
+        while start < section.entries.len() {
+            let single = |e: &Entry| !e.body.contains('\n');
+            if !single(&section.entries[start]) {
+                start += 1;
+                continue;
+            }
+            let mut end = start + 1;
+            while end < section.entries.len()
+                && section.entries[end].lead.is_empty()
+                && single(&section.entries[end])
+            {
+                end += 1;
+            }
+            let run = &mut section.entries[start..end];
+            let split: Vec<_> = run
+                .iter()
+                .map(|e| {
+                    let eq = find_outside_quotes(&e.body, '=').unwrap_or(e.body.len());
+                    (
+                        e.body[..eq].trim_end().to_string(),
+                        e.body[eq..]
+                            .trim_start_matches('=')
+                            .trim_start()
+                            .to_string(),
+                    )
+                })
+                .collect();
+            let width = split
+                .iter()
+                .map(|(k, _)| k.chars().count())
+                .max()
+                .unwrap_or(0);
+            for (entry, (key, value)) in run.iter_mut().zip(split) {
+                let pad = width - key.chars().count();
+                entry.body = format!("{key}{} = {value}", " ".repeat(pad));
+            }
+            start = end;
+        }
+    }
+    emit(&sections)
 }
@@ -1,23 +1,655 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use anyhow::Result;
 use dprint_plugin_typescript as ts;
+use jsonc_parser::{JsonObject, JsonValue, ParseOptions, parse_to_value};
 use once_cell::sync::Lazy;
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, ensure_newline, options};
 
-static CONF: Lazy<ts::configuration::Configuration> =
-    Lazy::new(|| ts::configuration::ConfigurationBuilder::new().build());
+/// Options left unset here are read from the nearest `.prettierrc` (or the
+/// `prettier` key of `package.json`, either with Prettier's defaults for
+/// missing keys) or `dprint.json` above the file, then fall back to dprint's
+/// defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TypeScriptOptions {
+    pub line_width: Option<u32>,
+    /// Spaces per indentation level.
+    pub indent: Option<u8>,
+    pub use_tabs: Option<bool>,
+    pub quotes: Option<Quotes>,
+    pub semicolons: Option<Semicolons>,
+    pub trailing_commas: Option<TrailingCommas>,
+    pub arrow_parens: Option<ArrowParens>,
+    /// Group the imports at the top of the file into builtin, external,
+    /// internal (`@/`, `~/`, `#`, `$`) and relative modules, sort them and
+    /// merge named imports of the same module.
+    pub organize_imports: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quotes {
+    Double,
+    Single,
+    /// Double unless the string contains more double quotes than single.
+    PreferDouble,
+    PreferSingle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Semicolons {
+    Always,
+    /// Semicolons except after the last member of a one-line type literal.
+    Prefer,
+    /// Only where automatic semicolon insertion would go wrong.
+    Asi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrailingCommas {
+    Never,
+    Always,
+    OnlyMultiLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArrowParens {
+    Always,
+    Avoid,
+    Maintain,
+}
+
+impl TypeScriptOptions {
+    /// `self`, with unset options taken from `fallback`.
+    fn or(&self, fallback: &TypeScriptOptions) -> TypeScriptOptions {
+        TypeScriptOptions {
+            line_width: self.line_width.or(fallback.line_width),
+            indent: self.indent.or(fallback.indent),
+            use_tabs: self.use_tabs.or(fallback.use_tabs),
+            quotes: self.quotes.or(fallback.quotes),
+            semicolons: self.semicolons.or(fallback.semicolons),
+            trailing_commas: self.trailing_commas.or(fallback.trailing_commas),
+            arrow_parens: self.arrow_parens.or(fallback.arrow_parens),
+            organize_imports: self.organize_imports,
+        }
+    }
+
+    fn configuration(&self) -> ts::configuration::Configuration {
+        use ts::configuration::{QuoteStyle, SemiColons, TrailingCommas as Commas, UseParentheses};
+
+        let mut builder = ts::configuration::ConfigurationBuilder::new();
+        if let Some(width) = self.line_width {
+            builder.line_width(width);
+        }
+        if let Some(indent) = self.indent {
+            builder.indent_width(indent);
+        }
+        if let Some(tabs) = self.use_tabs {
+            builder.use_tabs(tabs);
+        }
+        if let Some(quotes) = self.quotes {
+            builder.quote_style(match quotes {
+                Quotes::Double => QuoteStyle::AlwaysDouble,
+                Quotes::Single => QuoteStyle::AlwaysSingle,
+                Quotes::PreferDouble => QuoteStyle::PreferDouble,
+                Quotes::PreferSingle => QuoteStyle::PreferSingle,
+            });
+        }
+        if let Some(semicolons) = self.semicolons {
+            builder.semi_colons(match semicolons {
+                Semicolons::Always => SemiColons::Always,
+                Semicolons::Prefer => SemiColons::Prefer,
+                Semicolons::Asi => SemiColons::Asi,
+            });
+        }
+        if let Some(commas) = self.trailing_commas {
+            builder.trailing_commas(match commas {
+                TrailingCommas::Never => Commas::Never,
+                TrailingCommas::Always => Commas::Always,
+                TrailingCommas::OnlyMultiLine => Commas::OnlyMultiLine,
+            });
+        }
+        if let Some(parens) = self.arrow_parens {
+            builder.arrow_function_use_parentheses(match parens {
+                ArrowParens::Always => UseParentheses::Force,
+                ArrowParens::Avoid => UseParentheses::PreferNone,
+                ArrowParens::Maintain => UseParentheses::Maintain,
+            });
+        }
+        builder.build()
+    }
+}
 
 pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
-    let res = ts::format_text(ts::FormatTextOptions {
-        path,
-        extension: ext.as_deref(),
-        text: text.into(),
-        config: &CONF,
-        external_formatter: None,
+    let opts = options::for_path(path).typescript.or(&compat_options(path));
+    format_with_options(path, text, &opts)
+}
+
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &TypeScriptOptions,
+) -> Result<Option<String>, FormatError> {
+    let config = opts.configuration();
+    let run = |text: &str| {
+        let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+        ts::format_text(ts::FormatTextOptions {
+            path,
+            extension: ext.as_deref(),
+            text: text.into(),
+            config: &config,
+            external_formatter: None,
+        })
+        .map_err(|e| FormatError::Message(e.to_string()))
+    };
+    let mut res = run(text)?;
+    if opts.organize_imports {
+        let formatted = res.as_deref().unwrap_or(text);
+        let organized = organize_imports(formatted);
+        if organized != formatted {
+            // Merged imports are written on one line; dprint wraps them.
+            res = Some(run(&organized)?.unwrap_or(organized));
+        }
+    }
+    Ok(res.map(ensure_newline).filter(|out| out != text))
+}
+
+/// Options from the nearest Prettier or dprint configuration, cached per
+/// directory.
+fn compat_options(path: &Path) -> TypeScriptOptions {
+    static CACHE: Lazy<Mutex<HashMap<PathBuf, TypeScriptOptions>>> = Lazy::new(Default::default);
+
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let Ok(dir) = fs::canonicalize(dir) else {
+        return TypeScriptOptions::default();
+    };
+    if let Some(found) = CACHE.lock().expect("cache lock").get(&dir) {
+        return found.clone();
+    }
+    let found = dir.ancestors().find_map(read_compat).unwrap_or_default();
+    CACHE.lock().expect("cache lock").insert(dir, found.clone());
+    found
+}
+
+fn read_compat(dir: &Path) -> Option<TypeScriptOptions> {
+    for name in [
+        ".prettierrc",
+        ".prettierrc.json",
+        ".prettierrc.json5",
+        ".prettierrc.yaml",
+        ".prettierrc.yml",
+    ] {
+        if let Ok(text) = fs::read_to_string(dir.join(name)) {
+            let settings = json_object(&text)
+                .map(scalars)
+                .unwrap_or_else(|| yaml_scalars(&text));
+            return Some(from_prettier(&settings));
+        }
+    }
+    for name in [
+        "dprint.json",
+        ".dprint.json",
+        "dprint.jsonc",
+        ".dprint.jsonc",
+    ] {
+        if let Ok(text) = fs::read_to_string(dir.join(name)) {
+            let mut object = json_object(&text)?;
+            let typescript = object.take_object("typescript");
+            let mut settings = scalars(object);
+            settings.extend(typescript.map(scalars).unwrap_or_default());
+            return Some(from_dprint(&settings));
+        }
+    }
+    let package = fs::read_to_string(dir.join("package.json")).ok()?;
+    let prettier = json_object(&package)?.take_object("prettier")?;
+    Some(from_prettier(&scalars(prettier)))
+}
+
+fn json_object(text: &str) -> Option<JsonObject<'_>> {
+    match parse_to_value(text, &ParseOptions::default()) {
+        Ok(Some(JsonValue::Object(object))) => Some(object),
+        _ => None,
+    }
+}
+
+/// Strings, numbers and booleans of an object, as text.
+fn scalars(object: JsonObject) -> HashMap<String, String> {
+    object
+        .take_inner()
+        .into_iter()
+        .filter_map(|(key, value)| match value {
+            JsonValue::String(s) => Some((key, s.into_owned())),
+            JsonValue::Number(n) => Some((key, n.to_string())),
+            JsonValue::Boolean(b) => Some((key, b.to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Top-level `key: value` lines of a YAML `.prettierrc`.
+fn yaml_scalars(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-']))
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| {
+            let value = value.split(" #").next().unwrap_or_default().trim();
+            (
+                key.trim().to_string(),
+                value.trim_matches(['"', '\'']).to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Prettier settings, with Prettier's own defaults for keys left out.
+fn from_prettier(settings: &HashMap<String, String>) -> TypeScriptOptions {
+    let get = |key: &str, default: &'static str| settings.get(key).map_or(default, String::as_str);
+    TypeScriptOptions {
+        line_width: get("printWidth", "80").parse().ok(),
+        indent: get("tabWidth", "2").parse().ok(),
+        use_tabs: get("useTabs", "false").parse().ok(),
+        quotes: Some(match get("singleQuote", "false") {
+            "true" => Quotes::PreferSingle,
+            _ => Quotes::PreferDouble,
+        }),
+        semicolons: Some(match get("semi", "true") {
+            "false" => Semicolons::Asi,
+            _ => Semicolons::Prefer,
+        }),
+        trailing_commas: Some(match get("trailingComma", "all") {
+            "none" => TrailingCommas::Never,
+            _ => TrailingCommas::OnlyMultiLine,
+        }),
+        arrow_parens: Some(match get("arrowParens", "always") {
+            "avoid" => ArrowParens::Avoid,
+            _ => ArrowParens::Always,
+        }),
+        organize_imports: false,
+    }
+}
+
+fn from_dprint(settings: &HashMap<String, String>) -> TypeScriptOptions {
+    let get = |key: &str| settings.get(key).map(String::as_str);
+    TypeScriptOptions {
+        line_width: get("lineWidth").and_then(|v| v.parse().ok()),
+        indent: get("indentWidth").and_then(|v| v.parse().ok()),
+        use_tabs: get("useTabs").and_then(|v| v.parse().ok()),
+        quotes: get("quoteStyle").and_then(|v| match v {
+            "alwaysDouble" => Some(Quotes::Double),
+            "alwaysSingle" => Some(Quotes::Single),
+            "preferDouble" => Some(Quotes::PreferDouble),
+            "preferSingle" => Some(Quotes::PreferSingle),
+            _ => None,
+        }),
+        semicolons: get("semiColons").and_then(|v| match v {
+            "always" => Some(Semicolons::Always),
+            "prefer" => Some(Semicolons::Prefer),
+            "asi" => Some(Semicolons::Asi),
+            _ => None,
+        }),
+        trailing_commas: get("trailingCommas").and_then(|v| match v {
+            "never" => Some(TrailingCommas::Never),
+            "always" => Some(TrailingCommas::Always),
+            "onlyMultiLine" => Some(TrailingCommas::OnlyMultiLine),
+            _ => None,
+        }),
+        arrow_parens: get("arrowFunction.useParentheses").and_then(|v| match v {
+            "force" => Some(ArrowParens::Always),
+            "preferNone" => Some(ArrowParens::Avoid),
+            "maintain" => Some(ArrowParens::Maintain),
+            _ => None,
+        }),
+        organize_imports: false,
+    }
+}
+
+const NODE_BUILTINS: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Group {
+    Builtin,
+    External,
+    Internal,
+    Relative,
+}
+
+fn group(module: &str) -> Group {
+    let first = module.split('/').next().unwrap_or_default();
+    if module.starts_with("node:") || module.starts_with("bun:") || NODE_BUILTINS.contains(&first) {
+        Group::Builtin
+    } else if module.starts_with('.') {
+        Group::Relative
+    } else if module.starts_with("@/") || module.starts_with("~/") || module.starts_with(['#', '$'])
+    {
+        Group::Internal
+    } else {
+        Group::External
+    }
+}
+
+/// An import declaration of the leading import block.
+struct Import {
+    /// Comment lines directly above it.
+    comments: Vec<String>,
+    /// The declaration as written.
+    text: String,
+    module: String,
+    /// Set for `import "./polyfill"`, whose position matters.
+    side_effect: bool,
+    /// Parts of `import type? default?, { named }? from "module" attributes?`
+    /// when the declaration can be merged with another.
+    parts: Option<Parts>,
+}
+
+#[derive(Clone)]
+struct Parts {
+    type_only: bool,
+    default: Option<String>,
+    named: Vec<String>,
+    /// `"module" with { … }` as written.
+    source: String,
+    semicolon: bool,
+}
+
+/// Groups, sorts and merges the imports at the top of formatted code.
+/// Side-effect imports stay where they are and split the block in two.
+fn organize_imports(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+    // Leading comments and directives such as `"use client";` stay on top.
+    while i < lines.len() {
+        let line = lines[i].trim();
+        let directive = line.starts_with(['"', '\'']) && !line.contains(" from ");
+        if line.is_empty()
+            || line.starts_with("//")
+            || line.starts_with("/*")
+            || line.starts_with('*')
+            || directive
+        {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    // Back up over comments directly above the first import.
+    let mut start = i;
+    while start > 0 && lines[start - 1].trim_start().starts_with("//") {
+        start -= 1;
+    }
+    if start > 0 && !lines[start - 1].trim().is_empty() {
+        start = i;
+    }
+
+    let mut imports: Vec<Import> = Vec::new();
+    let mut comments = Vec::new();
+    let mut j = start;
+    let mut end = start;
+    while j < lines.len() {
+        let line = lines[j].trim();
+        if line.is_empty() {
+            if !comments.is_empty() {
+                break;
+            }
+            j += 1;
+            continue;
+        }
+        if line.starts_with("//") {
+            comments.push(lines[j].to_string());
+            j += 1;
+            continue;
+        }
+        if !is_import(line) {
+            break;
+        }
+        let Some(last) = (j..lines.len()).find(|&k| ends_import(lines[k])) else {
+            break;
+        };
+        let text = lines[j..=last].join("\n");
+        let Some(import) = parse_import(&text, std::mem::take(&mut comments)) else {
+            break;
+        };
+        imports.push(import);
+        j = last + 1;
+        end = j;
+    }
+    if imports.len() < 2 {
+        return text.to_string();
+    }
+
+    let mut out: Vec<String> = lines[..start].iter().map(|l| l.to_string()).collect();
+    let mut segment = Vec::new();
+    for import in imports {
+        if import.side_effect {
+            emit(&mut out, organize(std::mem::take(&mut segment)));
+            emit(&mut out, vec![vec![import]]);
+        } else {
+            segment.push(import);
+        }
+    }
+    emit(&mut out, organize(segment));
+    if end < lines.len() {
+        out.push(String::new());
+        out.extend(
+            lines[end..]
+                .iter()
+                .skip_while(|l| l.trim().is_empty())
+                .map(|l| l.to_string()),
+        );
+    }
+    let mut out = out.join("\n");
+    out.push('\n');
+    out
+}
+
+fn is_import(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix("import") else {
+        return false;
+    };
+    rest.starts_with([' ', '{', '"', '\''])
+        && !rest.trim_start().starts_with('(')
+        && !line.contains(" = require(")
+}
+
+/// Whether the declaration ends on this line: after the module specifier or
+/// an import attributes object.
+fn ends_import(line: &str) -> bool {
+    let line = line.split(" //").next().unwrap_or_default().trim_end();
+    let line = line.strip_suffix(';').unwrap_or(line);
+    line.ends_with(['"', '\''])
+        || (line.ends_with('}') && (line.contains(" with {") || line.contains(" assert {")))
+}
+
+fn parse_import(text: &str, comments: Vec<String>) -> Option<Import> {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let rest = flat.strip_prefix("import")?.trim_start();
+    if let Some(quote) = rest.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let module = rest[1..].split(quote).next()?.to_string();
+        return Some(Import {
+            comments,
+            text: text.to_string(),
+            module,
+            side_effect: true,
+            parts: None,
+        });
+    }
+    let from = rest.rfind(" from ")?;
+    let (clause, source) = (&rest[..from], rest[from + " from ".len()..].trim());
+    let quote = source.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let module = source[1..].split(quote).next()?.to_string();
+    let semicolon = source.ends_with(';');
+    let source = source.strip_suffix(';').unwrap_or(source).to_string();
+
+    let mergeable = !text.contains("//") && !text.contains("/*") && !clause.contains('*');
+    let parts = mergeable.then(|| {
+        let (type_only, clause) = match clause.strip_prefix("type ") {
+            Some(rest) => (true, rest.trim()),
+            None => (false, clause),
+        };
+        let (default, named) = match clause.find('{') {
+            Some(open) => {
+                let default = clause[..open].trim().trim_end_matches(',').trim();
+                let named = clause[open + 1..].trim_end().strip_suffix('}')?;
+                (default, named)
+            }
+            None => (clause.trim(), ""),
+        };
+        Some(Parts {
+            type_only,
+            default: (!default.is_empty()).then(|| default.to_string()),
+            named: named
+                .split(',')
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .map(str::to_string)
+                .collect(),
+            source,
+            semicolon,
+        })
+    });
+    Some(Import {
+        comments,
+        text: text.to_string(),
+        module,
+        side_effect: false,
+        parts: parts.flatten(),
     })
-    .map_err(|e| FormatError::Message(e.to_string()))?;
-    Ok(res.map(ensure_newline))
+}
+
+/// Merges and sorts a run of imports into groups.
+fn organize(imports: Vec<Import>) -> Vec<Vec<Import>> {
+    let mut merged: Vec<Import> = Vec::new();
+    for import in imports {
+        let target = merged.iter_mut().find(|m| match (&m.parts, &import.parts) {
+            (Some(a), Some(b)) => {
+                m.module == import.module
+                    && a.type_only == b.type_only
+                    && a.source == b.source
+                    && (a.default.is_none() || b.default.is_none() || a.default == b.default)
+            }
+            _ => false,
+        });
+        let Some(target) = target else {
+            merged.push(import);
+            continue;
+        };
+        let (Some(into), Some(from)) = (target.parts.as_mut(), import.parts) else {
+            continue;
+        };
+        into.default = into.default.take().or(from.default);
+        for name in from.named {
+            if !into.named.contains(&name) {
+                into.named.push(name);
+            }
+        }
+        target.comments.extend(import.comments);
+        target.text = render(into);
+    }
+
+    merged.sort_by(|a, b| {
+        let type_only = |i: &Import| i.parts.as_ref().is_some_and(|p| p.type_only);
+        (
+            group(&a.module),
+            a.module.to_lowercase(),
+            &a.module,
+            type_only(a),
+        )
+            .cmp(&(
+                group(&b.module),
+                b.module.to_lowercase(),
+                &b.module,
+                type_only(b),
+            ))
+    });
+    let mut groups: Vec<Vec<Import>> = Vec::new();
+    for import in merged {
+        match groups.last_mut() {
+            Some(last) if group(&last[0].module) == group(&import.module) => last.push(import),
+            _ => groups.push(vec![import]),
+        }
+    }
+    groups
+}
+
+fn render(parts: &Parts) -> String {
+    let mut clause = Vec::new();
+    if let Some(default) = &parts.default {
+        clause.push(default.clone());
+    }
+    if !parts.named.is_empty() {
+        clause.push(format!("{{ {} }}", parts.named.join(", ")));
+    }
+    format!(
+        "import {}{} from {}{}",
+        if parts.type_only { "type " } else { "" },
+        clause.join(", "),
+        parts.source,
+        if parts.semicolon { ";" } else { "" },
+    )
+}
+
+/// Appends groups separated by blank lines.
+fn emit(out: &mut Vec<String>, groups: Vec<Vec<Import>>) {
+    for group in groups {
+        if out.last().is_some_and(|l| !l.trim().is_empty()) {
+            out.push(String::new());
+        }
+        for import in group {
+            out.extend(import.comments);
+            out.push(import.text);
+        }
+    }
 }
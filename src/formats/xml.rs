@@ -1,31 +1,562 @@
 use std::path::Path;
 
 use anyhow::Result;
-use quick_xml::Writer;
-use quick_xml::events::Event;
+use quick_xml::events::{BytesStart, Event};
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, ensure_newline, options};
 
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct XmlOptions {
+    /// Spaces per nesting level (ignored with `use-tabs`).
+    pub indent: usize,
+    /// Indent with one tab per level.
+    pub use_tabs: bool,
+    /// Start tags wider than this get one attribute per line.
+    pub line_width: usize,
+    /// `pom.xml`: put elements in Maven's recommended order (as sortpom does).
+    pub pom_order: bool,
+    /// Android resources: order attributes the way Android Studio arranges them.
+    pub android_attribute_order: bool,
+    /// SVG: rewrite `<path d="...">` data as `M10 20 L30 40 Z`.
+    pub normalize_svg_paths: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            use_tabs: false,
+            line_width: 120,
+            pom_order: false,
+            android_attribute_order: false,
+            normalize_svg_paths: false,
+        }
+    }
+}
+
+/// Conventions of particular XML vocabularies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    Generic,
+    /// Maven `pom.xml`: canonical element order.
+    Pom,
+    /// Android manifests and resources (root declares `xmlns:android`).
+    Android,
+    Svg,
+    /// Apple property lists: tab indented, `<dict>` flush with `<plist>`.
+    Plist,
+}
+
+fn dialect_for(path: &Path, nodes: &[Node]) -> Dialect {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let android_root = nodes.iter().any(|n| match n {
+        Node::Element(el) => el.attrs.iter().any(|(k, _)| k == "xmlns:android"),
+        _ => false,
+    });
+    match ext.as_str() {
+        _ if name == "pom.xml" => Dialect::Pom,
+        "svg" => Dialect::Svg,
+        "plist" => Dialect::Plist,
+        _ if android_root => Dialect::Android,
+        _ => Dialect::Generic,
+    }
+}
+
+/// Child order per parent element, following sortpom's recommended order.
+const POM_ORDER: &[(&str, &[&str])] = &[
+    (
+        "project",
+        &[
+            "modelVersion",
+            "parent",
+            "groupId",
+            "artifactId",
+            "version",
+            "packaging",
+            "name",
+            "description",
+            "url",
+            "inceptionYear",
+            "organization",
+            "licenses",
+            "developers",
+            "contributors",
+            "mailingLists",
+            "prerequisites",
+            "modules",
+            "scm",
+            "issueManagement",
+            "ciManagement",
+            "distributionManagement",
+            "properties",
+            "dependencyManagement",
+            "dependencies",
+            "repositories",
+            "pluginRepositories",
+            "build",
+            "reporting",
+            "profiles",
+        ],
+    ),
+    (
+        "parent",
+        &["groupId", "artifactId", "version", "relativePath"],
+    ),
+    (
+        "dependency",
+        &[
+            "groupId",
+            "artifactId",
+            "version",
+            "type",
+            "classifier",
+            "scope",
+            "systemPath",
+            "optional",
+            "exclusions",
+        ],
+    ),
+    ("exclusion", &["groupId", "artifactId"]),
+    (
+        "plugin",
+        &[
+            "groupId",
+            "artifactId",
+            "version",
+            "extensions",
+            "executions",
+            "dependencies",
+            "goals",
+            "inherited",
+            "configuration",
+        ],
+    ),
+    (
+        "execution",
+        &["id", "phase", "goals", "inherited", "configuration"],
+    ),
+];
+
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).xml)
+}
+
+/// XML formatter:
+/// - element-only content is indented one element per line
+/// - text-only and mixed content (`<p>text <b>bold</b> more</p>`) and
+///   `xml:space="preserve"` subtrees are copied exactly as written
+/// - the declaration, DOCTYPE, processing instructions, comments and CDATA are kept verbatim
+/// - start tags wider than `line_width` get one attribute per line
+/// - `pom.xml`, Android resources, SVG and plist files get their dialect's rules
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &XmlOptions,
+) -> Result<Option<String>, FormatError> {
+    // Offsets into the source must not count a byte order mark.
+    let (bom, source) = match text.strip_prefix('\u{feff}') {
+        Some(rest) => ("\u{feff}", rest),
+        None => ("", text),
+    };
+    let mut nodes = parse(source)?;
+    let dialect = dialect_for(path, &nodes);
+    match dialect {
+        Dialect::Pom if opts.pom_order => walk(&mut nodes, &mut |el| {
+            if let Some((_, order)) = POM_ORDER.iter().find(|(name, _)| *name == el.name) {
+                reorder_children(el, order);
+            }
+        }),
+        Dialect::Android if opts.android_attribute_order => walk(&mut nodes, &mut |el| {
+            el.attrs
+                .sort_by(|(a, _), (b, _)| (android_rank(a), a).cmp(&(android_rank(b), b)));
+        }),
+        Dialect::Svg if opts.normalize_svg_paths => walk(&mut nodes, &mut |el| {
+            if el.name != "path" {
+                return;
+            }
+            for (key, value) in &mut el.attrs {
+                if key == "d" {
+                    if let Some(d) = normalize_path_data(value) {
+                        *value = d;
+                    }
+                }
+            }
+        }),
+        _ => {}
+    }
+    let mut printer = Printer {
+        text: source,
+        opts,
+        dialect,
+        lines: Vec::new(),
+    };
+    printer.block(&nodes, 0);
+    let out = ensure_newline(format!("{bom}{}", printer.lines.join("\n")));
+    if out == text { Ok(None) } else { Ok(Some(out)) }
+}
+
+#[derive(Debug)]
+enum Node {
+    Element(Element),
+    /// Character data as written (entities left escaped).
+    Text(String),
+    CData(String),
+    /// Comments, the declaration, DOCTYPE and processing instructions.
+    Markup(String),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    /// Attribute name and raw (still escaped) value.
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+    /// Written as `<a/>` (`Some(false)`) or `<a />` (`Some(true)`).
+    self_closing: Option<bool>,
+    /// Source range between the start and end tags.
+    content: (usize, usize),
+    /// Inside `xml:space="preserve"`.
+    preserve: bool,
+}
+
+impl Element {
+    fn from_start(e: &BytesStart, preserve: bool) -> Result<Self, FormatError> {
+        let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+        let mut attrs = Vec::new();
+        let mut preserve = preserve;
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| FormatError::Message(format!("XML parse error: {e}")))?;
+            let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+            let value = String::from_utf8_lossy(&attr.value).into_owned();
+            if key == "xml:space" {
+                preserve = value == "preserve";
+            }
+            attrs.push((key, value));
+        }
+        Ok(Self {
+            name,
+            attrs,
+            children: Vec::new(),
+            self_closing: None,
+            content: (0, 0),
+            preserve,
+        })
+    }
+
+    /// Whether any child is non-whitespace text, making whitespace significant.
+    fn has_text(&self) -> bool {
+        self.children.iter().any(|c| match c {
+            Node::Text(t) => !t.trim().is_empty(),
+            Node::CData(_) => true,
+            _ => false,
+        })
+    }
+
+    fn has_elements(&self) -> bool {
+        self.children.iter().any(|c| matches!(c, Node::Element(_)))
+    }
+}
+
+fn parse(text: &str) -> Result<Vec<Node>, FormatError> {
     let mut reader = quick_xml::Reader::from_str(text);
-    reader.trim_text(true);
-    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
-    let mut buf = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut top: Vec<Node> = Vec::new();
+    let mut pos = 0;
+
     loop {
-        match reader.read_event_into(&mut buf) {
-            Ok(Event::Eof) => break,
-            Ok(e) => writer
-                .write_event(e)
-                .map_err(|e| FormatError::Message(e.to_string()))?,
-            Err(e) => return Err(FormatError::Message(format!("XML parse error: {e}"))),
-        }
-        buf.clear();
-    }
-    let out =
-        String::from_utf8(writer.into_inner()).map_err(|e| FormatError::Message(e.to_string()))?;
-    if out == text {
-        Ok(None)
-    } else {
-        Ok(Some(ensure_newline(out)))
+        let event = reader
+            .read_event()
+            .map_err(|e| FormatError::Message(format!("XML parse error: {e}")))?;
+        let end = reader.buffer_position();
+        let source = &text[pos..end];
+        let preserve = stack.last().is_some_and(|e| e.preserve);
+        let node = match event {
+            Event::Eof => break,
+            Event::Start(e) => {
+                let mut el = Element::from_start(&e, preserve)?;
+                el.content.0 = end;
+                stack.push(el);
+                None
+            }
+            Event::End(_) => {
+                let mut el = stack.pop().expect("quick-xml checks end names");
+                el.content.1 = pos;
+                Some(Node::Element(el))
+            }
+            Event::Empty(e) => {
+                let mut el = Element::from_start(&e, preserve)?;
+                el.self_closing = Some(source.ends_with(" />"));
+                Some(Node::Element(el))
+            }
+            Event::Text(_) => Some(Node::Text(source.to_string())),
+            Event::CData(_) => Some(Node::CData(source.to_string())),
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {
+                Some(Node::Markup(source.to_string()))
+            }
+        };
+        pos = end;
+        if let Some(node) = node {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => top.push(node),
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(FormatError::Message(format!(
+            "XML parse error: unclosed element <{}>",
+            open.name
+        )));
+    }
+    Ok(top)
+}
+
+/// Visit every element, parents before children.
+fn walk(nodes: &mut [Node], f: &mut impl FnMut(&mut Element)) {
+    for node in nodes {
+        if let Node::Element(el) = node {
+            f(el);
+            walk(&mut el.children, f);
+        }
+    }
+}
+
+/// Stable sort of element-only content by `order`; unknown elements go
+/// last. Comments and whitespace travel with the element that follows them.
+fn reorder_children(el: &mut Element, order: &[&str]) {
+    if el.preserve || el.has_text() {
+        return;
+    }
+    let mut units: Vec<(usize, Vec<Node>)> = Vec::new();
+    let mut pending = Vec::new();
+    for node in std::mem::take(&mut el.children) {
+        let rank = match &node {
+            Node::Element(child) => order
+                .iter()
+                .position(|o| *o == child.name)
+                .unwrap_or(order.len()),
+            _ => {
+                pending.push(node);
+                continue;
+            }
+        };
+        pending.push(node);
+        units.push((rank, std::mem::take(&mut pending)));
+    }
+    units.sort_by_key(|(rank, _)| *rank);
+    el.children = units.into_iter().flat_map(|(_, nodes)| nodes).collect();
+    el.children.extend(pending);
+}
+
+/// Android Studio's default attribute arrangement; ties are sorted by name.
+fn android_rank(key: &str) -> u8 {
+    let local = key.split_once(':').map(|(_, l)| l);
+    match (key, local) {
+        ("xmlns:android", _) => 0,
+        (k, _) if k.starts_with("xmlns:") => 1,
+        (_, Some("id")) => 2,
+        (_, Some("name")) => 3,
+        ("name", None) => 4,
+        ("style", None) => 5,
+        (_, None) => 6,
+        (_, Some("layout_width")) => 7,
+        (_, Some("layout_height")) => 8,
+        (_, Some(l)) if l.starts_with("layout_") => 9,
+        (_, Some("width")) => 10,
+        (_, Some("height")) => 11,
+        (k, _) if k.starts_with("android:") => 12,
+        _ => 13,
+    }
+}
+
+/// `M 10,20 L30 , 40z` -> `M10 20 L30 40 z`; numbers keep their spelling.
+/// Returns `None` for data that does not parse, which is then left alone.
+fn normalize_path_data(d: &str) -> Option<String> {
+    let b = d.as_bytes();
+    let mut out = String::new();
+    let mut command = None;
+    let mut arg = 0;
+    let mut i = 0;
+    while i < b.len() {
+        let c = b[i];
+        if c.is_ascii_whitespace() || c == b',' {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_alphabetic() {
+            if !b"MmLlHhVvCcSsQqTtAaZz".contains(&c) {
+                return None;
+            }
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push(c as char);
+            command = Some(c.to_ascii_lowercase());
+            arg = 0;
+            i += 1;
+            continue;
+        }
+        let start = i;
+        match command {
+            None => return None,
+            // Arc flags are single digits and may be written without separators.
+            Some(b'a') if matches!(arg % 7, 3 | 4) => {
+                if !matches!(c, b'0' | b'1') {
+                    return None;
+                }
+                i += 1;
+            }
+            Some(_) => i = scan_number(b, i)?,
+        }
+        if arg > 0 {
+            out.push(' ');
+        }
+        out.push_str(&d[start..i]);
+        arg += 1;
+    }
+    Some(out)
+}
+
+/// End of the SVG number starting at `i` (`-1.5e-3`, `.5`).
+fn scan_number(b: &[u8], mut i: usize) -> Option<usize> {
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < b.len() && b[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    };
+    if matches!(b.get(i), Some(b'+' | b'-')) {
+        i += 1;
+    }
+    let mut mantissa = digits(&mut i);
+    if b.get(i) == Some(&b'.') {
+        i += 1;
+        mantissa += digits(&mut i);
+    }
+    if mantissa == 0 {
+        return None;
+    }
+    if matches!(b.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(b.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if digits(&mut j) > 0 {
+            i = j;
+        }
+    }
+    Some(i)
+}
+
+struct Printer<'a> {
+    text: &'a str,
+    opts: &'a XmlOptions,
+    dialect: Dialect,
+    lines: Vec<String>,
+}
+
+impl Printer<'_> {
+    fn pad(&self, level: usize) -> String {
+        if self.opts.use_tabs || self.dialect == Dialect::Plist {
+            "\t".repeat(level)
+        } else {
+            " ".repeat(level * self.opts.indent)
+        }
+    }
+
+    /// Element-only content: one node per line, keeping single blank lines.
+    fn block(&mut self, nodes: &[Node], level: usize) {
+        let start = self.lines.len();
+        let pad = self.pad(level);
+        for node in nodes {
+            match node {
+                Node::Text(t) if !t.trim().is_empty() => {
+                    self.lines.push(format!("{pad}{}", t.trim()));
+                }
+                Node::Text(t) => {
+                    if t.matches('\n').count() >= 2 && self.lines.len() > start {
+                        self.lines.push(String::new());
+                    }
+                }
+                Node::CData(s) | Node::Markup(s) => self.lines.push(format!("{pad}{}", s.trim())),
+                Node::Element(el) => self.element(el, level),
+            }
+        }
+        // A blank line before the closing tag is dropped.
+        while self.lines.len() > start && self.lines.last().is_some_and(String::is_empty) {
+            self.lines.pop();
+        }
+    }
+
+    fn element(&mut self, el: &Element, level: usize) {
+        let mut open = self.start_tag(el, level);
+        if let Some(spaced) = el.self_closing {
+            let last = open.last_mut().expect("start tag");
+            last.pop();
+            last.push_str(if spaced { " />" } else { "/>" });
+            self.lines.extend(open);
+            return;
+        }
+
+        let close = format!("</{}>", el.name);
+        if el.preserve || el.has_text() || !el.has_elements() {
+            let content = &self.text[el.content.0..el.content.1];
+            let content = if !el.preserve && content.trim().is_empty() {
+                ""
+            } else {
+                content
+            };
+            let last = open.pop().expect("start tag");
+            self.lines.extend(open);
+            self.lines.push(format!("{last}{content}{close}"));
+            return;
+        }
+
+        // Apple writes the top-level `<dict>` flush with `<plist>`.
+        let inner = if self.dialect == Dialect::Plist && el.name == "plist" {
+            level
+        } else {
+            level + 1
+        };
+        self.lines.extend(open);
+        self.block(&el.children, inner);
+        self.lines.push(format!("{}{close}", self.pad(level)));
+    }
+
+    /// `<name a="1">`, or one attribute per line when too wide.
+    fn start_tag(&self, el: &Element, level: usize) -> Vec<String> {
+        let pad = self.pad(level);
+        let attrs: Vec<String> = el
+            .attrs
+            .iter()
+            .map(|(k, v)| {
+                let quote = if v.contains('"') { '\'' } else { '"' };
+                format!("{k}={quote}{v}{quote}")
+            })
+            .collect();
+        let flat = format!(
+            "{pad}<{}{}>",
+            el.name,
+            attrs.iter().map(|a| format!(" {a}")).collect::<String>()
+        );
+        if attrs.len() < 2 || flat.chars().count() <= self.opts.line_width {
+            return vec![flat];
+        }
+        let inner = self.pad(level + 1);
+        let mut lines = vec![format!("{pad}<{}", el.name)];
+        lines.extend(attrs.iter().map(|a| format!("{inner}{a}")));
+        lines.last_mut().expect("attributes").push('>');
+        lines
     }
 }
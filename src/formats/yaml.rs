@@ -2,8 +2,72 @@ use std::path::Path;
 
 use anyhow::Result;
 use pretty_yaml::{config::*, format_text};
+use serde::Deserialize;
 
-use super::{FormatError, ensure_newline};
+use super::{FormatError, bash, ensure_newline, options};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct YamlOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+    /// Indent block sequences under their key (`key:\n  - a`) instead of
+    /// writing them flush (`key:\n- a`).
+    pub indent_sequences: bool,
+    pub quotes: YamlQuotes,
+    /// Wrap flow collections wider than this; unlimited when unset.
+    pub line_width: Option<usize>,
+    pub document_start: DocumentStart,
+    pub trim_trailing_whitespace: bool,
+    /// Kubernetes manifests: order top-level keys (`apiVersion`, `kind`,
+    /// `metadata`, `spec`, ...), sort `metadata.labels`/`annotations`, and
+    /// keep Go-template actions in Helm `templates/*.yaml` intact.
+    pub kubernetes: bool,
+    /// GitHub Actions workflows, `.gitlab-ci.yml` and Compose files: put
+    /// keys in canonical order (`name`, `on`, `permissions`, `env`, `jobs`; in
+    /// steps `name`, `id`, `if`, `uses`, `with`, `run`).
+    pub workflow_order: bool,
+    /// GitHub Actions workflows: format `run: |` scripts as bash.
+    pub format_run_scripts: bool,
+}
+
+impl Default for YamlOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            indent_sequences: true,
+            quotes: YamlQuotes::PreferDouble,
+            line_width: None,
+            document_start: DocumentStart::Preserve,
+            trim_trailing_whitespace: false,
+            kubernetes: false,
+            workflow_order: false,
+            format_run_scripts: true,
+        }
+    }
+}
+
+/// Quote style for strings that need no particular quoting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum YamlQuotes {
+    /// Double quotes unless the string contains one.
+    PreferDouble,
+    PreferSingle,
+    ForceDouble,
+    ForceSingle,
+}
+
+/// What to do with the `---` marker of the first document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DocumentStart {
+    Preserve,
+    /// Add `---` when the stream does not start with one.
+    Always,
+    /// Drop a bare leading `---` (separators between documents stay).
+    Never,
+}
 
 fn detect_line_break(text: &str) -> LineBreak {
     #[cfg(windows)]
@@ -18,25 +82,72 @@ fn detect_line_break(text: &str) -> LineBreak {
     }
 }
 
-pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
-    // Configure pretty_yaml to mirror yamlfmt 基本預設：縮排 2、無文檔起始、保留鍵順序、
-    // 不強制行寬、不修剪行尾空白、保留注釋。
+pub fn format(path: &Path, text: &str) -> Result<Option<String>, FormatError> {
+    format_with_options(path, text, &options::for_path(path).yaml)
+}
+
+/// YAML formatter backed by pretty_yaml. The defaults mirror yamlfmt: indent
+/// 2, keys in source order, comments kept, no line width, no `---` added and
+/// trailing whitespace left alone.
+pub fn format_with_options(
+    path: &Path,
+    text: &str,
+    opts: &YamlOptions,
+) -> Result<Option<String>, FormatError> {
     let line_break = detect_line_break(text);
+    let newline = match line_break {
+        LineBreak::Crlf => "\r\n",
+        LineBreak::Lf => "\n",
+    };
     let mut options = FormatOptions::default();
-    options.layout.indent_width = 2;
-    options.layout.print_width = 10_000; // 模擬「無行寬限制」
+    options.layout.indent_width = opts.indent;
+    options.layout.print_width = opts.line_width.unwrap_or(10_000);
     options.layout.line_break = line_break;
+    options.language.quotes = match opts.quotes {
+        YamlQuotes::PreferDouble => Quotes::PreferDouble,
+        YamlQuotes::PreferSingle => Quotes::PreferSingle,
+        YamlQuotes::ForceDouble => Quotes::ForceDouble,
+        YamlQuotes::ForceSingle => Quotes::ForceSingle,
+    };
+    options.language.indent_block_sequence_in_map = opts.indent_sequences;
     options.language.trailing_comma = false;
-    options.language.trim_trailing_whitespaces = false;
+    options.language.trim_trailing_whitespaces = opts.trim_trailing_whitespace;
     options.language.prefer_single_line = false;
     options.language.flow_sequence_prefer_single_line = None;
     options.language.flow_map_prefer_single_line = None;
 
-    let formatted = format_text(text, &options)
+    let helm = opts.kubernetes && is_helm_template(path);
+    let (source, actions) = if helm {
+        mask_templates(text)?
+    } else {
+        (text.to_string(), Vec::new())
+    };
+    let formatted = format_text(&source, &options)
         .map_err(|e| FormatError::Message(format!("YAML format error: {e}")))?;
-
-    // pretty_yaml 不自動確保尾隨換行，與現有 formatter 行為一致需要補齊。
-    let output = ensure_newline(formatted);
+    // pretty_yaml does not guarantee a trailing newline.
+    let mut formatted = ensure_newline(formatted);
+    if opts.kubernetes {
+        formatted = map_documents(&formatted, |doc| order_block(doc, true).concat());
+    }
+    if let Some(workflow) = workflow_for(path) {
+        if opts.workflow_order {
+            let rules = match workflow {
+                Workflow::GithubActions => GITHUB_ACTIONS_ORDER,
+                Workflow::GitlabCi => GITLAB_CI_ORDER,
+                Workflow::Compose => COMPOSE_ORDER,
+            };
+            formatted = map_documents(&formatted, |doc| {
+                order_workflow(&doc.concat(), rules, &mut Vec::new())
+            });
+        }
+        if workflow == Workflow::GithubActions && opts.format_run_scripts {
+            formatted = format_run_scripts(path, &formatted, newline);
+        }
+    }
+    if helm {
+        formatted = unmask_templates(&formatted, &actions)?;
+    }
+    let output = document_start(formatted, opts.document_start, newline);
 
     if output == text {
         Ok(None)
@@ -44,3 +155,730 @@ pub fn format(_path: &Path, text: &str) -> Result<Option<String>, FormatError> {
         Ok(Some(output))
     }
 }
+
+/// Add or drop the first document's `---`, looking past leading comments.
+/// Streams starting with a `%YAML`/`%TAG` directive need the marker and are
+/// left alone.
+fn document_start(text: String, mode: DocumentStart, newline: &str) -> String {
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end();
+        if content.is_empty() || content.starts_with('#') {
+            offset += line.len();
+            continue;
+        }
+        return match mode {
+            DocumentStart::Always if !content.starts_with("---") && !content.starts_with('%') => {
+                format!("---{newline}{text}")
+            }
+            DocumentStart::Never if content == "---" => {
+                format!("{}{}", &text[..offset], &text[offset + line.len()..])
+            }
+            _ => text,
+        };
+    }
+    text
+}
+
+/// Top-level key order of a Kubernetes object; other keys follow in source
+/// order, then `status`.
+const KUBERNETES_ORDER: &[&str] = &[
+    "apiVersion",
+    "kind",
+    "metadata",
+    "type",
+    "immutable",
+    "spec",
+    "data",
+    "binaryData",
+    "stringData",
+];
+
+const LINE_ACTION: &str = "#__helm_line_";
+const INLINE_ACTION: &str = "__helm_tpl_";
+
+/// Files in a chart's `templates/` directory.
+fn is_helm_template(path: &Path) -> bool {
+    path.parent()
+        .is_some_and(|dir| dir.components().any(|c| c.as_os_str() == "templates"))
+}
+
+/// Replace Helm `{{ ... }}` actions so the file parses as YAML: lines made
+/// only of actions (`{{- if .Values.x }}`) become comments, other actions
+/// become plain scalars. Returns the placeholders with their actions.
+fn mask_templates(text: &str) -> Result<(String, Vec<(String, String)>), FormatError> {
+    let action_end = |from: usize| {
+        text[from..]
+            .find("}}")
+            .map(|i| from + i + 2)
+            .ok_or_else(|| FormatError::Message("unterminated template action `{{`".into()))
+    };
+    let mut out = String::new();
+    let mut actions = Vec::new();
+    let mut pos = 0;
+    let mut line_start = true;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        if line_start {
+            let indent = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            if rest[indent..].starts_with("{{") {
+                let mut end = action_end(pos + indent)?;
+                loop {
+                    let after = text[end..].trim_start_matches([' ', '\t']);
+                    if after.starts_with("{{") {
+                        end = action_end(text.len() - after.len())?;
+                        continue;
+                    }
+                    if !(after.is_empty() || after.starts_with('\n') || after.starts_with("\r\n")) {
+                        end = 0;
+                    }
+                    break;
+                }
+                if end > 0 {
+                    let placeholder = format!("{LINE_ACTION}{}__", actions.len());
+                    out.push_str(&rest[..indent]);
+                    out.push_str(&placeholder);
+                    actions.push((placeholder, text[pos..end].to_string()));
+                    pos = end;
+                    line_start = false;
+                    continue;
+                }
+            }
+        }
+        if rest.starts_with("{{") {
+            let end = action_end(pos)?;
+            let action = &text[pos..end];
+            // Inside '...' a `"` in the placeholder stops the quotes from being
+            // switched to double quotes, which the action's own `"` would break.
+            let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+            let single_quoted = line.matches('\'').count() % 2 == 1;
+            let quote = if single_quoted && action.contains('"') {
+                "\""
+            } else {
+                ""
+            };
+            let placeholder = format!("{INLINE_ACTION}{}{quote}__", actions.len());
+            out.push_str(&placeholder);
+            actions.push((placeholder, action.to_string()));
+            pos = end;
+            line_start = false;
+            continue;
+        }
+        let c = rest.chars().next().expect("non-empty");
+        out.push(c);
+        pos += c.len_utf8();
+        line_start = c == '\n';
+    }
+    Ok((out, actions))
+}
+
+/// Put the masked actions back; action-only lines keep their original indentation.
+fn unmask_templates(text: &str, actions: &[(String, String)]) -> Result<String, FormatError> {
+    let mut restored = vec![false; actions.len()];
+    let mut out = String::new();
+    for line in text.split_inclusive('\n') {
+        let content = line.trim();
+        let index = content
+            .starts_with(LINE_ACTION)
+            .then(|| actions.iter().position(|(p, _)| p == content))
+            .flatten();
+        match index {
+            Some(n) => {
+                out.push_str(&actions[n].1);
+                out.push_str(&line[line.trim_end().len()..]);
+                restored[n] = true;
+            }
+            None => out.push_str(line),
+        }
+    }
+    for (n, (placeholder, action)) in actions.iter().enumerate() {
+        if !restored[n] && out.contains(placeholder.as_str()) {
+            out = out.replace(placeholder.as_str(), action);
+            restored[n] = true;
+        }
+    }
+    match restored.iter().position(|done| !done) {
+        Some(n) => Err(FormatError::Message(format!(
+            "YAML format error: could not keep template action `{}` intact",
+            actions[n].1
+        ))),
+        None => Ok(out),
+    }
+}
+
+/// Rewrite each document of a formatted stream; `---`/`...` lines are kept.
+fn map_documents(text: &str, mut f: impl FnMut(Vec<&str>) -> String) -> String {
+    let mut out = String::new();
+    let mut doc: Vec<&str> = Vec::new();
+    for line in text.split_inclusive('\n') {
+        let marker = ["---", "..."].iter().any(|m| {
+            line.strip_prefix(m)
+                .is_some_and(|rest| rest.trim().is_empty() || rest.starts_with(' '))
+        });
+        if marker {
+            out.push_str(&f(std::mem::take(&mut doc)));
+            out.push_str(line);
+        } else {
+            doc.push(line);
+        }
+    }
+    out.push_str(&f(doc));
+    out
+}
+
+/// A key of a block mapping with the lines that belong to it.
+struct Entry<'a> {
+    key: String,
+    /// Blank lines and comments above the key.
+    lead: Vec<&'a str>,
+    line: &'a str,
+    body: Vec<&'a str>,
+}
+
+/// Split the lines of a block mapping into entries. Returns `None` when the
+/// lines are not a block mapping (a sequence, a scalar, a directive, ...).
+fn split_entries<'a>(lines: &[&'a str]) -> Option<(Vec<&'a str>, Vec<Entry<'a>>, Vec<&'a str>)> {
+    let indent_of = |line: &str| line.len() - line.trim_start_matches(' ').len();
+    let indent = lines
+        .iter()
+        .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|l| indent_of(l))?;
+    let mut head = Vec::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut pending = Vec::new();
+    for &line in lines {
+        let content = line.trim();
+        let deeper = indent_of(line) > indent;
+        if content.is_empty() || (content.starts_with('#') && !deeper) {
+            pending.push(line);
+            continue;
+        }
+        if deeper || content == "-" || content.starts_with("- ") {
+            let entry = entries.last_mut()?;
+            entry.body.append(&mut pending);
+            entry.body.push(line);
+            continue;
+        }
+        if indent_of(line) < indent {
+            return None;
+        }
+        let key = mapping_key(content)?;
+        if entries.is_empty() {
+            head.append(&mut pending);
+        }
+        entries.push(Entry {
+            key,
+            lead: std::mem::take(&mut pending),
+            line,
+            body: Vec::new(),
+        });
+    }
+    Some((head, entries, pending))
+}
+
+fn join_entries<'a>(
+    head: Vec<&'a str>,
+    entries: Vec<Entry<'a>>,
+    tail: Vec<&'a str>,
+) -> Vec<&'a str> {
+    let mut out = head;
+    for entry in entries {
+        out.extend(entry.lead);
+        out.push(entry.line);
+        out.extend(entry.body);
+    }
+    out.extend(tail);
+    out
+}
+
+/// `key: value`, `key:` or `"quoted key": value`.
+fn mapping_key(content: &str) -> Option<String> {
+    if let Some(quote) = content.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let end = content[1..].find(quote)? + 1;
+        let rest = &content[end + 1..];
+        return (rest == ":" || rest.starts_with(": ")).then(|| content[1..end].to_string());
+    }
+    if content.starts_with(['?', '[', '{', '&', '*', '!', '|', '>', '%']) {
+        return None;
+    }
+    let colon = content
+        .find(": ")
+        .or_else(|| content.strip_suffix(':').map(str::len))?;
+    Some(content[..colon].to_string())
+}
+
+/// Order the top-level keys of an object (documents with a `kind`) and sort
+/// `labels`/`annotations` under every `metadata`, recursing into mappings.
+/// Levels where Helm action lines sit between keys are left in order, since
+/// moving keys would move them out of their `if`/`range` blocks.
+fn order_block(lines: Vec<&str>, top: bool) -> Vec<&str> {
+    let Some((head, mut entries, tail)) = split_entries(&lines) else {
+        return lines;
+    };
+    let templated = entries
+        .iter()
+        .any(|e| e.lead.iter().any(|l| l.contains(LINE_ACTION)));
+    if top && !templated && entries.iter().any(|e| e.key == "kind") {
+        let rank = |key: &str| match KUBERNETES_ORDER.iter().position(|k| *k == key) {
+            Some(i) => i,
+            None if key == "status" => KUBERNETES_ORDER.len() + 1,
+            None => KUBERNETES_ORDER.len(),
+        };
+        entries.sort_by_key(|e| rank(&e.key));
+    }
+    for entry in &mut entries {
+        let body = std::mem::take(&mut entry.body);
+        entry.body = if entry.key == "metadata" {
+            sort_metadata(body)
+        } else {
+            order_block(body, false)
+        };
+    }
+    join_entries(head, entries, tail)
+}
+
+fn sort_metadata(lines: Vec<&str>) -> Vec<&str> {
+    let Some((head, mut entries, tail)) = split_entries(&lines) else {
+        return lines;
+    };
+    for entry in entries
+        .iter_mut()
+        .filter(|e| e.key == "labels" || e.key == "annotations")
+    {
+        let Some((inner_head, mut inner, inner_tail)) = split_entries(&entry.body) else {
+            continue;
+        };
+        if inner
+            .iter()
+            .any(|e| e.lead.iter().any(|l| l.contains(LINE_ACTION)))
+        {
+            continue;
+        }
+        inner.sort_by(|a, b| a.key.cmp(&b.key));
+        entry.body = join_entries(inner_head, inner, inner_tail);
+    }
+    join_entries(head, entries, tail)
+}
+
+/// Files whose schema fixes a conventional key order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Workflow {
+    GithubActions,
+    GitlabCi,
+    Compose,
+}
+
+fn workflow_for(path: &Path) -> Option<Workflow> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    if ext != "yml" && ext != "yaml" {
+        return None;
+    }
+    let dir = path.parent();
+    let in_workflows = dir.is_some_and(|d| d.ends_with(".github/workflows"));
+    if in_workflows {
+        Some(Workflow::GithubActions)
+    } else if name == ".gitlab-ci.yml" || name == ".gitlab-ci.yaml" {
+        Some(Workflow::GitlabCi)
+    } else if name.starts_with("docker-compose.") || name.starts_with("compose.") {
+        Some(Workflow::Compose)
+    } else {
+        None
+    }
+}
+
+/// Key order by mapping path: `*` matches any key, `-` a sequence item.
+/// Keys not listed keep their relative order after the listed ones.
+type KeyOrder = &'static [(&'static [&'static str], &'static [&'static str])];
+
+const GITHUB_ACTIONS_ORDER: KeyOrder = &[
+    (
+        &[],
+        &[
+            "name",
+            "run-name",
+            "on",
+            "permissions",
+            "env",
+            "defaults",
+            "concurrency",
+            "jobs",
+        ],
+    ),
+    (
+        &["jobs", "*"],
+        &[
+            "name",
+            "needs",
+            "if",
+            "runs-on",
+            "environment",
+            "concurrency",
+            "permissions",
+            "outputs",
+            "env",
+            "defaults",
+            "timeout-minutes",
+            "continue-on-error",
+            "strategy",
+            "container",
+            "services",
+            "uses",
+            "with",
+            "secrets",
+            "steps",
+        ],
+    ),
+    (
+        &["jobs", "*", "steps", "-"],
+        &["name", "id", "if", "uses", "with", "run"],
+    ),
+];
+
+/// Global keywords first; jobs keep their order after them.
+const GITLAB_CI_ORDER: KeyOrder = &[
+    (
+        &[],
+        &["include", "workflow", "default", "stages", "variables"],
+    ),
+    (
+        &["*"],
+        &[
+            "extends",
+            "stage",
+            "image",
+            "services",
+            "tags",
+            "rules",
+            "needs",
+            "dependencies",
+            "variables",
+            "cache",
+            "before_script",
+            "script",
+            "after_script",
+            "artifacts",
+        ],
+    ),
+];
+
+const COMPOSE_ORDER: KeyOrder = &[
+    (
+        &[],
+        &[
+            "version", "name", "include", "services", "networks", "volumes", "configs", "secrets",
+        ],
+    ),
+    (
+        &["services", "*"],
+        &[
+            "image",
+            "build",
+            "container_name",
+            "hostname",
+            "restart",
+            "depends_on",
+            "entrypoint",
+            "command",
+            "working_dir",
+            "user",
+            "env_file",
+            "environment",
+            "ports",
+            "expose",
+            "volumes",
+            "networks",
+            "healthcheck",
+            "deploy",
+            "labels",
+        ],
+    ),
+];
+
+fn path_matches(pattern: &[&str], path: &[String]) -> bool {
+    pattern.len() == path.len() && pattern.iter().zip(path).all(|(p, k)| *p == "*" || p == k)
+}
+
+/// Reorder the mappings of a document that `rules` cover, descending into
+/// block mappings and sequences while a rule could still apply below.
+fn order_workflow(text: &str, rules: KeyOrder, path: &mut Vec<String>) -> String {
+    let below = rules.iter().any(|(pattern, _)| {
+        pattern.len() >= path.len() && path_matches(&pattern[..path.len()], path)
+    });
+    if !below {
+        return text.to_string();
+    }
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if let Some((head, mut entries, tail)) = split_entries(&lines) {
+        if let Some((_, order)) = rules
+            .iter()
+            .find(|(pattern, _)| path_matches(pattern, path))
+        {
+            let rank = |key: &str| order.iter().position(|k| *k == key).unwrap_or(order.len());
+            entries.sort_by_key(|e| rank(&e.key));
+        }
+        let mut out = head.concat();
+        for entry in entries {
+            out.push_str(&entry.lead.concat());
+            out.push_str(entry.line);
+            path.push(entry.key);
+            out.push_str(&order_workflow(&entry.body.concat(), rules, path));
+            path.pop();
+        }
+        out.push_str(&tail.concat());
+        return out;
+    }
+    let Some((head, items, tail)) = split_items(&lines) else {
+        return text.to_string();
+    };
+    let mut out = head.concat();
+    path.push("-".to_string());
+    for (lead, item) in items {
+        out.push_str(&lead.concat());
+        // Order `- a: 1` items as the mapping `  a: 1`, then put the dash back
+        // on whichever key ends up first.
+        let dash = item[0].len() - item[0].trim_start().len();
+        let mapping = format!(
+            "{} {}{}",
+            &item[0][..dash],
+            &item[0][dash + 1..],
+            item[1..].concat()
+        );
+        let ordered = order_workflow(&mapping, rules, path);
+        let mut first = true;
+        for line in ordered.split_inclusive('\n') {
+            let content = line.trim();
+            if first && !content.is_empty() && !content.starts_with('#') {
+                out.push_str(&line[..dash]);
+                out.push('-');
+                out.push_str(&line[dash + 1..]);
+                first = false;
+            } else {
+                out.push_str(line);
+            }
+        }
+    }
+    path.pop();
+    out.push_str(&tail.concat());
+    out
+}
+
+/// Items of a block sequence, each with the comments and blank lines above it.
+#[allow(clippy::type_complexity)]
+fn split_items<'a>(
+    lines: &[&'a str],
+) -> Option<(
+    Vec<&'a str>,
+    Vec<(Vec<&'a str>, Vec<&'a str>)>,
+    Vec<&'a str>,
+)> {
+    let indent_of = |line: &str| line.len() - line.trim_start_matches(' ').len();
+    let indent = lines
+        .iter()
+        .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|l| indent_of(l))?;
+    let mut head = Vec::new();
+    let mut items: Vec<(Vec<&str>, Vec<&str>)> = Vec::new();
+    let mut pending = Vec::new();
+    for &line in lines {
+        let content = line.trim();
+        let deeper = indent_of(line) > indent;
+        if content.is_empty() || (content.starts_with('#') && !deeper) {
+            pending.push(line);
+        } else if deeper {
+            let (_, item) = items.last_mut()?;
+            item.append(&mut pending);
+            item.push(line);
+        } else if content == "-" || content.starts_with("- ") {
+            if items.is_empty() {
+                head.append(&mut pending);
+            }
+            items.push((std::mem::take(&mut pending), vec![line]));
+        } else {
+            return None;
+        }
+    }
+    Some((head, items, pending))
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Column of the key on `line`, past a leading `- `.
+fn key_column(line: &str) -> usize {
+    let content = line.trim_start_matches(' ');
+    indent_of(line) + content.len() - content.strip_prefix("- ").unwrap_or(content).len()
+}
+
+/// Lines of the mapping whose keys sit at `indent` and that contains line `at`.
+fn mapping_bounds(lines: &[&str], at: usize, indent: usize) -> (usize, usize) {
+    let significant = |line: &str| !line.trim().is_empty() && !line.trim_start().starts_with('#');
+    let is_item = |line: &str| line.trim_start().starts_with("- ") && key_column(line) == indent;
+    let mut start = at;
+    while start > 0 && !(significant(lines[start]) && is_item(lines[start])) {
+        let above = lines[start - 1];
+        if significant(above) && indent_of(above) < indent {
+            // The parent key, or the `- ` starting this sequence item.
+            if is_item(above) {
+                start -= 1;
+            }
+            break;
+        }
+        start -= 1;
+    }
+    let mut end = at + 1;
+    while end < lines.len() && !(significant(lines[end]) && indent_of(lines[end]) < indent) {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Scalar at `path` (`["defaults", "run", "shell"]`) in the mapping at
+/// `indent` spanning `range`.
+fn lookup(lines: &[&str], range: (usize, usize), indent: usize, path: &[&str]) -> Option<String> {
+    let (first, rest) = path.split_first()?;
+    let at = (range.0..range.1).find(|&i| {
+        let line = lines[i];
+        key_column(line) == indent && {
+            let content = line.trim_start();
+            let content = content.strip_prefix("- ").unwrap_or(content);
+            content
+                .split_once(':')
+                .is_some_and(|(key, _)| key.trim().trim_matches(['"', '\'']) == *first)
+        }
+    })?;
+    let value = lines[at].split_once(':')?.1.trim();
+    if rest.is_empty() {
+        let value = value.split(" #").next().unwrap_or_default().trim();
+        return Some(value.trim_matches(['"', '\'']).to_string());
+    }
+    let child = lines[at + 1..range.1]
+        .iter()
+        .position(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|p| at + 1 + p)?;
+    let child_indent = key_column(lines[child]);
+    if child_indent <= indent {
+        return None;
+    }
+    lookup(
+        lines,
+        mapping_bounds(lines, child, child_indent),
+        child_indent,
+        rest,
+    )
+}
+
+/// Whether the step holding the `run:` key on line `at` runs in bash or sh.
+fn runs_in_bash(lines: &[&str], at: usize, indent: usize) -> bool {
+    const SHELL: &[&str] = &["defaults", "run", "shell"];
+    let step = mapping_bounds(lines, at, indent);
+    let mut shell = lookup(lines, step, indent, &["shell"]);
+    let mut windows = false;
+    // The job holds the `steps:` sequence the step belongs to.
+    let steps = (0..step.0)
+        .rev()
+        .find(|&i| key_column(lines[i]) < indent && lines[i].trim().starts_with("steps:"));
+    if let Some(steps) = steps {
+        let job_indent = key_column(lines[steps]);
+        let job = mapping_bounds(lines, steps, job_indent);
+        shell = shell.or_else(|| lookup(lines, job, job_indent, SHELL));
+        windows = lookup(lines, job, job_indent, &["runs-on"])
+            .is_some_and(|runner| runner.contains("windows"));
+    }
+    let shell = shell.or_else(|| lookup(lines, (0, lines.len()), 0, SHELL));
+    match shell {
+        Some(shell) => {
+            let program = shell.split_whitespace().next().unwrap_or_default();
+            matches!(program.rsplit('/').next(), Some("bash" | "sh"))
+        }
+        None => !windows,
+    }
+}
+
+/// Replaces each `${{ … }}` with a plain `${GITHUB_EXPR}` parameter.
+fn mask_expressions(script: &str) -> String {
+    let mut out = String::new();
+    let mut rest = script;
+    while let Some(at) = rest.find("${{") {
+        let Some(len) = rest[at..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..at]);
+        out.push_str("${GITHUB_EXPR}");
+        rest = &rest[at + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Format the body of every `run: |` block scalar whose step runs in bash or
+/// sh with the bash formatter; steps with another `shell:` (set on the step
+/// or in `defaults.run.shell`) or on a Windows runner are left alone. A
+/// script bash cannot parse is left as written with a warning.
+fn format_run_scripts(path: &Path, text: &str, newline: &str) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        out.push_str(line);
+        i += 1;
+        let content = line.trim();
+        let key = content.strip_prefix("- ").unwrap_or(content);
+        if !matches!(key, "run: |" | "run: |-" | "run: |+") {
+            continue;
+        }
+        let key_indent = indent_of(line) + content.len() - key.len();
+        if !runs_in_bash(&lines, i - 1, key_indent) {
+            continue;
+        }
+        let start = i;
+        while i < lines.len() && (lines[i].trim().is_empty() || indent_of(lines[i]) > key_indent) {
+            i += 1;
+        }
+        // Trailing blank lines are not part of the script.
+        while i > start && lines[i - 1].trim().is_empty() {
+            i -= 1;
+        }
+        let body = &lines[start..i];
+        let Some(indent) = body
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| indent_of(l))
+            .min()
+        else {
+            continue;
+        };
+        let script: String = body
+            .iter()
+            .map(|l| format!("{}\n", l.get(indent..).unwrap_or("").trim_end()))
+            .collect();
+        // `${{ expr }}` is substituted before the shell sees the script.
+        let masked = mask_expressions(&script);
+        let res = match bash::syntax_error_line(&masked) {
+            Some(line) => Err((start + line, "bash syntax error".to_string())),
+            None => bash::format(&path.with_extension("sh"), &script)
+                .map_err(|err| (start, err.to_string().trim_end().to_string())),
+        };
+        let formatted = match res {
+            Ok(formatted) => formatted.unwrap_or(script),
+            Err((line, err)) => {
+                eprintln!(
+                    "{}: warning: line {line}: run script left unformatted: {err}",
+                    path.display()
+                );
+                out.extend(body.iter().copied());
+                continue;
+            }
+        };
+        for line in formatted.lines() {
+            if !line.is_empty() {
+                out.push_str(&" ".repeat(indent));
+                out.push_str(line);
+            }
+            out.push_str(newline);
+        }
+    }
+    out
+}
@@ -2,14 +2,20 @@ mod formats;
 
 use std::collections::HashSet;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow};
 use clap::{ArgAction, Parser};
 use ignore::{DirEntry, WalkBuilder};
 use rayon::prelude::*;
+use similar::TextDiff;
 
-use crate::formats::{FormatKind, detect_kind, detect_kind_from_label, format_dispatch};
+use crate::formats::ndjson;
+use crate::formats::options::{self, CONFIG_FILE_NAME, Config};
+use crate::formats::{
+    FormatKind, check_notes, detect_kind, detect_kind_from_label, format_dispatch,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Multi-language formatter (pure Rust)")]
@@ -30,6 +36,10 @@ struct Cli {
     #[arg(long, action = ArgAction::SetTrue)]
     check: bool,
 
+    /// Print a unified diff of the changes instead of writing them
+    #[arg(long, action = ArgAction::SetTrue)]
+    diff: bool,
+
     /// Number of worker threads (default: CPU cores)
     #[arg(long, value_name = "N")]
     jobs: Option<usize>,
@@ -46,6 +56,10 @@ struct Cli {
     #[arg(long, value_delimiter = ',', value_name = "KINDS")]
     skip: Vec<String>,
 
+    /// Formatter options file (default: ./.formatter.toml when present)
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
     /// Verbose logging
     #[arg(long, short, action = ArgAction::SetTrue)]
     verbose: bool,
@@ -74,6 +88,14 @@ fn main() -> Result<()> {
             .unwrap_or(1)
     });
 
+    let config_path = cli.config.clone().or_else(|| {
+        let default = PathBuf::from(CONFIG_FILE_NAME);
+        default.is_file().then_some(default)
+    });
+    if let Some(path) = config_path {
+        options::install(Config::load(&path)?);
+    }
+
     let wanted_only = to_kind_set(&cli.only);
     let skip = to_kind_set(&cli.skip);
 
@@ -236,11 +258,6 @@ fn process_file(job: &FileJob, output_root: &Option<PathBuf>, cli: &Cli) -> Outc
         }
     };
 
-    let content = match fs::read_to_string(&job.path) {
-        Ok(c) => c,
-        Err(_err) => return Outcome::Error,
-    };
-
     let relative = job
         .path
         .strip_prefix(&job.root)
@@ -257,6 +274,15 @@ fn process_file(job: &FileJob, output_root: &Option<PathBuf>, cli: &Cli) -> Outc
         .map(|root| root.join(&relative))
         .unwrap_or_else(|| job.path.clone());
 
+    if kind == FormatKind::Ndjson && !cli.diff {
+        return process_stream(job, &target_path, cli);
+    }
+
+    let content = match fs::read_to_string(&job.path) {
+        Ok(c) => c,
+        Err(_err) => return Outcome::Error,
+    };
+
     let formatted = match format_dispatch(kind, &job.path, &content) {
         Ok(Some(new_text)) => new_text,
         Ok(None) => {
@@ -264,7 +290,7 @@ fn process_file(job: &FileJob, output_root: &Option<PathBuf>, cli: &Cli) -> Outc
                 if cli.verbose {
                     println!("Copy unchanged {}", job.path.display());
                 }
-                if !cli.check && !cli.dry_run {
+                if !cli.check && !cli.dry_run && !cli.diff {
                     if let Some(parent) = target_path.parent() {
                         if let Err(_err) = fs::create_dir_all(parent) {
                             return Outcome::Error;
@@ -277,10 +303,21 @@ fn process_file(job: &FileJob, output_root: &Option<PathBuf>, cli: &Cli) -> Outc
             }
             return Outcome::Unchanged;
         }
-        Err(_err) => return Outcome::Error,
+        Err(err) => {
+            eprintln!("{}: {err}", job.path.display());
+            return Outcome::Error;
+        }
     };
 
-    if cli.check || cli.dry_run {
+    if cli.diff {
+        print_diff(&job.path, &content, &formatted);
+    }
+    if cli.check {
+        for note in check_notes(kind, &job.path, &content) {
+            println!("{}: {note}", job.path.display());
+        }
+    }
+    if cli.check || cli.dry_run || cli.diff {
         if cli.verbose {
             println!("Would format {}", job.path.display());
         }
@@ -298,6 +335,75 @@ fn process_file(job: &FileJob, output_root: &Option<PathBuf>, cli: &Cli) -> Outc
     }
 }
 
+/// Printed in one piece so that diffs from parallel workers do not interleave.
+fn print_diff(path: &Path, old: &str, new: &str) {
+    let name = path.display().to_string();
+    let diff = TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&name, &name)
+        .to_string();
+    print!("{diff}");
+}
+
+/// NDJSON logs can run to gigabytes, so they are formatted line by line into
+/// a temporary file next to the target instead of being read whole.
+fn process_stream(job: &FileJob, target_path: &Path, cli: &Cli) -> Outcome {
+    let input = match fs::File::open(&job.path) {
+        Ok(f) => io::BufReader::new(f),
+        Err(_err) => return Outcome::Error,
+    };
+
+    if cli.check || cli.dry_run {
+        return match ndjson::format_stream(input, io::sink()) {
+            Ok(true) => {
+                if cli.verbose {
+                    println!("Would format {}", job.path.display());
+                }
+                Outcome::Formatted
+            }
+            Ok(false) => Outcome::Unchanged,
+            Err(err) => {
+                eprintln!("{}: {err}", job.path.display());
+                Outcome::Error
+            }
+        };
+    }
+
+    let dir = match target_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if let Err(_err) = fs::create_dir_all(dir) {
+        return Outcome::Error;
+    }
+    let mut tmp = match tempfile::NamedTempFile::new_in(dir) {
+        Ok(tmp) => tmp,
+        Err(_err) => return Outcome::Error,
+    };
+    if let Ok(meta) = fs::metadata(&job.path) {
+        let _ = tmp.as_file().set_permissions(meta.permissions());
+    }
+    let changed = match ndjson::format_stream(input, io::BufWriter::new(tmp.as_file_mut())) {
+        Ok(changed) => changed,
+        Err(err) => {
+            eprintln!("{}: {err}", job.path.display());
+            return Outcome::Error;
+        }
+    };
+    let mirrored = target_path != job.path;
+    if !changed && !mirrored {
+        return Outcome::Unchanged;
+    }
+    if !changed && cli.verbose {
+        println!("Copy unchanged {}", job.path.display());
+    }
+    match tmp.persist(target_path) {
+        Ok(_) if changed => Outcome::Formatted,
+        Ok(_) => Outcome::Unchanged,
+        Err(_) => Outcome::Error,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -317,10 +423,12 @@ mod tests {
             output: Some(tmp.path().join("out")),
             dry_run: false,
             check: false,
+            diff: false,
             jobs: Some(1),
             ignore: vec![],
             only: vec![],
             skip: vec![],
+            config: None,
             verbose: false,
         };
         let out_root = cli.output.clone();
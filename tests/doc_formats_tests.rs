@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use formatter::formats::markdown::MarkdownOptions;
+use formatter::formats::{FormatKind, asciidoc, detect_kind, mdx, rst};
+
+#[test]
+fn mdx_formats_esm_and_jsx_through_typescript() {
+    let input = "---\ntitle: x\n---\nimport {Tabs} from './tabs'\nexport const meta = {a:1}\n\n# Hello\n\n<Tabs items={['a','b']}><Tab>one</Tab></Tabs>\n\n<Note>\n\n*   some **md**\n\n</Note>\n\n```js\nlet a=1\n```\n";
+    let expected = "---\ntitle: x\n---\n\nimport { Tabs } from \"./tabs\";\nexport const meta = { a: 1 };\n\n# Hello\n\n<Tabs items={[\"a\", \"b\"]}>\n  <Tab>one</Tab>\n</Tabs>\n\n<Note>\n\n- some **md**\n\n</Note>\n\n```js\nlet a = 1;\n```\n";
+    let (out, warnings) = mdx::format_with_warnings(input, &MarkdownOptions::default()).unwrap();
+    assert_eq!(out.as_deref(), Some(expected));
+    assert!(warnings.is_empty(), "{warnings:?}");
+    let (again, _) = mdx::format_with_warnings(expected, &MarkdownOptions::default()).unwrap();
+    assert_eq!(again, None);
+}
+
+#[test]
+fn rst_normalizes_sections_lists_and_tables() {
+    let input = "=====\nTitle\n=====\nIntro text.\n\n\n\n* one\n* two\n\n  continued\n\n.. code-block:: json\n   :caption: data\n\n   {\"a\":1,\n    \"b\":[1,2]}\n\nLiteral::\n\n    *  keep\n\n\n    this\n\nSub section\n----\n\n+-------+-----+\n| a     | b   |\n+=======+=====+\n| 名稱  | 2   |\n+-------+-----+\n";
+    let expected = "=====\nTitle\n=====\n\nIntro text.\n\n- one\n- two\n\n  continued\n\n.. code-block:: json\n   :caption: data\n\n   { \"a\": 1, \"b\": [1, 2] }\n\nLiteral::\n\n    *  keep\n\n\n    this\n\nSub section\n-----------\n\n+------+---+\n| a    | b |\n+======+===+\n| 名稱 | 2 |\n+------+---+\n";
+    let (out, warnings) = rst::format_with_warnings(input);
+    assert_eq!(out.as_deref(), Some(expected));
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(rst::format_with_warnings(expected).0, None);
+
+    let spanning = "+---+---+\n| a     |\n+---+---+\n";
+    assert_eq!(rst::format_with_warnings(spanning).0, None);
+    let (_, warnings) = rst::format_with_warnings("Text\n\n.. code:: json\n\n   {bad\n");
+    assert!(
+        warnings[0].starts_with("line 5: `json` code block"),
+        "{warnings:?}"
+    );
+}
+
+#[test]
+fn rst_rewrites_bullets_per_list() {
+    let input = "Sub\n---\n* one\n* two\n\n  body\n\n* three\n";
+    let expected = "Sub\n---\n\n- one\n- two\n\n  body\n\n- three\n";
+    assert_eq!(
+        rst::format_with_warnings(input).0.as_deref(),
+        Some(expected)
+    );
+    assert_eq!(rst::format_with_warnings(expected).0, None);
+
+    // A change of bullet starts a new list, so neither is rewritten into
+    // the other.
+    let separate = "* a\n\n- b\n\n+ c\n  more\n\n- d\n";
+    assert_eq!(rst::format_with_warnings(separate).0, None);
+}
+
+#[test]
+fn asciidoc_normalizes_sections_lists_tables_and_listings() {
+    let input = "= Doc\n:toc:\n\n==  Intro\ntext\n\n\n*   one\n**    two\n.  first\n\n[source,json]\n.Example\n----\n{\"a\":1}\n----\n\n----\nkeep   this\n----\n\n|===\n|a |bbbb\n\n|cc|d\n|===\n\n[cols=\"2\"]\n|===\n2+|span\n|===\n";
+    let expected = "= Doc\n:toc:\n\n== Intro\n\ntext\n\n* one\n** two\n. first\n\n[source,json]\n.Example\n----\n{ \"a\": 1 }\n----\n\n----\nkeep   this\n----\n\n|===\n| a  | bbbb\n\n| cc | d\n|===\n\n[cols=\"2\"]\n|===\n2+|span\n|===\n";
+    let (out, warnings) = asciidoc::format_with_warnings(input);
+    assert_eq!(out.as_deref(), Some(expected));
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(asciidoc::format_with_warnings(expected).0, None);
+}
+
+#[test]
+fn detects_document_formats() {
+    for (path, kind) in [
+        ("docs/intro.mdx", FormatKind::Mdx),
+        ("docs/index.rst", FormatKind::Rst),
+        ("docs/guide.adoc", FormatKind::AsciiDoc),
+        ("docs/guide.asciidoc", FormatKind::AsciiDoc),
+    ] {
+        assert_eq!(detect_kind(Path::new(path)), Some(kind), "{path}");
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use formatter::formats::{
+    FormatError, FormatKind, apache, caddyfile, detect_kind, format_dispatch, haproxy,
+};
+
+fn run(
+    f: fn(&Path, &str) -> anyhow::Result<Option<String>, FormatError>,
+    path: &str,
+    input: &str,
+) -> String {
+    f(Path::new(path), input)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn detects_conventional_filenames() {
+    let cases = [
+        ("etc/httpd/conf/httpd.conf", FormatKind::Apache),
+        ("site/.htaccess", FormatKind::Apache),
+        (
+            "etc/apache2/sites-available/default.conf",
+            FormatKind::Apache,
+        ),
+        ("etc/haproxy/haproxy.cfg", FormatKind::Haproxy),
+        ("deploy/haproxy-edge.cfg", FormatKind::Haproxy),
+        ("Caddyfile", FormatKind::Caddyfile),
+        ("Caddyfile.dev", FormatKind::Caddyfile),
+        ("etc/nginx/conf.d/app.conf", FormatKind::Nginx),
+    ];
+    for (path, kind) in cases {
+        assert_eq!(detect_kind(Path::new(path)), Some(kind), "{path}");
+    }
+}
+
+#[test]
+fn generic_conf_files_are_routed_by_content() {
+    let apache_src = "<VirtualHost *:80>\nServerName a\n</VirtualHost>\n";
+    let out = format_dispatch(FormatKind::Nginx, Path::new("site.conf"), apache_src)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        out,
+        "<VirtualHost *:80>\n    ServerName a\n</VirtualHost>\n"
+    );
+
+    let haproxy_src = "global\nmaxconn 100\n";
+    let out = format_dispatch(FormatKind::Nginx, Path::new("lb.conf"), haproxy_src)
+        .unwrap()
+        .unwrap();
+    assert_eq!(out, "global\n    maxconn 100\n");
+}
+
+#[test]
+fn apache_indents_nested_sections() {
+    let input = "# main\nServerRoot   \"/etc/httpd\"\n\n\n<VirtualHost *:80>\n\nDocumentRoot \"/var/www/my  site\"\n<Directory \"/var/www\" >\nOptions   Indexes FollowSymLinks\n\n</Directory>\n</VirtualHost>\n";
+    let expected = "# main\nServerRoot \"/etc/httpd\"\n\n<VirtualHost *:80>\n    DocumentRoot \"/var/www/my  site\"\n    <Directory \"/var/www\">\n        Options Indexes FollowSymLinks\n    </Directory>\n</VirtualHost>\n";
+    assert_eq!(run(apache::format, "httpd.conf", input), expected);
+}
+
+#[test]
+fn apache_continuations_and_mismatched_tags() {
+    let input =
+        "<IfModule log_config_module>\nLogFormat \"%h %l\" \\\n\"%u\" common\n</IfModule>\n";
+    let expected = "<IfModule log_config_module>\n    LogFormat \"%h %l\" \\\n        \"%u\" common\n</IfModule>\n";
+    assert_eq!(run(apache::format, "httpd.conf", input), expected);
+
+    let err = apache::format(Path::new("httpd.conf"), "<Directory />\n</Location>\n").unwrap_err();
+    assert!(err.to_string().contains("closes <Directory>"));
+}
+
+#[test]
+fn haproxy_indents_sections_and_keeps_header_comments() {
+    let input = "global\n  log   stdout format raw local0\ndefaults\nmode http\n# frontend for web\nfrontend web\n    bind *:80\n\n    # route api\n  use_backend api if { path_beg /api }\n";
+    let expected = "global\n    log stdout format raw local0\n\ndefaults\n    mode http\n\n# frontend for web\nfrontend web\n    bind *:80\n\n    # route api\n    use_backend api if { path_beg /api }\n";
+    assert_eq!(run(haproxy::format, "haproxy.cfg", input), expected);
+    // A blank line already before the comments is not doubled.
+    let input = "backend a\n  server s 1.2.3.4\n\n# web\n# tier\nfrontend b\n  bind :80\n";
+    let expected = "backend a\n    server s 1.2.3.4\n\n# web\n# tier\nfrontend b\n    bind :80\n";
+    assert_eq!(run(haproxy::format, "haproxy.cfg", input), expected);
+}
+
+#[test]
+fn caddyfile_indents_blocks_with_tabs() {
+    let input = "{\nemail a@b.c\n}\n\nexample.com{\n  @api   path /api/*\n    reverse_proxy @api localhost:8080 {\n  header_up Host {upstream_hostport}\n}\n\n\n  respond \"hello   world\"\n}\n";
+    let expected = "{\n\temail a@b.c\n}\n\nexample.com {\n\t@api path /api/*\n\treverse_proxy @api localhost:8080 {\n\t\theader_up Host {upstream_hostport}\n\t}\n\n\trespond \"hello   world\"\n}\n";
+    assert_eq!(run(caddyfile::format, "Caddyfile", input), expected);
+}
+
+#[test]
+fn caddyfile_keeps_heredocs_verbatim() {
+    let input = "a.com {\nrespond <<HTML\n  <p>  x </p>\n  HTML 200\n}\n";
+    let expected = "a.com {\n\trespond <<HTML\n  <p>  x </p>\n  HTML 200\n}\n";
+    assert_eq!(run(caddyfile::format, "Caddyfile", input), expected);
+}
+
+#[test]
+fn caddyfile_keeps_trailing_comments_on_block_lines() {
+    let input =
+        "example.com   { # main\n  respond \"a # b\"   # reply\n  header X-Id `#1`\n} # end\n";
+    let expected =
+        "example.com { # main\n\trespond \"a # b\" # reply\n\theader X-Id `#1`\n} # end\n";
+    assert_eq!(run(caddyfile::format, "Caddyfile", input), expected);
+    assert_eq!(
+        caddyfile::format(Path::new("Caddyfile"), expected).unwrap(),
+        None
+    );
+}
@@ -81,7 +81,7 @@ fn html_serializes_and_trims() {
 fn ini_round_trips_and_trims() {
     let input = " [a]\n x = 1 \n";
     let out = run(ini::format, "a.ini", input);
-    assert!(out.contains("[a]\nx=1"));
+    assert!(out.contains("[a]\nx = 1"));
     assert!(out.ends_with('\n'));
 }
 
@@ -167,7 +167,7 @@ fn python_ruff_spacing() {
 fn rlang_indents_braces() {
     let input = "if (TRUE){\nprint(1)\n}\n";
     let out = run(rlang::format, "a.r", input);
-    assert!(out.contains("if (TRUE){"));
+    assert!(out.contains("if (TRUE) {"));
     assert!(out.contains("\n  print(1)\n"));
 }
 
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use formatter::formats::{FormatKind, detect_kind, hcl};
+
+fn fmt(path: &str, input: &str) -> String {
+    hcl::format(Path::new(path), input)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn aligns_attribute_groups_and_keeps_comments() {
+    let input = "# network\nresource \"aws_vpc\" \"main\"{\n# CIDR for the VPC\ncidr_block=\"10.0.0.0/16\"\n  enable_dns_support   = true // needed\ntags={Name=\"main\"}\n\n  count = var.n*2\n}\n";
+    let expected = "# network\nresource \"aws_vpc\" \"main\" {\n  # CIDR for the VPC\n  cidr_block         = \"10.0.0.0/16\"\n  enable_dns_support = true // needed\n  tags               = { Name = \"main\" }\n\n  count = var.n * 2\n}\n";
+    assert_eq!(fmt("main.tf", input), expected);
+}
+
+#[test]
+fn multi_line_values_break_alignment_groups() {
+    let input = "locals {\na = 1\nlong_name = {\nx = [1,2]\ny=-1\n}\nb = f(a)[0]\n}\n";
+    let expected =
+        "locals {\n  a = 1\n  long_name = {\n    x = [1, 2]\n    y = -1\n  }\n  b = f(a)[0]\n}\n";
+    assert_eq!(fmt("main.tf", input), expected);
+}
+
+#[test]
+fn heredocs_and_templates_are_verbatim() {
+    let input = "user_data=<<-EOT\n   #!/bin/bash\n     echo \"${var.x}\"\n  EOT\nname = \"${ var.a }-x\"\n";
+    let expected = "user_data = <<-EOT\n   #!/bin/bash\n     echo \"${var.x}\"\n  EOT\nname = \"${ var.a }-x\"\n";
+    assert_eq!(fmt("main.tf", input), expected);
+}
+
+#[test]
+fn expressions_are_spaced_like_terraform_fmt() {
+    let input = "x = {for k,v in var.m : k=>v if !v.skip}\ny = [for s in local.list: upper(s)]\nz = a.*.id\nw = var.b ? 1 : -1\n";
+    let expected = "x = { for k, v in var.m : k => v if !v.skip }\ny = [for s in local.list : upper(s)]\nz = a.*.id\nw = var.b ? 1 : -1\n";
+    assert_eq!(fmt("main.tf", input), expected);
+}
+
+#[test]
+fn tfvars_and_parse_errors() {
+    let input = "region=\"eu-west-1\"\ninstance_count =3\n";
+    assert_eq!(
+        fmt("prod.tfvars", input),
+        "region         = \"eu-west-1\"\ninstance_count = 3\n"
+    );
+    assert!(hcl::format(Path::new("main.tf"), "a = {\n").is_err());
+}
+
+#[test]
+fn detects_terraform_packer_and_nomad_files() {
+    for path in [
+        "main.tf",
+        "prod.tfvars",
+        "tests/main.tftest.hcl",
+        "build.pkr.hcl",
+        "jobs/web.nomad",
+        "jobs/web.nomad.hcl",
+    ] {
+        assert_eq!(
+            detect_kind(Path::new(path)),
+            Some(FormatKind::Hcl),
+            "{path}"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use formatter::formats::html::{self, HtmlOptions, Template};
+use formatter::formats::{FormatKind, detect_kind};
+
+fn fmt(input: &str, template: Template) -> String {
+    html::format_template_with_options(
+        Path::new("page.html"),
+        input,
+        &HtmlOptions::default(),
+        template,
+    )
+    .unwrap()
+    .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn jinja_blocks_are_indented_like_elements() {
+    let input = "{% extends \"base.html\" %}\n{% block content %}\n<ul>\n{% for item in items %}\n<li {% if item.active %}class=\"on\"{% endif %}>{{ item.name }}</li>\n{% else %}\n<li>None</li>\n{% endfor %}\n</ul>\n{% endblock %}\n";
+    let expected = "{% extends \"base.html\" %}\n{% block content %}\n  <ul>\n    {% for item in items %}\n      <li {% if item.active %}class=\"on\"{% endif %}>{{ item.name }}</li>\n    {% else %}\n      <li>None</li>\n    {% endfor %}\n  </ul>\n{% endblock %}\n";
+    assert_eq!(fmt(input, Template::Jinja), expected);
+}
+
+#[test]
+fn single_line_blocks_stay_in_the_text_flow() {
+    let input = "<p>Hello {% if user %}{{ user.name }}{% else %}guest{% endif %}!</p>\n";
+    assert_eq!(fmt(input, Template::Jinja), input);
+}
+
+#[test]
+fn elements_split_across_branches_are_not_closed() {
+    let input =
+        "{% if wide %}<div class=\"wide\">{% else %}<div>{% endif %}\n<p>body</p>\n</div>\n";
+    let expected = "{% if wide %}\n  <div class=\"wide\">\n{% else %}\n  <div>\n{% endif %}\n<p>body</p>\n</div>\n";
+    assert_eq!(fmt(input, Template::Jinja), expected);
+}
+
+#[test]
+fn raw_blocks_and_templated_scripts_are_verbatim() {
+    let input =
+        "{% raw %}{{ keep  this }}{% endraw %}\n<script>var x = {{ data|tojson }};</script>\n";
+    let expected = "{% raw %}{{ keep  this }}{% endraw %}\n<script>\n  var x = {{ data|tojson }};\n</script>\n";
+    assert_eq!(fmt(input, Template::Jinja), expected);
+}
+
+#[test]
+fn handlebars_and_go_blocks() {
+    let hbs = "{{!-- list --}}\n<div>\n{{#if author}}\n<h1>{{first}} {{last}}</h1>\n{{else}}\n<h1>Unknown</h1>\n{{/if}}\n{{> footer}}\n</div>\n";
+    let expected = "{{!-- list --}}\n<div>\n  {{#if author}}\n    <h1>{{first}} {{last}}</h1>\n  {{else}}\n    <h1>Unknown</h1>\n  {{/if}}\n  {{> footer}}\n</div>\n";
+    assert_eq!(fmt(hbs, Template::Handlebars), expected);
+
+    let go = "{{define \"main\"}}\n<main>\n{{range .Items}}\n<h2>{{.Title}}</h2>\n{{end}}\n</main>\n{{end}}\n";
+    let expected = "{{define \"main\"}}\n  <main>\n    {{range .Items}}\n      <h2>{{.Title}}</h2>\n    {{end}}\n  </main>\n{{end}}\n";
+    assert_eq!(fmt(go, Template::Go), expected);
+}
+
+#[test]
+fn detects_template_extensions() {
+    let cases = [
+        ("templates/page.html.j2", Some(FormatKind::Jinja)),
+        ("templates/page.jinja", Some(FormatKind::Jinja)),
+        ("roles/web/templates/nginx.conf.j2", None),
+        ("web/page.gohtml", Some(FormatKind::GoTemplate)),
+        ("web/page.html.tmpl", Some(FormatKind::GoTemplate)),
+        ("views/post.hbs", Some(FormatKind::Handlebars)),
+    ];
+    for (path, kind) in cases {
+        assert_eq!(detect_kind(Path::new(path)), kind, "{path}");
+    }
+}
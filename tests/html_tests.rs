@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use formatter::formats::html::{self, HtmlOptions};
+
+fn fmt(input: &str) -> String {
+    fmt_with(input, &HtmlOptions::default())
+}
+
+fn fmt_with(input: &str, opts: &HtmlOptions) -> String {
+    html::format_with_options(Path::new("index.html"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn indents_blocks_and_keeps_doctype() {
+    let input = "<!doctype html>\n<html><head><meta charset=utf-8><title>Demo</title></head>\n<body><ul><li>one<li>two</ul></body></html>\n";
+    let expected = "<!doctype html>\n<html>\n  <head>\n    <meta charset=\"utf-8\">\n    <title>Demo</title>\n  </head>\n  <body>\n    <ul>\n      <li>one</li>\n      <li>two</li>\n    </ul>\n  </body>\n</html>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn wraps_inline_text_and_breaks_after_br() {
+    let opts = HtmlOptions {
+        line_width: 30,
+        ..HtmlOptions::default()
+    };
+    let input = "<p>Hello <b>bold   world</b>,<em>glued</em> text that wraps here.<br>Next</p>";
+    let expected = "<p>\n  Hello <b>bold\n  world</b>,<em>glued</em>\n  text that wraps here.<br>\n  Next\n</p>\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+}
+
+#[test]
+fn wraps_long_start_tags_one_attribute_per_line() {
+    let input = "<section><input type=\"text\" name=\"query\" placeholder=\"Search the documentation\" autofocus></section>";
+    let expected = "<section>\n  <input\n    type=\"text\"\n    name=\"query\"\n    placeholder=\"Search the documentation\"\n    autofocus\n  >\n</section>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn preserves_pre_textarea_and_comments() {
+    let input =
+        "<div>\n<pre>  a\n    b </pre>\n\n\n<!-- keep -->\n<textarea>  x\n y</textarea></div>";
+    let expected = "<div>\n  <pre>  a\n    b </pre>\n\n  <!-- keep -->\n  <textarea>  x\n y</textarea>\n</div>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn delegates_script_style_and_json_ld() {
+    let input = "<head>\n<style>p{color:red}</style>\n<script>\n    let a={b:1}\n</script>\n<script type=\"application/ld+json\">{\"@type\":\"Thing\"}</script>\n<script type=\"text/template\">\n  <b>{{ x }}</b>\n</script>\n</head>\n";
+    let out = fmt(input);
+    assert!(out.contains("  <style>\n    p {\n"), "{out}");
+    assert!(
+        out.contains("  <script>\n    let a = { b: 1 };\n  </script>\n"),
+        "{out}"
+    );
+    assert!(
+        out.contains(
+            "<script type=\"application/ld+json\">\n    { \"@type\": \"Thing\" }\n  </script>"
+        ),
+        "{out}"
+    );
+    assert!(
+        out.contains("<script type=\"text/template\">\n    <b>{{ x }}</b>\n  </script>"),
+        "{out}"
+    );
+}
+
+#[test]
+fn output_is_stable_and_errors_are_reported() {
+    let once = fmt("<div><p>a <a href=x>link</a></p><img src=a.png></div>");
+    assert_eq!(fmt(&once), once);
+    assert!(html::format(Path::new("a.html"), "<div><!-- open").is_err());
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use formatter::formats::ini::{self, IniOptions};
+use formatter::formats::{FormatKind, detect_kind};
+
+fn fmt(path: &str, input: &str) -> String {
+    fmt_with(path, input, &IniOptions::default())
+}
+
+fn fmt_with(path: &str, input: &str, opts: &IniOptions) -> String {
+    ini::format_with_options(Path::new(path), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn keeps_comments_order_and_duplicate_keys() {
+    let input = "; global\nname=demo\n\n\n# servers\n[ server ]  ; primary\nhost=\"a b\"\nhost=b\n# port below\nport :8080\nflag\n";
+    let expected = "; global\nname = demo\n\n# servers\n[server] ; primary\nhost = \"a b\"\nhost = b\n# port below\nport: 8080\nflag\n";
+    assert_eq!(fmt("app.ini", input), expected);
+}
+
+#[test]
+fn configparser_continuations_are_reindented() {
+    let input = "[options]\ninstall_requires =\n  requests>=2.0\n\tclick\npackages=find:\n";
+    let expected =
+        "[options]\ninstall_requires =\n    requests>=2.0\n    click\npackages = find:\n";
+    assert_eq!(fmt("setup.cfg", input), expected);
+}
+
+#[test]
+fn gitconfig_keys_are_tab_indented() {
+    let input = "[user]\nname=A B\n    email = a@b.c\n[alias]\n  # short\n  co = checkout\n";
+    let expected = "[user]\n\tname = A B\n\temail = a@b.c\n\n[alias]\n\t# short\n\tco = checkout\n";
+    assert_eq!(fmt(".gitconfig", input), expected);
+}
+
+#[test]
+fn systemd_units_keep_compact_pairs_and_backslash_continuations() {
+    let input = "[Service]\nExecStart = /bin/app \\\n    --flag\nRestart= always\n";
+    let expected = "[Service]\nExecStart=/bin/app \\\n    --flag\nRestart=always\n";
+    assert_eq!(fmt("app.service", input), expected);
+}
+
+#[test]
+fn aligns_equals_within_each_section() {
+    let opts = IniOptions {
+        align_equals: true,
+        ..IniOptions::default()
+    };
+    let input = "[a]\nx=1\nlong_name=2\n[b]\nyy=3\n";
+    let expected = "[a]\nx         = 1\nlong_name = 2\n\n[b]\nyy = 3\n";
+    assert_eq!(fmt_with("a.ini", input, &opts), expected);
+}
+
+#[test]
+fn detects_conventional_ini_files() {
+    for path in [
+        "setup.cfg",
+        "tox.ini",
+        "php.ini",
+        "home/.gitconfig",
+        "repo/.git/config",
+        "etc/my.cnf",
+        "etc/systemd/system/app.service",
+    ] {
+        assert_eq!(
+            detect_kind(Path::new(path)),
+            Some(FormatKind::Ini),
+            "{path}"
+        );
+    }
+}
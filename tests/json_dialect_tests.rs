@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use formatter::formats::{FormatKind, detect_kind, hjson, json, json5, ndjson};
+
+fn run(
+    format: fn(&Path, &str) -> Result<Option<String>, formatter::formats::FormatError>,
+    path: &str,
+    input: &str,
+) -> String {
+    format(Path::new(path), input)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn known_jsonc_files_keep_trailing_commas() {
+    let input = "{\n  // strict mode\n  \"compilerOptions\": {\"strict\": true,},\n  \"include\": [\n    \"src\"\n  ],\n}\n";
+    let jsonc = "{\n  // strict mode\n  \"compilerOptions\": { \"strict\": true },\n  \"include\": [\n    \"src\",\n  ],\n}\n";
+    for path in [
+        "tsconfig.json",
+        "tsconfig.build.json",
+        ".vscode/settings.json",
+        ".devcontainer/devcontainer.json",
+    ] {
+        assert_eq!(run(json::format, path, input), jsonc, "{path}");
+    }
+    let plain = "{\n  // strict mode\n  \"compilerOptions\": { \"strict\": true },\n  \"include\": [\n    \"src\"\n  ]\n}\n";
+    assert_eq!(run(json::format, "data.json", input), plain);
+}
+
+#[test]
+fn json5_keeps_its_own_literals() {
+    let input = "// config\n{unquoted: 'single', hex: 0xFF, half: .5, whole: 5., big: +Infinity, nan: NaN,\nline: \"a\\\nb\", list: [1, 2,],}\n";
+    let expected = "// config\n{\n  unquoted: 'single',\n  hex: 0xFF,\n  half: .5,\n  whole: 5.,\n  big: +Infinity,\n  nan: NaN,\n  line: \"a\\\nb\",\n  list: [1, 2],\n}\n";
+    let out = run(json5::format, "a.json5", input);
+    assert_eq!(out, expected);
+    assert_eq!(run(json5::format, "a.json5", &out), out);
+}
+
+#[test]
+fn hjson_is_laid_out_one_member_per_line() {
+    let input = "# settings\n{\n    name: my app, # quoteless\n  port: 8080, debug:true\n\n\n  tags: [ \"a\", 2 ]\n  nested: {\n  // inner\n  key: \"v\"\n  }\n      text:\n          '''\n          first\n            second\n          '''\n}\n";
+    let expected = "# settings\n{\n  name: my app, # quoteless\n  port: 8080\n  debug: true\n\n  tags: [\"a\", 2]\n  nested: {\n    // inner\n    key: \"v\"\n  }\n  text:\n    '''\n    first\n      second\n    '''\n}\n";
+    let out = run(hjson::format, "a.hjson", input);
+    assert_eq!(out, expected);
+    assert_eq!(run(hjson::format, "a.hjson", &out), out);
+}
+
+#[test]
+fn hjson_root_braces_are_optional() {
+    let input = "a: 1\nlist:\n[\n1\n2\n]\n";
+    assert_eq!(
+        run(hjson::format, "a.hjson", input),
+        "a: 1\nlist: [\n  1\n  2\n]\n"
+    );
+    assert!(hjson::format(Path::new("a.hjson"), "{\n  a: [1, 2\n").is_err());
+}
+
+#[test]
+fn ndjson_compacts_each_record_and_keeps_lines() {
+    let input = "{ \"a\": 1,  \"b\": [ 1.50, \"x y\" ] }\r\n\n{\"c\":{\"d\":null}}";
+    assert_eq!(
+        run(ndjson::format, "log.ndjson", input),
+        "{\"a\":1,\"b\":[1.50,\"x y\"]}\r\n\n{\"c\":{\"d\":null}}\n"
+    );
+    let err = ndjson::format(Path::new("log.jsonl"), "{}\n{\"a\": }\n").unwrap_err();
+    assert!(err.to_string().starts_with("line 2:"), "{err}");
+
+    let mut out = Vec::new();
+    let changed = ndjson::format_stream("{\"a\":1}\n[]\n".as_bytes(), &mut out).unwrap();
+    assert!(!changed);
+    assert_eq!(out, b"{\"a\":1}\n[]\n");
+}
+
+#[test]
+fn detects_json_dialects() {
+    for (path, kind) in [
+        ("a.json5", FormatKind::Json5),
+        ("a.hjson", FormatKind::Hjson),
+        ("events.ndjson", FormatKind::Ndjson),
+        ("events.jsonl", FormatKind::Ndjson),
+        ("tsconfig.json", FormatKind::Json),
+    ] {
+        assert_eq!(detect_kind(Path::new(path)), Some(kind), "{path}");
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use formatter::formats::json::{self, JsonOptions};
+
+fn fmt_with(input: &str, opts: &JsonOptions) -> String {
+    json::format_with_options(Path::new("a.json"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn formats_only_by_default() {
+    let input = "{\"b\":1,\"a\":{\"d\":1.50,\"c\":2}}";
+    assert_eq!(
+        fmt_with(input, &JsonOptions::default()),
+        "{ \"b\": 1, \"a\": { \"d\": 1.50, \"c\": 2 } }\n"
+    );
+}
+
+#[test]
+fn sorts_keys_recursively_with_priority_and_comments() {
+    let opts = JsonOptions {
+        sort_keys: true,
+        priority_keys: vec!["$schema".into(), "name".into()],
+        ..JsonOptions::default()
+    };
+    let input = "// header\n{\n  \"zeta\": 1, // about zeta\n  /* about alpha */\n  \"alpha\": {\"y\": [{\"b\": 1, \"a\": 2}], \"x\": 1},\n  \"name\": \"demo\",\n  \"$schema\": \"s\"\n}\n";
+    let expected = "// header\n{\n  \"$schema\": \"s\",\n  \"name\": \"demo\",\n  /* about alpha */\n  \"alpha\": { \"x\": 1, \"y\": [{ \"a\": 2, \"b\": 1 }] },\n  \"zeta\": 1 // about zeta\n}\n";
+    let out = fmt_with(input, &opts);
+    assert_eq!(out, expected);
+    assert_eq!(fmt_with(&out, &opts), out);
+}
+
+#[test]
+fn normalizes_numbers_and_strings() {
+    let opts = JsonOptions {
+        normalize: true,
+        ..JsonOptions::default()
+    };
+    let input =
+        "[1.50, 1E3, -0.0, -0, 2e-7, 12345678901234567890, \"\\u0041\\/\\u00e9\", \"tab\\t\"]\n";
+    let expected = "[1.5, 1000, -0.0, -0, 2e-7, 12345678901234567890, \"A/é\", \"tab\\t\"]\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+    // Digits an f64 cannot hold are kept as written.
+    let input = "[3.14159265358979323846, 0.100000000000000000001, 1e-400, 1.25E+2]\n";
+    let expected = "[3.14159265358979323846, 0.100000000000000000001, 1e-400, 125]\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+}
+
+#[test]
+fn rejects_duplicate_keys() {
+    let opts = JsonOptions {
+        reject_duplicate_keys: true,
+        ..JsonOptions::default()
+    };
+    let err =
+        json::format_with_options(Path::new("a.json"), "{\"a\": {\"x\": 1,\n\"x\": 2}}", &opts)
+            .unwrap_err();
+    assert_eq!(err.to_string(), "duplicate key \"x\" on line 2");
+    assert!(json::format(Path::new("a.json"), "{\"x\": 1, \"x\": 2}").is_ok());
+}
+
+#[test]
+fn canonical_output_follows_rfc_8785() {
+    let opts = JsonOptions {
+        canonical: true,
+        ..JsonOptions::default()
+    };
+    let input = "{\n  \"numbers\": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000001, 1e-7],\n  \"string\": \"\\u20ac$\\u000F\\u000aA'\\u0042\\u0022\\u005c\\\\\\\"\\/\",\n  \"literals\": [null, true, false],\n  \"\\u00e9\": 1, \"\\r\": 2, \"1\": 3\n}\n";
+    let expected = "{\"\\r\":2,\"1\":3,\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,0.000001,1e-7],\"string\":\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\",\"é\":1}";
+    assert_eq!(fmt_with(input, &opts), expected);
+    assert!(json::format_with_options(Path::new("a.json"), "{\"a\": 1, // c\n}", &opts).is_err());
+}
+
+fn fmt_preset(path: &str, input: &str) -> String {
+    let opts = JsonOptions {
+        presets: true,
+        ..JsonOptions::default()
+    };
+    json::format_with_options(Path::new(path), input, &opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn package_json_preset_orders_fields_and_sorts_dependencies() {
+    let input = "{\n  \"scripts\": {\n    \"test\": \"jest\",\n    \"build\": \"tsc\"\n  },\n  \"devDependencies\": {\n    \"typescript\": \"^5.0.0\",\n    \"@types/node\": \"^20.0.0\"\n  },\n  \"custom\": true,\n  \"version\": \"1.0.0\",\n  \"name\": \"demo\",\n  \"dependencies\": { \"zod\": \"^3.0.0\", \"axios\": \"^1.0.0\" }\n}\n";
+    let expected = "{\n  \"name\": \"demo\",\n  \"version\": \"1.0.0\",\n  \"scripts\": {\n    \"test\": \"jest\",\n    \"build\": \"tsc\"\n  },\n  \"dependencies\": { \"axios\": \"^1.0.0\", \"zod\": \"^3.0.0\" },\n  \"devDependencies\": {\n    \"@types/node\": \"^20.0.0\",\n    \"typescript\": \"^5.0.0\"\n  },\n  \"custom\": true\n}\n";
+    assert_eq!(fmt_preset("app/package.json", input), expected);
+    // Other files and the default options are left in their order.
+    assert_eq!(fmt_preset("other.json", expected), expected);
+    assert_eq!(fmt_with(input, &JsonOptions::default()), input);
+}
+
+#[test]
+fn composer_tsconfig_and_eslintrc_presets() {
+    let composer = "{\n  \"require\": {\n    \"symfony/console\": \"^6.0\",\n    \"ext-json\": \"*\",\n    \"monolog/monolog\": \"^3.0\",\n    \"php\": \">=8.1\"\n  },\n  \"name\": \"acme/app\"\n}\n";
+    assert_eq!(
+        fmt_preset("composer.json", composer),
+        "{\n  \"name\": \"acme/app\",\n  \"require\": {\n    \"ext-json\": \"*\",\n    \"php\": \">=8.1\",\n    \"monolog/monolog\": \"^3.0\",\n    \"symfony/console\": \"^6.0\"\n  }\n}\n"
+    );
+
+    let tsconfig = "{\n  // shared settings\n  \"include\": [\"src\"],\n  \"compilerOptions\": { \"strict\": true, \"noEmit\": true },\n  \"extends\": \"./base.json\",\n}\n";
+    assert_eq!(
+        fmt_preset("tsconfig.app.json", tsconfig),
+        "{\n  \"extends\": \"./base.json\",\n  \"compilerOptions\": { \"strict\": true, \"noEmit\": true },\n  // shared settings\n  \"include\": [\"src\"],\n}\n"
+    );
+
+    let eslintrc =
+        "{\n  \"rules\": { \"semi\": \"error\", \"eqeqeq\": \"warn\" },\n  \"root\": true\n}\n";
+    assert_eq!(
+        fmt_preset(".eslintrc.json", eslintrc),
+        "{\n  \"root\": true,\n  \"rules\": { \"eqeqeq\": \"warn\", \"semi\": \"error\" },\n}\n"
+    );
+}
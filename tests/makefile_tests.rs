@@ -0,0 +1,60 @@
+use std::path::Path;
+
+use formatter::formats::makefile::{self, MakefileOptions};
+
+fn fmt(input: &str) -> String {
+    fmt_with(input, &MakefileOptions::default())
+}
+
+fn fmt_with(input: &str, opts: &MakefileOptions) -> String {
+    makefile::format_with_options(Path::new("Makefile"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn assignments_are_not_rules() {
+    let input = "CC:=gcc\nOBJS::=a.o\nCFLAGS?=-O2\nLDFLAGS +=-lm\nNOW!=date\nexport PATH = /bin\nSRC=$(wildcard *.c:.c=.o)\n";
+    let expected = "CC := gcc\nOBJS ::= a.o\nCFLAGS ?= -O2\nLDFLAGS += -lm\nNOW != date\nexport PATH = /bin\nSRC = $(wildcard *.c:.c=.o)\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn variable_continuations_are_not_recipes() {
+    let input = "all: build\n\techo done\nSRCS = a.c \\\n    b.c \\\n  c.c\nbuild:\n  cc $(SRCS)\n";
+    let expected =
+        "all: build\n\techo done\nSRCS = a.c \\\n    b.c \\\n  c.c\nbuild:\n\tcc $(SRCS)\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn conditionals_are_indented_by_depth() {
+    let input = "ifeq($(OS),Windows_NT)\nEXT:=.exe\nifdef DEBUG\nCFLAGS+=-g\nendif\nelse ifeq ($(OS),Darwin)\nEXT:=\nelse\nEXT:=.bin\nendif\n";
+    let expected = "ifeq ($(OS),Windows_NT)\n  EXT := .exe\n  ifdef DEBUG\n    CFLAGS += -g\n  endif\nelse ifeq ($(OS),Darwin)\n  EXT :=\nelse\n  EXT := .bin\nendif\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn define_bodies_are_untouched() {
+    let input =
+        "define RUN_TEST\n  @echo  $(1) : x=1\n\tgo test $(1)\nendef\nx:\n\t$(call RUN_TEST,a)\n";
+    assert_eq!(fmt(input), input);
+}
+
+#[test]
+fn rules_phony_pattern_and_target_specific_variables() {
+    let input = ".PHONY:  all   clean\n%.o:%.c   | dirs\n\t$(CC) -c $<\ndebug:CFLAGS+=-g\nclean:;rm -f *.o\n";
+    let expected = ".PHONY: all clean\n%.o: %.c | dirs\n\t$(CC) -c $<\ndebug: CFLAGS += -g\nclean: ; rm -f *.o\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn aligns_assignment_operators_when_enabled() {
+    let opts = MakefileOptions {
+        align_assignments: true,
+        ..MakefileOptions::default()
+    };
+    let input = "CC = gcc\nCFLAGS ?= -O2\n\nPREFIX := /usr\n";
+    let expected = "CC     = gcc\nCFLAGS ?= -O2\n\nPREFIX := /usr\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+}
@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use formatter::formats::markdown::{
+    self, Emphasis, ListMarker, MarkdownOptions, Numbering, TextWrap,
+};
+
+fn fmt_with(input: &str, opts: &MarkdownOptions) -> String {
+    markdown::format_with_options(Path::new("a.md"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn formats_yaml_and_toml_front_matter() {
+    let input = "---\ntitle:   Hello\ntags: [ a,  b ]\n---\n# Title\n";
+    assert_eq!(
+        fmt_with(input, &MarkdownOptions::default()),
+        "---\ntitle: Hello\ntags: [a, b]\n---\n\n# Title\n"
+    );
+    let input = "+++\ntitle=\"Hello\"\n+++\n\n# Title\n";
+    assert_eq!(
+        fmt_with(input, &MarkdownOptions::default()),
+        "+++\ntitle = \"Hello\"\n+++\n\n# Title\n"
+    );
+    let templated = "---\ntitle: {{ .Title\n---\n\ntext\n";
+    assert_eq!(fmt_with(templated, &MarkdownOptions::default()), templated);
+    // Template tags that happen to parse as YAML are not rewritten either.
+    let templated = "---\n{{ .Foo }}\n---\n\ntext\n";
+    assert_eq!(fmt_with(templated, &MarkdownOptions::default()), templated);
+}
+
+#[test]
+fn markers_follow_options() {
+    let opts = MarkdownOptions {
+        emphasis: Emphasis::Asterisks,
+        strong: Emphasis::Underscores,
+        list_marker: ListMarker::Asterisk,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(
+        fmt_with("_a_ and **b**\n\n- one\n- two\n", &opts),
+        "*a* and __b__\n\n* one\n* two\n"
+    );
+}
+
+#[test]
+fn ordered_lists_are_renumbered() {
+    let input = "1. a\n1. b\n1. c\n";
+    let ascending = MarkdownOptions {
+        ordered_list: Numbering::Ascending,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(fmt_with(input, &ascending), "1. a\n2. b\n3. c\n");
+    assert_eq!(fmt_with(input, &MarkdownOptions::default()), input);
+
+    let one = MarkdownOptions {
+        ordered_list: Numbering::One,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(fmt_with("1. a\n2. b\n3. c\n", &one), input);
+    // The start number is kept.
+    let out = fmt_with("3. one\n7. two\n", &one);
+    assert_eq!(out, "3. one\n4. two\n");
+    assert_eq!(fmt_with(&out, &one), out);
+}
+
+#[test]
+fn wraps_text() {
+    let opts = MarkdownOptions {
+        text_wrap: TextWrap::Always,
+        line_width: 20,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(
+        fmt_with("one two three four five six\n", &opts),
+        "one two three four\nfive six\n"
+    );
+    let opts = MarkdownOptions {
+        text_wrap: TextWrap::Never,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(fmt_with("one two\nthree\n", &opts), "one two three\n");
+}
+
+#[test]
+fn fences_use_tag_mapping_and_attributes() {
+    let input = "```mjs title=\"a.mjs\"\nconst a={b:1}\n```\n\n```json no-format\n{\"a\":1}\n```\n\n```console\n$ echo   hi\n```\n\n- item\n\n  ```jsonc\n  {\"a\":1,\n  }\n  ```\n\n> ```{.yaml .numberLines}\n> a:   1\n> ```\n";
+    let expected = "```mjs title=\"a.mjs\"\nconst a = { b: 1 };\n```\n\n```json no-format\n{\"a\":1}\n```\n\n```console\n$ echo   hi\n```\n\n- item\n\n  ```jsonc\n  { \"a\": 1 }\n  ```\n\n> ```{.yaml .numberLines}\n> a: 1\n> ```\n";
+    let out = fmt_with(input, &MarkdownOptions::default());
+    assert_eq!(out, expected);
+    assert_eq!(fmt_with(&out, &MarkdownOptions::default()), out);
+
+    let mut opts = MarkdownOptions::default();
+    opts.fences.insert("conf".into(), "ini".into());
+    opts.fences.insert("mjs".into(), "none".into());
+    assert_eq!(
+        fmt_with("```conf\n[a]\nb=1\n```\n\n```mjs\nx=1\n```\n", &opts),
+        "```conf\n[a]\nb = 1\n```\n\n```mjs\nx=1\n```\n"
+    );
+}
+
+#[test]
+fn broken_blocks_are_left_alone_with_a_warning() {
+    let input = "# Title\n\n```json\n{\"a\": }\n```\n\n```yaml\na:   1\n```\n";
+    let (out, warnings) =
+        markdown::format_with_warnings(input, &MarkdownOptions::default()).unwrap();
+    assert_eq!(
+        out.as_deref(),
+        Some("# Title\n\n```json\n{\"a\": }\n```\n\n```yaml\na: 1\n```\n")
+    );
+    assert_eq!(warnings.len(), 1);
+    assert!(
+        warnings[0].starts_with("line 3: `json` code block"),
+        "{warnings:?}"
+    );
+    assert_eq!(warnings[0], warnings[0].trim_end());
+}
+
+#[test]
+fn aligns_tables_by_display_width() {
+    let opts = MarkdownOptions {
+        align_tables: true,
+        ..MarkdownOptions::default()
+    };
+    let input = "| 名稱 | b |\n|:-|:-:|\n| ⚠️ | 👨‍👩‍👧 |\n| a | x |\n\n> | a | b |\n> |---|--:|\n> | ⚠️ | 1 |\n";
+    let expected = "| 名稱 |  b  |\n| :--- | :-: |\n| ⚠️   | 👨‍👩‍👧  |\n| a    |  x  |\n\n> | a  |  b |\n> | -- | -: |\n> | ⚠️ |  1 |\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+    assert_eq!(fmt_with(expected, &opts), expected);
+}
+
+#[test]
+fn sorts_link_definitions_and_converts_inline_links() {
+    let input = "# Doc\n\n[zeta]: http://z\n\nSee [*a*](http://a \"A\") and [b][Zeta], [c](http://a \"A\").\n\n[Zeta]: http://dup\n[alpha]: http://alpha\n[2]: http://two\n\n```\n[x]: not a def\n```\n";
+    let sort = MarkdownOptions {
+        sort_link_definitions: true,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(
+        fmt_with(input, &sort),
+        "# Doc\n\nSee [_a_](http://a \"A\") and [b][Zeta], [c](http://a \"A\").\n\n```\n[x]: not a def\n```\n\n[2]: http://two\n[alpha]: http://alpha\n[zeta]: http://z\n"
+    );
+
+    let reference = MarkdownOptions {
+        reference_links: true,
+        ..MarkdownOptions::default()
+    };
+    assert_eq!(
+        fmt_with(input, &reference),
+        "# Doc\n\n[zeta]: http://z\n\nSee [_a_][1] and [b][Zeta], [c][1].\n\n[alpha]: http://alpha\n[2]: http://two\n\n```\n[x]: not a def\n```\n\n[1]: http://a \"A\"\n"
+    );
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use formatter::formats::nginx::{self, NginxOptions};
+
+fn fmt(input: &str) -> String {
+    fmt_with(input, &NginxOptions::default())
+}
+
+fn fmt_with(input: &str, opts: &NginxOptions) -> String {
+    nginx::format_with_options(Path::new("nginx.conf"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn splits_directives_and_closing_braces() {
+    let input = "http { server { listen 80; server_name a.com;} }\n";
+    let expected =
+        "http {\n    server {\n        listen 80;\n        server_name a.com;\n    }\n}\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn braces_and_semicolons_inside_quotes_are_not_structure() {
+    let input = "location ~ \"^/a{3};$\" {\nreturn 200 'ok;{}';\n}\nset $x \"${host}z\";\n";
+    let expected = "location ~ \"^/a{3};$\" {\n    return 200 'ok;{}';\n}\nset $x \"${host}z\";\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn keeps_blank_line_groups_and_comments() {
+    let input = "# top\nuser nginx;\n\n\n\nworker_processes auto; # per core\nevents {\n\n  worker_connections 1024;\n\n}\n";
+    let expected = "# top\nuser nginx;\n\nworker_processes auto; # per core\nevents {\n    worker_connections 1024;\n}\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn map_blocks_and_multiline_arguments() {
+    let input = "map $uri $new {\n  default 0;\n  ~^/old/(.*) /new/$1;\n}\nlog_format main '$remote_addr'\n      '$status';\n";
+    let expected = "map $uri $new {\n    default 0;\n    ~^/old/(.*) /new/$1;\n}\nlog_format main '$remote_addr'\n    '$status';\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn lua_block_bodies_are_reindented_not_parsed() {
+    let input = "location / {\ncontent_by_lua_block {\n      local t = { a = \"}\" } -- }\n      ngx.say(t.a)\n}\n}\n";
+    let expected = "location / {\n    content_by_lua_block {\n        local t = { a = \"}\" } -- }\n        ngx.say(t.a)\n    }\n}\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn aligns_values_when_enabled() {
+    let opts = NginxOptions {
+        indent: 2,
+        align_values: true,
+    };
+    let input = "types {\ntext/html html;\napplication/javascript js;\n}\ninclude conf.d/*.conf;\n";
+    let expected = "types {\n  text/html              html;\n  application/javascript js;\n}\ninclude conf.d/*.conf;\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+}
+
+#[test]
+fn reports_unbalanced_braces() {
+    let err = nginx::format(Path::new("nginx.conf"), "server {\nlisten 80;\n").unwrap_err();
+    assert!(err.to_string().contains("missing closing"));
+    let err = nginx::format(Path::new("nginx.conf"), "listen 80\n}").unwrap_err();
+    assert!(err.to_string().contains("line 2"));
+}
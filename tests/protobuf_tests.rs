@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use formatter::formats::options::Config;
+use formatter::formats::protobuf::{self, ProtobufOptions};
+use formatter::formats::{FormatKind, detect_kind, textproto};
+
+fn fmt_textproto(input: &str) -> String {
+    textproto::format(Path::new("a.textproto"), input)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn detects_text_format_extensions() {
+    for name in ["a.textproto", "b.txtpb", "c.pbtxt"] {
+        assert_eq!(detect_kind(Path::new(name)), Some(FormatKind::TextProto));
+    }
+}
+
+#[test]
+fn textproto_nests_messages_and_drops_separators() {
+    let input = "name:\"x\";  nested:<id:1,tags:[ \"a\",\"b\" ]>\nempty{}\n";
+    let expected = "name: \"x\"\nnested {\n  id: 1\n  tags: [\"a\", \"b\"]\n}\nempty {}\n";
+    assert_eq!(fmt_textproto(input), expected);
+}
+
+#[test]
+fn textproto_keeps_comments_blank_lines_and_escapes() {
+    let input = "# header\nfoo: 'it\\'s'  # trailing\n\n\n[ext.pkg.field] { a: -1.5e3 }\n";
+    let expected = "# header\nfoo: 'it\\'s' # trailing\n\n[ext.pkg.field] {\n  a: -1.5e3\n}\n";
+    assert_eq!(fmt_textproto(input), expected);
+}
+
+#[test]
+fn textproto_repeated_messages_and_multiline_strings() {
+    let input = "items: [{a: 1}, {a: 2}]\ntext: \"one \"\n  \"two\"\n";
+    let expected =
+        "items: [\n  {\n    a: 1\n  },\n  {\n    a: 2\n  }\n]\ntext:\n  \"one \"\n  \"two\"\n";
+    assert_eq!(fmt_textproto(input), expected);
+}
+
+#[test]
+fn textproto_reports_unclosed_message() {
+    let err = textproto::format(Path::new("a.txtpb"), "a {\n b: 1\n").unwrap_err();
+    assert!(err.to_string().contains("unclosed message"));
+}
+
+#[test]
+fn textproto_rejects_nested_lists() {
+    let err = textproto::format(Path::new("a.txtpb"), "a: [1, [2, 3]]\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "textproto parse error on line 1: lists cannot be nested, found `[`"
+    );
+}
+
+#[test]
+fn lint_accepts_conventional_names() {
+    let input = r#"syntax = "proto3";
+message SearchRequest {
+  string query = 1;
+  map<string, int32> page_counts = 2;
+  oneof filter_kind { string by_name = 3; }
+  enum Corpus {
+    CORPUS_UNSPECIFIED = 0;
+    CORPUS_WEB = 1;
+  }
+}
+service SearchService {
+  rpc Search(SearchRequest) returns (SearchRequest);
+}
+"#;
+    assert!(protobuf::lint(input).is_empty());
+}
+
+#[test]
+fn lint_reports_naming_violations_with_lines() {
+    let input = "message search_request {\n  string Query = 1;\n}\nenum Color {\n  red = 1;\n}\n";
+    let rules: Vec<_> = protobuf::lint(input)
+        .into_iter()
+        .map(|v| (v.line, v.rule))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            (1, "MESSAGE_PASCAL_CASE"),
+            (2, "FIELD_LOWER_SNAKE_CASE"),
+            (5, "ENUM_VALUE_UPPER_SNAKE_CASE"),
+            (5, "ENUM_FIRST_VALUE_ZERO"),
+        ]
+    );
+}
+
+#[test]
+fn lint_is_opt_in() {
+    let input = "message bad {\n}\n";
+    assert!(
+        protobuf::format_with_options(Path::new("a.proto"), input, &ProtobufOptions::default())
+            .is_ok()
+    );
+    let err =
+        protobuf::format_with_options(Path::new("a.proto"), input, &ProtobufOptions { lint: true })
+            .unwrap_err();
+    assert!(err.to_string().contains("MESSAGE_PASCAL_CASE"));
+}
+
+#[test]
+fn config_overrides_apply_by_glob() {
+    let root = PathBuf::from("/repo");
+    let config = Config::parse(
+        "[protobuf]\nlint = true\n\n[[overrides]]\nfiles = [\"legacy/**\"]\nprotobuf = { lint = false }\n",
+        root.clone(),
+    )
+    .unwrap();
+    assert!(config.options_for(&root.join("api/a.proto")).protobuf.lint);
+    assert!(
+        !config
+            .options_for(&root.join("legacy/old/b.proto"))
+            .protobuf
+            .lint
+    );
+    assert!(Config::parse("[protobuf]\nlnt = true\n", root).is_err());
+}
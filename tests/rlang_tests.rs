@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use formatter::formats::rlang::{self, RlangOptions};
+
+fn fmt(path: &str, input: &str) -> String {
+    rlang::format(Path::new(path), input)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn spaces_operators_and_arguments() {
+    let input = "x<-c(1,2,3)\ny = x^2+ -1\nz <- pkg::fn(1:10, x$y, a=TRUE)\nif(!is.null(x)&&y) print ( \"yes\" )\n";
+    let expected = "x <- c(1, 2, 3)\ny = x^2 + -1\nz <- pkg::fn(1:10, x$y, a = TRUE)\nif (!is.null(x) && y) print(\"yes\")\n";
+    assert_eq!(fmt("a.R", input), expected);
+}
+
+#[test]
+fn indents_braces_and_keeps_else_on_closing_line() {
+    let input = "f<-function(a,b=2){\nif(a>b){\nreturn(a)\n}else{\nreturn(b)\n}\n}\n";
+    let expected = "f <- function(a, b = 2) {\n  if (a > b) {\n    return(a)\n  } else {\n    return(b)\n  }\n}\n";
+    assert_eq!(fmt("a.r", input), expected);
+}
+
+#[test]
+fn indents_pipelines_and_multiline_calls() {
+    let input = "result <- df%>%\nfilter(x>1)%>%\nmutate(\nz=x*2\n)|>\nsummarise(n=n())\n";
+    let expected = "result <- df %>%\n  filter(x > 1) %>%\n  mutate(\n    z = x * 2\n  ) |>\n  summarise(n = n())\n";
+    assert_eq!(fmt("a.r", input), expected);
+}
+
+#[test]
+fn keeps_comments_blank_lines_and_strings() {
+    let input = "#setup\nlibrary(dplyr) #load\n\n\n\n#' roxygen\nx <- r\"(a{b)\" ; y <- 'it\\'s'\nl <- list(a=1)[[\"a\"]]\nm <- x[1, ]\n";
+    let expected = "# setup\nlibrary(dplyr) # load\n\n#' roxygen\nx <- r\"(a{b)\"; y <- 'it\\'s'\nl <- list(a = 1)[[\"a\"]]\nm <- x[1, ]\n";
+    assert_eq!(fmt("a.r", input), expected);
+}
+
+#[test]
+fn wraps_long_calls_one_argument_per_line() {
+    let opts = RlangOptions {
+        line_width: 40,
+        ..RlangOptions::default()
+    };
+    let input = "plot_data(first_argument = 1, second = \"two\", third = c(1, 2))\n";
+    let expected = "plot_data(\n  first_argument = 1,\n  second = \"two\",\n  third = c(1, 2)\n)\n";
+    let out = rlang::format_with_options(Path::new("a.r"), input, &opts)
+        .unwrap()
+        .unwrap();
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn formats_only_r_chunks_in_rmarkdown() {
+    let input = "# Title\n\nSome  text x<-1\n\n```{r setup, echo=FALSE}\nx<-1\n```\n\n```{python}\nx=1\n```\n";
+    let expected = "# Title\n\nSome  text x<-1\n\n```{r setup, echo=FALSE}\nx <- 1\n```\n\n```{python}\nx=1\n```\n";
+    assert_eq!(fmt("report.Rmd", input), expected);
+}
+
+#[test]
+fn reports_unbalanced_brackets() {
+    let err = rlang::format(Path::new("a.r"), "f(x\n").unwrap_err();
+    assert!(err.to_string().contains("never closed"));
+}
+
+#[test]
+fn reports_unterminated_infix_operator_at_end_of_input() {
+    let err = rlang::format(Path::new("a.r"), "x %in").unwrap_err();
+    assert!(err.to_string().contains("unterminated %op%"), "{err}");
+    assert_eq!(fmt("a.r", "x%in%y"), "x %in% y\n");
+}
+
+#[test]
+fn keeps_tidy_eval_embracing_together() {
+    assert_eq!(
+        fmt(
+            "a.r",
+            "f <- function(df, col) {\n  summarise(df, m=mean({{ col }}))\n}\n"
+        ),
+        "f <- function(df, col) {\n  summarise(df, m = mean({{ col }}))\n}\n"
+    );
+    assert_eq!(
+        fmt("a.r", "g <- function(x) { { x } }\n"),
+        "g <- function(x) { { x } }\n"
+    );
+}
+
+#[test]
+fn operators_after_a_condition_or_parameters_are_prefix() {
+    assert_eq!(
+        fmt(
+            "a.r",
+            "y <- if (x>1) -1 else 2\nwhile (i<3) -i\nf <- function(x) -x\ng <- \\(x) !x\nz <- (a) - 1\n"
+        ),
+        "y <- if (x > 1) -1 else 2\nwhile (i < 3) -i\nf <- function(x) -x\ng <- \\(x) !x\nz <- (a) - 1\n"
+    );
+}
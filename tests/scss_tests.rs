@@ -0,0 +1,92 @@
+use std::path::Path;
+
+use formatter::formats::css::{self, ColorCase, CssOptions, Quotes, SortProperties};
+use formatter::formats::scss::{self, Syntax};
+use formatter::formats::{FormatKind, detect_kind};
+
+fn format(text: &str, syntax: Syntax) -> Option<String> {
+    scss::format_with_options(text, syntax, &CssOptions::default()).unwrap()
+}
+
+#[test]
+fn scss_formats_nesting_mixins_and_maps() {
+    let input = "@use \"sass:math\";\n$map: (\n  'a': 1,   // first\n     'b': (c: 2),\n);\n// Buttons\n%btn{padding:0}\n@mixin size($w,$h:$w){width:$w;height:$h}\n.btn,.link>a{\n  @extend %btn;\n  color:#FFF;   background:url(data:image/png;base64,AA==);\n  &:hover{color:darken($c,10%)}\n  &-primary{ @include size(10px) ; }\n  #{$prop}-top: 1px;\n  @if $a==1{a:b}\n  @else{c:d}\n  @media (max-width:map-get($breakpoints,small)){display:none}\n\n\n  margin:0 // trailing\n}\n";
+    let expected = "@use \"sass:math\";\n$map: (\n    'a': 1, // first\n    'b': (c: 2),\n);\n// Buttons\n%btn {\n    padding: 0;\n}\n@mixin size($w, $h:$w) {\n    width: $w;\n    height: $h;\n}\n.btn,\n.link > a {\n    @extend %btn;\n    color: #FFF;\n    background: url(data:image/png;base64,AA==);\n    &:hover {\n        color: darken($c, 10%);\n    }\n    &-primary {\n        @include size(10px);\n    }\n    #{$prop}-top: 1px;\n    @if $a==1 {\n        a: b;\n    } @else {\n        c: d;\n    }\n    @media (max-width: map-get($breakpoints, small)) {\n        display: none;\n    }\n\n    margin: 0; // trailing\n}\n";
+    assert_eq!(format(input, Syntax::Scss).as_deref(), Some(expected));
+    assert_eq!(format(expected, Syntax::Scss), None);
+
+    let err = scss::format_with_options(
+        ".a {\n  color: red;\n",
+        Syntax::Scss,
+        &CssOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "line 3: unclosed block");
+}
+
+#[test]
+fn less_keeps_guards_mixin_calls_and_detached_rulesets() {
+    let input = "@color:#ABC;\n.mixin(@a;@b:2) when (iscolor(@a)){color:@a}\n.box{.mixin(#fff;1);&:extend(.a all);\n@media @phone{width:~\"calc(100% - @{w})\"}\n.@{name}-x{color:@color}\n@detached:{background:red};\n@detached();\n}\n";
+    let expected = "@color: #ABC;\n.mixin(@a;@b:2) when (iscolor(@a)) {\n    color: @a;\n}\n.box {\n    .mixin(#fff;1);\n    &:extend(.a all);\n    @media @phone {\n        width: ~\"calc(100% - @{w})\";\n    }\n    .@{name}-x {\n        color: @color;\n    }\n    @detached: {\n        background: red;\n    }\n    @detached();\n}\n";
+    assert_eq!(format(input, Syntax::Less).as_deref(), Some(expected));
+    assert_eq!(format(expected, Syntax::Less), None);
+}
+
+#[test]
+fn sass_reindents_by_nesting() {
+    let input = "// Comment\n  continues here\n$c:   red\n=m($a)\n  color:$a\n.a,.b\n  color :$c\n  +m(blue)\n\n\n  &:hover\n    background:#FFF\n";
+    let expected = "// Comment\n  continues here\n$c: red\n=m($a)\n    color: $a\n.a, .b\n    color: $c\n    +m(blue)\n\n    &:hover\n        background: #FFF\n";
+    assert_eq!(format(input, Syntax::Sass).as_deref(), Some(expected));
+    assert_eq!(format(expected, Syntax::Sass), None);
+}
+
+#[test]
+fn css_options_sort_properties_and_normalize_colors_and_quotes() {
+    let opts = CssOptions {
+        sort_properties: SortProperties::Grouped,
+        color_case: ColorCase::Lower,
+        quotes: Quotes::Single,
+        ..CssOptions::default()
+    };
+    let input = ".a{color:#FFF;z-index:1;-webkit-transition:none;transition:none;display:block;position:absolute;content:\"x\"}\n";
+    let expected = ".a {\n    position: absolute;\n    z-index: 1;\n    display: block;\n    color: #fff;\n    content: 'x';\n    -webkit-transition: none;\n    transition: none;\n}\n";
+    let out = css::format_with_options(Path::new("a.css"), input, &opts).unwrap();
+    assert_eq!(out.as_deref(), Some(expected));
+    assert_eq!(
+        css::format_with_options(Path::new("a.css"), expected, &opts).unwrap(),
+        None
+    );
+
+    // Blank lines, variables and nested rules end a sorted run.
+    let opts = CssOptions {
+        sort_properties: SortProperties::Alphabetical,
+        ..CssOptions::default()
+    };
+    let input = ".a {\n    width: 1px;\n    color: red;\n\n    margin: 0;\n    $x: 1;\n    border: 0;\n    align-items: center;\n    &:hover {\n        z-index: 1;\n        top: 0;\n    }\n}\n";
+    let expected = ".a {\n    color: red;\n    width: 1px;\n\n    margin: 0;\n    $x: 1;\n    align-items: center;\n    border: 0;\n    &:hover {\n        top: 0;\n        z-index: 1;\n    }\n}\n";
+    let out = scss::format_with_options(input, Syntax::Scss, &opts).unwrap();
+    assert_eq!(out.as_deref(), Some(expected));
+
+    assert_eq!(detect_kind(Path::new("a.scss")), Some(FormatKind::Scss));
+    assert_eq!(detect_kind(Path::new("a.pcss")), Some(FormatKind::Scss));
+    assert_eq!(detect_kind(Path::new("a.sass")), Some(FormatKind::Sass));
+    assert_eq!(detect_kind(Path::new("a.less")), Some(FormatKind::Less));
+}
+
+#[test]
+fn scss_keeps_non_ascii_values() {
+    let input = "a { font-family: Yu ゴシック; content: \"url(é)\"; }\n";
+    let expected = "a {\n    font-family: Yu ゴシック;\n    content: \"url(é)\";\n}\n";
+    assert_eq!(format(input, Syntax::Scss).as_deref(), Some(expected));
+    assert_eq!(format(expected, Syntax::Scss), None);
+
+    let opts = CssOptions {
+        quotes: Quotes::Single,
+        ..CssOptions::default()
+    };
+    let out = css::format_with_options(Path::new("a.css"), input, &opts).unwrap();
+    assert_eq!(
+        out.as_deref(),
+        Some("a {\n    font-family: Yu ゴシック;\n    content: 'url(é)';\n}\n")
+    );
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use formatter::formats::html::HtmlOptions;
+use formatter::formats::sfc::{self, Framework};
+use formatter::formats::{FormatKind, detect_kind};
+
+fn format(name: &str, text: &str, framework: Framework) -> Option<String> {
+    sfc::format_with_options(Path::new(name), text, &HtmlOptions::default(), framework).unwrap()
+}
+
+#[test]
+fn vue_formats_template_script_and_style_sections() {
+    let input = "<template>\n<div class=\"app\"><h1>{{ title }}</h1>\n<template v-if=\"ok\"><p>yes</p></template>\n</div>\n</template>\n<script setup lang=\"ts\">\nimport {ref} from 'vue'\nconst title=ref<string>('hi')\n</script>\n\n\n<style scoped>\n.app{color:red}\n</style>\n<i18n lang=\"json\">\n{\"en\":  {\"hi\":\"Hi\"}}\n</i18n>\n";
+    let expected = "<template>\n  <div class=\"app\">\n    <h1>{{ title }}</h1>\n    <template v-if=\"ok\">\n      <p>yes</p>\n    </template>\n  </div>\n</template>\n\n<script setup lang=\"ts\">\nimport { ref } from \"vue\";\nconst title = ref<string>(\"hi\");\n</script>\n\n<style scoped>\n.app {\n    color: red;\n}\n</style>\n\n<i18n lang=\"json\">\n{\"en\":  {\"hi\":\"Hi\"}}\n</i18n>\n";
+    assert_eq!(
+        format("App.vue", input, Framework::Vue).as_deref(),
+        Some(expected)
+    );
+    assert_eq!(format("App.vue", expected, Framework::Vue), None);
+}
+
+#[test]
+fn svelte_indents_blocks_and_keeps_expression_attributes() {
+    let input = "<script>\n  let count=0\n</script>\n<h1>Count {count}</h1>\n{#if count>1}<p>many</p>{:else}<p>few</p>{/if}\n<button on:click={() => count++}>+</button>\n";
+    let expected = "<script>\nlet count = 0;\n</script>\n\n<h1>Count {count}</h1>\n{#if count>1}\n  <p>many</p>\n{:else}\n  <p>few</p>\n{/if}\n<button on:click={() => count++}>+</button>\n";
+    assert_eq!(
+        format("App.svelte", input, Framework::Svelte).as_deref(),
+        Some(expected)
+    );
+    assert_eq!(format("App.svelte", expected, Framework::Svelte), None);
+}
+
+#[test]
+fn astro_formats_frontmatter_as_typescript() {
+    let input = "---\nimport Layout from '../layouts/Layout.astro'\nconst {title}=Astro.props\n---\n<Layout title={title}><h1>{title}</h1></Layout>\n<script>\nconst x:number=1\n</script>\n";
+    let expected = "---\nimport Layout from \"../layouts/Layout.astro\";\nconst { title } = Astro.props;\n---\n\n<Layout title={title}>\n  <h1>{title}</h1>\n</Layout>\n\n<script>\nconst x: number = 1;\n</script>\n";
+    assert_eq!(
+        format("page.astro", input, Framework::Astro).as_deref(),
+        Some(expected)
+    );
+    assert_eq!(format("page.astro", expected, Framework::Astro), None);
+}
+
+#[test]
+fn sfc_formats_preprocessor_styles_and_detects_kinds() {
+    let styles = "<template>\n  <p>x</p>\n</template>\n\n<style lang=\"scss\">\n$w: 10px;\n.a { width: $w; }\n</style>\n\n<style lang=\"stylus\">\n.a\n  color red\n</style>\n";
+    let expected = "<template>\n  <p>x</p>\n</template>\n\n<style lang=\"scss\">\n$w: 10px;\n.a {\n    width: $w;\n}\n</style>\n\n<style lang=\"stylus\">\n.a\n  color red\n</style>\n";
+    assert_eq!(
+        format("App.vue", styles, Framework::Vue).as_deref(),
+        Some(expected)
+    );
+    let err = sfc::format_with_options(
+        Path::new("App.vue"),
+        "<script>\nlet = ;\n</script>\n",
+        &HtmlOptions::default(),
+        Framework::Vue,
+    )
+    .unwrap_err();
+    assert!(err.to_string().starts_with("<script> block:"), "{err}");
+
+    assert_eq!(detect_kind(Path::new("src/App.vue")), Some(FormatKind::Vue));
+    assert_eq!(
+        detect_kind(Path::new("Counter.svelte")),
+        Some(FormatKind::Svelte)
+    );
+    assert_eq!(
+        detect_kind(Path::new("index.astro")),
+        Some(FormatKind::Astro)
+    );
+}
+
+#[test]
+fn svelte_and_astro_allow_quotes_in_jsx_text() {
+    let input = "---\nconst show = true\n---\n<div>{show && <p>Don't panic</p>}</div>\n<ul>{items.map((i) => <li class=\"x\">{i < 2 ? 'a' : \"b\"}<br/></li>)}</ul>\n";
+    let expected = "---\nconst show = true;\n---\n\n<div>{show && <p>Don't panic</p>}</div>\n<ul>{items.map((i) => <li class=\"x\">{i < 2 ? 'a' : \"b\"}<br/></li>)}</ul>\n";
+    assert_eq!(
+        format("page.astro", input, Framework::Astro).as_deref(),
+        Some(expected)
+    );
+    assert_eq!(format("page.astro", expected, Framework::Astro), None);
+
+    let input = "<p>{ok ? <b>it's</b> : 'no'}</p>\n";
+    assert_eq!(format("App.svelte", input, Framework::Svelte), None);
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use formatter::formats::options::{self, Config};
+use formatter::formats::toml_fmt::{self, TomlOptions, WrapArrays};
+
+fn fmt_with(path: &str, input: &str, opts: &TomlOptions) -> String {
+    toml_fmt::format_with_options(Path::new(path), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+fn presets() -> TomlOptions {
+    TomlOptions {
+        presets: true,
+        ..TomlOptions::default()
+    }
+}
+
+#[test]
+fn cargo_preset_orders_package_and_sorts_dependencies() {
+    let input = "[dependencies]\nserde = { version = \"1\", features = [\"derive\"] }\n# async runtime\ntokio = \"1\"\nanyhow = \"1\"\n\n[package]\nedition = \"2021\"\ndescription = \"demo\"\nversion = \"0.1.0\"\nname = \"demo\"\n\n[dev-dependencies]\ntempfile = \"3\"\ninsta = \"1\"\n";
+    let expected = "[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2021\"\ndescription = \"demo\"\n\n[dependencies]\nanyhow = \"1\"\nserde = { version = \"1\", features = [\"derive\"] }\n# async runtime\ntokio = \"1\"\n\n[dev-dependencies]\ninsta = \"1\"\ntempfile = \"3\"\n";
+    let out = fmt_with("Cargo.toml", input, &presets());
+    assert_eq!(out, expected);
+    assert_eq!(fmt_with("Cargo.toml", &out, &presets()), out);
+}
+
+#[test]
+fn pyproject_preset_orders_tables_and_project_fields() {
+    let input = "[tool.ruff]\nline-length = 100\n\n[project]\ndependencies = [\"requests\"]\nversion = \"1.0\"\nname = \"demo\"\n\n[tool.black]\nline-length = 100\n\n[build-system]\nbuild-backend = \"hatchling.build\"\nrequires = [\"hatchling\"]\n";
+    let expected = "[build-system]\nrequires = [\"hatchling\"]\nbuild-backend = \"hatchling.build\"\n\n[project]\nname = \"demo\"\nversion = \"1.0\"\ndependencies = [\"requests\"]\n\n[tool.black]\nline-length = 100\n\n[tool.ruff]\nline-length = 100\n";
+    assert_eq!(fmt_with("pyproject.toml", input, &presets()), expected);
+}
+
+#[test]
+fn sorting_keeps_groups_and_array_tables() {
+    let opts = TomlOptions {
+        sort_keys: true,
+        sort_tables: true,
+        ..TomlOptions::default()
+    };
+    let input = "[z]\nb = 1\na = 2\n\nd = 3\nc = 4\n\n[[a]]\nname = \"x\"\n\n[a.sub]\nk = 1\n\n[[a]]\nname = \"y\"\n";
+    let expected = "[[a]]\nname = \"x\"\n\n[a.sub]\nk = 1\n\n[[a]]\nname = \"y\"\n\n[z]\na = 2\nb = 1\n\nc = 4\nd = 3\n";
+    assert_eq!(fmt_with("a.toml", input, &opts), expected);
+}
+
+#[test]
+fn aligns_entries_and_wraps_arrays() {
+    let opts = TomlOptions {
+        align_entries: true,
+        wrap_arrays: WrapArrays::Always,
+        ..TomlOptions::default()
+    };
+    let input = "[a]\nname = \"x\"\nlong-key = 1\nlist = [1, 2]\n\nother = true\n";
+    assert_eq!(
+        fmt_with("a.toml", input, &opts),
+        "[a]\nname     = \"x\"\nlong-key = 1\nlist = [\n  1,\n  2,\n]\n\nother = true\n"
+    );
+
+    let opts = TomlOptions {
+        wrap_arrays: WrapArrays::Never,
+        ..TomlOptions::default()
+    };
+    let input = "a = [\n  1,\n  2,\n]\nb = [\n  1, # one\n]\n";
+    assert_eq!(
+        fmt_with("a.toml", input, &opts),
+        "a = [1, 2]\nb = [\n  1, # one\n]\n"
+    );
+}
+
+#[test]
+fn default_options_are_unchanged() {
+    let input = "[package]\nversion = \"0.1.0\"\nname = \"demo\"\n";
+    assert_eq!(
+        fmt_with("Cargo.toml", input, &TomlOptions::default()),
+        "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n"
+    );
+    let input = "b = 1\na = 2\n";
+    assert_eq!(fmt_with("a.toml", input, &TomlOptions::default()), input);
+}
+
+#[test]
+fn check_reports_out_of_order_keys() {
+    options::install(Config::parse("[toml]\npresets = true\n", PathBuf::from("/repo")).unwrap());
+    let input = "[package]\nversion = \"0.1.0\"\nname = \"demo\"\n\n[dependencies]\nserde = \"1\"\nanyhow = \"1\"\n";
+    assert_eq!(
+        toml_fmt::out_of_order(Path::new("/repo/Cargo.toml"), input),
+        [
+            "[package]: \"name\" should come before \"version\"",
+            "[dependencies]: \"anyhow\" should come before \"serde\"",
+        ]
+    );
+    assert!(toml_fmt::out_of_order(Path::new("/repo/other.toml"), input).is_empty());
+}
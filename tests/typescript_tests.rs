@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+
+use formatter::formats::typescript::{
+    self, ArrowParens, Quotes, Semicolons, TrailingCommas, TypeScriptOptions,
+};
+
+fn format(name: &str, text: &str, opts: &TypeScriptOptions) -> Option<String> {
+    typescript::format_with_options(Path::new(name), text, opts).unwrap()
+}
+
+#[test]
+fn typescript_options_configure_dprint() {
+    let opts = TypeScriptOptions {
+        indent: Some(4),
+        quotes: Some(Quotes::Single),
+        semicolons: Some(Semicolons::Asi),
+        trailing_commas: Some(TrailingCommas::Never),
+        arrow_parens: Some(ArrowParens::Avoid),
+        ..TypeScriptOptions::default()
+    };
+    let input = "const f = (x) => {\n  return [\n    \"a\",\n    x,\n  ];\n};\n";
+    let expected = "const f = x => {\n    return [\n        'a',\n        x\n    ]\n}\n";
+    assert_eq!(format("a.ts", input, &opts).as_deref(), Some(expected));
+    assert_eq!(format("a.ts", expected, &opts), None);
+
+    // Nothing set keeps dprint's defaults.
+    assert_eq!(
+        format("a.js", "const a = 'x'\n", &TypeScriptOptions::default()).as_deref(),
+        Some("const a = \"x\";\n")
+    );
+}
+
+#[test]
+fn typescript_reads_prettierrc_and_dprint_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let prettier = dir.path().join("web");
+    let nested = prettier.join("src");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(
+        prettier.join(".prettierrc"),
+        "{ \"singleQuote\": true, \"semi\": false, \"tabWidth\": 4 }",
+    )
+    .unwrap();
+    let file = nested.join("a.ts");
+    let out = typescript::format(&file, "if (a) {\n  b(\"x\");\n}\n").unwrap();
+    assert_eq!(out.as_deref(), Some("if (a) {\n    b('x')\n}\n"));
+
+    let yaml = dir.path().join("yaml");
+    fs::create_dir_all(&yaml).unwrap();
+    fs::write(yaml.join(".prettierrc.yaml"), "singleQuote: true # yes\n").unwrap();
+    let out = typescript::format(&yaml.join("a.js"), "b(\"x\");\n").unwrap();
+    assert_eq!(out.as_deref(), Some("b('x');\n"));
+    // Keys it leaves out take Prettier's defaults, such as an 80 column width.
+    let long =
+        "someFunction(firstArgument, secondArgument, thirdArgument, fourthArgumentNumber);\n";
+    assert!((81..=120).contains(&long.trim_end().len()));
+    let out = typescript::format(&yaml.join("b.js"), long).unwrap();
+    assert_eq!(
+        out.as_deref(),
+        Some(
+            "someFunction(\n  firstArgument,\n  secondArgument,\n  thirdArgument,\n  fourthArgumentNumber,\n);\n"
+        )
+    );
+
+    let dprint = dir.path().join("dprint");
+    fs::create_dir_all(&dprint).unwrap();
+    fs::write(
+        dprint.join("dprint.json"),
+        "{\n  // shared\n  \"indentWidth\": 3,\n  \"typescript\": { \"quoteStyle\": \"alwaysSingle\", \"semiColons\": \"asi\" }\n}\n",
+    )
+    .unwrap();
+    let out = typescript::format(&dprint.join("a.ts"), "if (a) {\n  b(\"x\");\n}\n").unwrap();
+    assert_eq!(out.as_deref(), Some("if (a) {\n   b('x')\n}\n"));
+}
+
+#[test]
+fn organize_imports_groups_sorts_and_merges() {
+    let opts = TypeScriptOptions {
+        organize_imports: true,
+        ..TypeScriptOptions::default()
+    };
+    let input = "\"use client\";\n// Header\n\nimport { b } from \"./b\";\nimport React from \"react\";\nimport { readFile } from \"node:fs/promises\";\nimport { Button } from \"@/components/button\";\nimport { useState } from \"react\";\nimport type { Props } from \"./types\";\nimport path from \"path\";\n// Axios client\nimport axios from \"axios\";\nimport \"./polyfill\";\nimport { z } from \"zod\";\nimport { a } from \"../a\";\n\nconst x = 1;\n";
+    let expected = "\"use client\";\n// Header\n\nimport { readFile } from \"node:fs/promises\";\nimport path from \"path\";\n\n// Axios client\nimport axios from \"axios\";\nimport React, { useState } from \"react\";\n\nimport { Button } from \"@/components/button\";\n\nimport { b } from \"./b\";\nimport type { Props } from \"./types\";\n\nimport \"./polyfill\";\n\nimport { z } from \"zod\";\n\nimport { a } from \"../a\";\n\nconst x = 1;\n";
+    assert_eq!(format("a.tsx", input, &opts).as_deref(), Some(expected));
+    assert_eq!(format("a.tsx", expected, &opts), None);
+
+    // Merged imports that no longer fit are wrapped by dprint.
+    let opts = TypeScriptOptions {
+        line_width: Some(40),
+        ..opts
+    };
+    let input = "import { alpha } from \"module\";\nimport { beta, gamma } from \"module\";\n\nalpha(beta, gamma);\n";
+    let expected =
+        "import {\n  alpha,\n  beta,\n  gamma,\n} from \"module\";\n\nalpha(beta, gamma);\n";
+    assert_eq!(format("a.ts", input, &opts).as_deref(), Some(expected));
+    assert_eq!(format("a.ts", expected, &opts), None);
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use formatter::formats::xml::{self, XmlOptions};
+use formatter::formats::{FormatKind, detect_kind};
+
+fn fmt(input: &str) -> String {
+    fmt_with(input, &XmlOptions::default())
+}
+
+fn fmt_with(input: &str, opts: &XmlOptions) -> String {
+    xml::format_with_options(Path::new("a.xml"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn keeps_prolog_exactly() {
+    let input = "<?xml version=\"1.0\"  encoding=\"UTF-8\"?>\n<!DOCTYPE note SYSTEM \"note.dtd\">\n<?xml-stylesheet type=\"text/xsl\" href=\"s.xsl\"?>\n<note><to>A</to></note>";
+    let expected = "<?xml version=\"1.0\"  encoding=\"UTF-8\"?>\n<!DOCTYPE note SYSTEM \"note.dtd\">\n<?xml-stylesheet type=\"text/xsl\" href=\"s.xsl\"?>\n<note>\n  <to>A</to>\n</note>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn mixed_content_and_preserved_whitespace_are_untouched() {
+    let input = "<doc><p>text <b>bold</b>   more</p><code xml:space=\"preserve\">  a\n    <i>b</i>  </code><data><![CDATA[ <raw> ]]></data></doc>";
+    let expected = "<doc>\n  <p>text <b>bold</b>   more</p>\n  <code xml:space=\"preserve\">  a\n    <i>b</i>  </code>\n  <data><![CDATA[ <raw> ]]></data>\n</doc>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn comments_blank_lines_and_empty_elements() {
+    let input = "<root>\n<!-- first -->\n<a/>\n\n\n<b />\n<c>  </c>\n\n</root>\n";
+    let expected = "<root>\n  <!-- first -->\n  <a/>\n\n  <b />\n  <c></c>\n</root>\n";
+    assert_eq!(fmt(input), expected);
+}
+
+#[test]
+fn wraps_wide_start_tags_and_uses_tabs() {
+    let opts = XmlOptions {
+        use_tabs: true,
+        line_width: 30,
+        ..XmlOptions::default()
+    };
+    let input = "<r><item id='1' name=\"long-name\" enabled=\"true\"><v>1</v></item></r>";
+    let expected = "<r>\n\t<item\n\t\tid=\"1\"\n\t\tname=\"long-name\"\n\t\tenabled=\"true\">\n\t\t<v>1</v>\n\t</item>\n</r>\n";
+    assert_eq!(fmt_with(input, &opts), expected);
+}
+
+#[test]
+fn keeps_byte_order_mark() {
+    let input = "\u{feff}<?xml version=\"1.0\"?>\n<a><b>x</b></a>\n";
+    let expected = "\u{feff}<?xml version=\"1.0\"?>\n<a>\n  <b>x</b>\n</a>\n";
+    assert_eq!(fmt(input), expected);
+    assert_eq!(xml::format(Path::new("a.xml"), expected).unwrap(), None);
+}
+
+#[test]
+fn reports_malformed_documents() {
+    assert!(xml::format(Path::new("a.xml"), "<a><b></a>").is_err());
+    assert!(xml::format(Path::new("a.xml"), "<a><b></b>").is_err());
+}
+
+fn fmt_path(path: &str, input: &str, opts: &XmlOptions) -> String {
+    xml::format_with_options(Path::new(path), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn pom_elements_follow_the_recommended_order() {
+    let input = "<project>\n  <dependencies>\n    <!-- tests -->\n    <dependency><scope>test</scope><artifactId>junit</artifactId><groupId>junit</groupId></dependency>\n  </dependencies>\n  <version>1.0</version>\n  <artifactId>demo</artifactId>\n  <groupId>com.example</groupId>\n  <modelVersion>4.0.0</modelVersion>\n</project>\n";
+    let expected = "<project>\n  <modelVersion>4.0.0</modelVersion>\n  <groupId>com.example</groupId>\n  <artifactId>demo</artifactId>\n  <version>1.0</version>\n  <dependencies>\n    <!-- tests -->\n    <dependency>\n      <groupId>junit</groupId>\n      <artifactId>junit</artifactId>\n      <scope>test</scope>\n    </dependency>\n  </dependencies>\n</project>\n";
+    let opts = XmlOptions {
+        pom_order: true,
+        ..XmlOptions::default()
+    };
+    assert_eq!(fmt_path("pom.xml", input, &opts), expected);
+    // Only pom.xml is reordered, and only when asked.
+    assert_eq!(fmt_path("other.xml", expected, &opts), expected);
+    let kept = fmt_path("pom.xml", input, &XmlOptions::default());
+    assert!(kept.starts_with("<project>\n  <dependencies>\n"), "{kept}");
+}
+
+#[test]
+fn android_attributes_use_android_studio_order() {
+    let input = "<LinearLayout android:orientation=\"vertical\" android:layout_height=\"wrap_content\" android:layout_width=\"match_parent\" xmlns:android=\"http://schemas.android.com/apk/res/android\">\n  <TextView android:text=\"hi\" android:layout_margin=\"4dp\" style=\"@style/X\" android:id=\"@+id/t\"/>\n</LinearLayout>\n";
+    let expected = "<LinearLayout\n  xmlns:android=\"http://schemas.android.com/apk/res/android\"\n  android:layout_width=\"match_parent\"\n  android:layout_height=\"wrap_content\"\n  android:orientation=\"vertical\">\n  <TextView android:id=\"@+id/t\" style=\"@style/X\" android:layout_margin=\"4dp\" android:text=\"hi\"/>\n</LinearLayout>\n";
+    let opts = XmlOptions {
+        android_attribute_order: true,
+        ..XmlOptions::default()
+    };
+    assert_eq!(fmt_path("res/layout/main.xml", input, &opts), expected);
+    let kept = fmt_path("res/layout/main.xml", input, &XmlOptions::default());
+    assert!(
+        kept.starts_with("<LinearLayout\n  android:orientation=\"vertical\"\n"),
+        "{kept}"
+    );
+}
+
+#[test]
+fn plist_keeps_apple_layout_and_svg_paths_are_optional() {
+    let input = "<plist version=\"1.0\">\n  <dict>\n    <key>Items</key><array><true/></array>\n  </dict>\n</plist>\n";
+    let expected = "<plist version=\"1.0\">\n<dict>\n\t<key>Items</key>\n\t<array>\n\t\t<true/>\n\t</array>\n</dict>\n</plist>\n";
+    assert_eq!(
+        fmt_path("Info.plist", input, &XmlOptions::default()),
+        expected
+    );
+
+    let svg = "<svg>\n  <path d=\"M 10,20 L30 , 40-5 a25 25 -30 0110 20z\"/>\n</svg>\n";
+    assert_eq!(fmt_path("icon.svg", svg, &XmlOptions::default()), svg);
+    let opts = XmlOptions {
+        normalize_svg_paths: true,
+        ..XmlOptions::default()
+    };
+    assert_eq!(
+        fmt_path("icon.svg", svg, &opts),
+        "<svg>\n  <path d=\"M10 20 L30 40 -5 a25 25 -30 0 1 10 20 z\"/>\n</svg>\n"
+    );
+}
+
+#[test]
+fn visual_studio_projects_with_a_bom_keep_their_content() {
+    let input = "\u{feff}<Project Sdk=\"Microsoft.NET.Sdk\">\r\n  <PropertyGroup><TargetFramework>net8.0</TargetFramework></PropertyGroup>\r\n  <ItemGroup>\r\n    <PackageReference Include=\"Serilog\" Version=\"3.1.1\" />\r\n  </ItemGroup>\r\n</Project>\r\n";
+    let expected = "\u{feff}<Project Sdk=\"Microsoft.NET.Sdk\">\n  <PropertyGroup>\n    <TargetFramework>net8.0</TargetFramework>\n  </PropertyGroup>\n  <ItemGroup>\n    <PackageReference Include=\"Serilog\" Version=\"3.1.1\" />\n  </ItemGroup>\n</Project>\n";
+    let out = fmt_path("App.csproj", input, &XmlOptions::default());
+    assert_eq!(out, expected);
+    assert_eq!(fmt_path("App.csproj", &out, &XmlOptions::default()), out);
+}
+
+#[test]
+fn detects_xml_dialect_extensions() {
+    for path in [
+        "icon.svg",
+        "schema.xsd",
+        "style.xsl",
+        "App.csproj",
+        "Directory.Build.props",
+        "Info.plist",
+        "MainWindow.xaml",
+        "Strings.resx",
+    ] {
+        assert_eq!(
+            detect_kind(Path::new(path)),
+            Some(FormatKind::Xml),
+            "{path}"
+        );
+    }
+}
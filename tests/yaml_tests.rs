@@ -1,4 +1,4 @@
-use formatter::formats::yaml;
+use formatter::formats::yaml::{self, DocumentStart, YamlOptions, YamlQuotes};
 use std::path::Path;
 
 fn run(input: &str) -> String {
@@ -7,6 +7,12 @@ fn run(input: &str) -> String {
         .unwrap_or_else(|| input.to_string())
 }
 
+fn run_with(input: &str, opts: &YamlOptions) -> String {
+    yaml::format_with_options(Path::new("test.yaml"), input, opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
 #[test]
 fn adds_trailing_newline() {
     let input = "foo: 1";
@@ -43,3 +49,194 @@ fn detects_crlf_and_preserves() {
     let out = run(input);
     assert!(out.contains("\r\n"));
 }
+
+#[test]
+fn multi_document_streams_keep_markers_and_directives() {
+    let input = "%YAML 1.2\n---\na:   1\n...\n---\nb: 2\n--- !shape\n- !circle {x: 1,   y: 2}\n";
+    let expected = "%YAML 1.2\n---\na: 1\n...\n---\nb: 2\n---\n!shape\n- !circle { x: 1, y: 2 }\n";
+    let out = run(input);
+    assert_eq!(out, expected);
+    assert_eq!(run(&out), out);
+}
+
+#[test]
+fn anchors_aliases_and_merge_keys() {
+    let input =
+        "base: &base\n  name:   x\nderived:\n  <<: *base\n  extra: 1\nlist: [*base, &two 2]\n";
+    let expected =
+        "base: &base\n  name: x\nderived:\n  <<: *base\n  extra: 1\nlist: [*base, &two 2]\n";
+    assert_eq!(run(input), expected);
+}
+
+#[test]
+fn block_scalars_with_indicators_are_verbatim() {
+    let input = "script: |2-\n    indented two\n   one\nfolded: >+\n  keep\n\nnext: 1\n";
+    assert_eq!(run(input), input);
+}
+
+#[test]
+fn tags_are_kept() {
+    let input = "tagged: !!str 123\ncustom: !Ref   MyThing\nset: !!set {a, b}\n";
+    let expected = "tagged: !!str 123\ncustom: !Ref MyThing\nset: !!set { a, b }\n";
+    assert_eq!(run(input), expected);
+}
+
+#[test]
+fn indent_sequence_style_and_quotes_are_configurable() {
+    let opts = YamlOptions {
+        indent: 4,
+        indent_sequences: false,
+        quotes: YamlQuotes::PreferSingle,
+        ..YamlOptions::default()
+    };
+    let input = "a:\n  b:\n    - \"x\"\n    - \"it's\"\n";
+    let expected = "a:\n    b:\n    - 'x'\n    - \"it's\"\n";
+    assert_eq!(run_with(input, &opts), expected);
+}
+
+#[test]
+fn document_start_and_trailing_whitespace() {
+    let always = YamlOptions {
+        document_start: DocumentStart::Always,
+        ..YamlOptions::default()
+    };
+    assert_eq!(run_with("# c\na: 1\n", &always), "---\n# c\na: 1\n");
+    assert_eq!(
+        run_with("%YAML 1.2\n---\na: 1\n", &always),
+        "%YAML 1.2\n---\na: 1\n"
+    );
+
+    let never = YamlOptions {
+        document_start: DocumentStart::Never,
+        trim_trailing_whitespace: true,
+        ..YamlOptions::default()
+    };
+    assert_eq!(
+        run_with("# c\n---\na: 1   \n---\nb: 2\n", &never),
+        "# c\na: 1\n---\nb: 2\n"
+    );
+}
+
+fn run_kubernetes(path: &str, input: &str) -> String {
+    let opts = YamlOptions {
+        kubernetes: true,
+        ..YamlOptions::default()
+    };
+    yaml::format_with_options(Path::new(path), input, &opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn kubernetes_objects_get_canonical_key_order_and_sorted_labels() {
+    let input = "# header\nspec:\n  template:\n    metadata:\n      labels:\n        tier: web\n        app: demo\nstatus: {}\nmetadata:\n  annotations:\n    zeta: \"1\"\n    # why\n    alpha: \"2\"\n  name: demo\nkind: Deployment\napiVersion: apps/v1\n---\nb: 1\na: 2\n";
+    let expected = "# header\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  annotations:\n    # why\n    alpha: \"2\"\n    zeta: \"1\"\n  name: demo\nspec:\n  template:\n    metadata:\n      labels:\n        app: demo\n        tier: web\nstatus: {}\n---\nb: 1\na: 2\n";
+    assert_eq!(run_kubernetes("deploy.yaml", input), expected);
+    // Off by default.
+    assert_eq!(
+        run("b: 1\nkind: X\napiVersion: v1\n"),
+        "b: 1\nkind: X\napiVersion: v1\n"
+    );
+}
+
+#[test]
+fn helm_templates_keep_go_template_actions() {
+    let input = "metadata:\n  name: {{ include \"demo.fullname\" . }}\n  labels:\n    {{- include \"demo.labels\" . | nindent 4 }}\n    zz:   last\nspec:\n  {{- if not .Values.autoscaling.enabled }}\n  replicas: {{ .Values.replicaCount }}\n  {{- end }}\n  containers:\n  - image: \"{{ .Values.image.repository }}:{{ .Values.image.tag }}\"\n    args: ['{{ include \"x\" . }}']\nkind: Deployment\napiVersion: apps/v1\n";
+    let expected = "apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: {{ include \"demo.fullname\" . }}\n  labels:\n    {{- include \"demo.labels\" . | nindent 4 }}\n    zz: last\nspec:\n  {{- if not .Values.autoscaling.enabled }}\n  replicas: {{ .Values.replicaCount }}\n  {{- end }}\n  containers:\n    - image: \"{{ .Values.image.repository }}:{{ .Values.image.tag }}\"\n      args: ['{{ include \"x\" . }}']\n";
+    let out = run_kubernetes("chart/templates/deployment.yaml", input);
+    assert_eq!(out, expected);
+    assert_eq!(run_kubernetes("chart/templates/deployment.yaml", &out), out);
+}
+
+#[test]
+fn helm_action_lines_between_keys_pin_their_order() {
+    let input =
+        "kind: ConfigMap\n{{- if .Values.extra }}\ndata:\n  b: 1\n{{- end }}\napiVersion: v1\n";
+    assert_eq!(run_kubernetes("chart/templates/cm.yaml", input), input);
+}
+
+fn run_workflow(path: &str, input: &str) -> String {
+    let opts = YamlOptions {
+        workflow_order: true,
+        ..YamlOptions::default()
+    };
+    yaml::format_with_options(Path::new(path), input, &opts)
+        .unwrap()
+        .unwrap_or_else(|| input.to_string())
+}
+
+#[test]
+fn github_workflows_get_canonical_order_and_formatted_scripts() {
+    let input = "jobs:\n  build:\n    steps:\n      # checkout first\n      - uses: actions/checkout@v4\n        name: Checkout\n      - run: |\n          if [ -n \"${{ github.sha }}\" ]; then\n          echo hi\n              fi\n        name: Test\n        env:\n          A: 1\n    runs-on: ubuntu-latest\n    name: Build\non: push\nname: CI\n";
+    let expected = "name: CI\non: push\njobs:\n  build:\n    name: Build\n    runs-on: ubuntu-latest\n    steps:\n      # checkout first\n      - name: Checkout\n        uses: actions/checkout@v4\n      - name: Test\n        run: |\n          if [ -n \"${{ github.sha }}\" ]; then\n            echo hi\n          fi\n        env:\n          A: 1\n";
+    let out = run_workflow(".github/workflows/ci.yml", input);
+    assert_eq!(out, expected);
+    assert_eq!(run_workflow(".github/workflows/ci.yml", &out), out);
+}
+
+#[test]
+fn run_scripts_are_formatted_without_reordering_by_default() {
+    let input = "on: push\nname: CI\njobs:\n  a:\n    steps:\n      - run: |\n          for f in *; do\n          echo $f\n          done\n\n      - run: echo  done\n";
+    let expected = "on: push\nname: CI\njobs:\n  a:\n    steps:\n      - run: |\n          for f in *; do\n            echo $f\n          done\n\n      - run: echo  done\n";
+    assert_eq!(
+        yaml::format(Path::new(".github/workflows/a.yaml"), input)
+            .unwrap()
+            .unwrap(),
+        expected
+    );
+    // Only workflow files are affected.
+    let plain = "steps:\n  - run: |\n      if true; then\n      echo\n      fi\n";
+    assert_eq!(run_workflow("ci.yml", plain), plain);
+}
+
+#[test]
+fn run_scripts_of_other_shells_are_left_alone() {
+    let input = "defaults:\n  run:\n    shell: bash\njobs:\n  lint:\n    runs-on: ubuntu-latest\n    steps:\n      - shell: pwsh\n        run: |\n          Get-ChildItem    -Path .\n      - name: Py\n        shell: python\n        run: |\n          print(  1 )\n      - run: |\n          if true; then\n          echo ok\n          fi\n  win:\n    runs-on: windows-latest\n    defaults:\n      run:\n        shell: pwsh\n    steps:\n      - run: |\n          Write-Host    hi\n";
+    let expected = input.replace(
+        "if true; then\n          echo ok",
+        "if true; then\n            echo ok",
+    );
+    assert_eq!(
+        yaml::format(Path::new(".github/workflows/a.yml"), input)
+            .unwrap()
+            .as_deref(),
+        Some(expected.as_str())
+    );
+
+    // Windows runners default to PowerShell.
+    let input = "jobs:\n  win:\n    runs-on: windows-latest\n    steps:\n      - run: |\n          Write-Host    hi\n      - shell: bash\n        run: |\n          echo   hi\n";
+    let expected = input.replace("echo   hi", "echo hi");
+    assert_eq!(
+        yaml::format(Path::new(".github/workflows/a.yml"), input)
+            .unwrap()
+            .as_deref(),
+        Some(expected.as_str())
+    );
+}
+
+#[test]
+fn run_scripts_bash_cannot_parse_are_left_alone() {
+    let input = "jobs:\n  a:\n    steps:\n      - run: |\n          if ($x) {\n            Write-Host  \"x\"\n          } else {  exit 1 }\n      - run: |\n          if true; then\n          echo ok\n          fi\n";
+    let expected = "jobs:\n  a:\n    steps:\n      - run: |\n          if ($x) {\n            Write-Host  \"x\"\n          } else {  exit 1 }\n      - run: |\n          if true; then\n            echo ok\n          fi\n";
+    assert_eq!(
+        yaml::format(Path::new(".github/workflows/a.yml"), input)
+            .unwrap()
+            .as_deref(),
+        Some(expected)
+    );
+}
+
+#[test]
+fn gitlab_ci_and_compose_files_are_ordered() {
+    let gitlab =
+        "test:\n  script:\n    - make test\n  stage: test\n  image: rust\nstages: [test]\n";
+    assert_eq!(
+        run_workflow(".gitlab-ci.yml", gitlab),
+        "stages: [test]\ntest:\n  stage: test\n  image: rust\n  script:\n    - make test\n"
+    );
+    let compose = "services:\n  web:\n    ports: [\"80:80\"]\n    image: nginx\nname: demo\n";
+    assert_eq!(
+        run_workflow("docker-compose.yml", compose),
+        "name: demo\nservices:\n  web:\n    image: nginx\n    ports: [\"80:80\"]\n"
+    );
+}